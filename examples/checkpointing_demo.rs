@@ -133,6 +133,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             Message::StreamEvent { event, .. } => {
                                 tracing::trace!(event = %event, "Stream event");
                             }
+                            Message::Unknown { raw } => {
+                                tracing::debug!(raw = %raw, "Unknown message");
+                            }
                         }
                     }
                     Err(e) => {
@@ -153,6 +153,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let task_message = Message::Assistant {
         parent_tool_use_id: None,
         message: anthropic_agent_sdk::types::AssistantMessageContent {
+            id: None,
             model: "claude-sonnet-4-20250514".to_string(),
             content: vec![ContentBlock::ToolUse {
                 id: "toolu_abc123".to_string(),
@@ -163,6 +164,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     "subagent_type": "general-purpose"
                 }),
             }],
+            usage: None,
         },
         session_id: None,
     };
@@ -188,6 +190,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
         session_id: None,
         uuid: None,
+        tool_use_result: None,
     };
 
     // Process the result - should trigger SubagentStop hook
@@ -274,6 +277,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     Message::StreamEvent { .. } => {
                                         trace!("Stream event");
                                     }
+                                    Message::Unknown { .. } => {
+                                        trace!("Unknown message");
+                                    }
                                 }
 
                                 // Process through our hook manager
@@ -38,6 +38,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             decision: None, // Allow the operation
             system_message: Some("Hook logged the event".to_string()),
             hook_specific_output: None,
+            ..Default::default()
         })
     });
 
@@ -106,6 +107,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 decision: Some(HookDecision::Block),
                 system_message: Some(format!("Tool {tool} was blocked by security policy")),
                 hook_specific_output: None,
+                ..Default::default()
             });
         }
 
@@ -114,6 +116,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             decision: None,
             system_message: None,
             hook_specific_output: None,
+            ..Default::default()
         })
     });
 
@@ -186,6 +189,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     "approved": true
                 }
             })),
+            ..Default::default()
         })
     });
 
@@ -211,6 +211,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             state.accumulated
                         )
                     })),
+                    ..Default::default()
                 });
             }
 
@@ -0,0 +1,85 @@
+//! Partial messages demo
+//!
+//! Streams a response with `include_partial_messages` and prints text as it
+//! arrives, using typed stream events and `PartialMessageAccumulator`:
+//! - `Message::stream_event()` parses the raw event
+//! - text deltas are printed as they stream
+//! - tool call input JSON is shown while it is being generated
+//! - the running token count comes from `ClaudeSDKClient::current_usage()`
+//!
+//! Run with: cargo run --example partial_messages_demo
+
+use std::io::Write;
+
+use anthropic_agent_sdk::types::{ContentBlock, ContentDelta, StreamEvent};
+use anthropic_agent_sdk::{
+    ClaudeAgentOptions, ClaudeSDKClient, Message, PartialMessageAccumulator,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("Partial Messages Demo");
+    println!("=====================\n");
+
+    let options = ClaudeAgentOptions::builder()
+        .include_partial_messages(true)
+        .allowed_tools(vec!["Glob".into()])
+        .max_turns(3)
+        .build();
+
+    let mut client = ClaudeSDKClient::new(options, None).await?;
+    client
+        .send_message("List the Rust files in src/ with Glob, then describe them in two sentences.")
+        .await?;
+
+    let mut partial = PartialMessageAccumulator::new();
+    while let Some(message) = client.next_message().await {
+        let message = message?;
+        partial.observe(&message);
+
+        match message.stream_event() {
+            Some(StreamEvent::ContentBlockDelta {
+                delta: ContentDelta::TextDelta { text },
+                ..
+            }) => {
+                print!("{text}");
+                std::io::stdout().flush()?;
+            }
+            Some(StreamEvent::ContentBlockDelta {
+                delta: ContentDelta::InputJsonDelta { .. },
+                ..
+            }) => {
+                // Show the tool input JSON generated so far
+                if let Some(ContentBlock::ToolUse { id, name, .. }) =
+                    partial.message().and_then(|m| m.content.last())
+                {
+                    if let Some(json) = partial.partial_tool_input(id) {
+                        println!("\n[{name}] input so far: {json}");
+                    }
+                }
+            }
+            Some(StreamEvent::MessageStop) => {
+                let usage = client.current_usage();
+                println!(
+                    "\n[message complete: stop_reason={:?}, {} tokens so far]",
+                    partial.stop_reason(),
+                    usage.total_tokens()
+                );
+            }
+            _ => {}
+        }
+
+        if let Message::Result { usage, .. } = message {
+            if let Some(usage) = usage {
+                println!(
+                    "\nTurn usage: {} input, {} output, {} cache read",
+                    usage.input_tokens, usage.output_tokens, usage.cache_read_input_tokens
+                );
+            }
+            break;
+        }
+    }
+
+    client.close().await?;
+    Ok(())
+}
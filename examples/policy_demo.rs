@@ -0,0 +1,59 @@
+//! Policy Demo
+//!
+//! Demonstrates declarative hook and permission rules loaded from
+//! `examples/policy.toml`. Edit the file while the demo runs: the policy is
+//! reloaded without restarting.
+//!
+//! Run with: cargo run --example `policy_demo` --features policy
+
+use anthropic_agent_sdk::policy::PolicyEngine;
+use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient, ContentBlock, Message};
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "info".parse().unwrap()),
+        )
+        .init();
+
+    println!("Policy Demo");
+    println!("===========\n");
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/policy.toml");
+    let engine = PolicyEngine::load(path).await?;
+    println!("Loaded {engine:?}\n");
+
+    // Reload the policy whenever the file changes
+    let _watcher = engine.watch(Duration::from_secs(1))?;
+
+    let options = ClaudeAgentOptions::builder()
+        .hooks(engine.hooks())
+        .can_use_tool(engine.can_use_tool())
+        .max_turns(3_u32)
+        .build();
+
+    let mut client = ClaudeSDKClient::new(options, None).await?;
+    client
+        .send_message("Run `rm -rf ./build` with the Bash tool, then tell me what happened.")
+        .await?;
+
+    while let Some(message) = client.next_message().await {
+        match message? {
+            Message::Assistant { message, .. } => {
+                for block in &message.content {
+                    if let ContentBlock::Text { text } = block {
+                        println!("[Assistant] {text}");
+                    }
+                }
+            }
+            Message::Result { .. } => break,
+            _ => {}
+        }
+    }
+
+    client.close().await?;
+    Ok(())
+}
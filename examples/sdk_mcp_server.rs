@@ -0,0 +1,131 @@
+//! In-process SDK MCP Server Example
+//!
+//! This example registers an rmcp tool server directly on `ClaudeAgentOptions`.
+//! It shows:
+//! 1. Wrapping an `rmcp::ServerHandler` in `RmcpSdkServer`
+//! 2. Registering it under a name with `sdk_mcp_servers`
+//! 3. Claude calling the tools, which read application state in-process
+//!
+//! Unlike `mcp_integration`, no server binary is spawned: the CLI sends the
+//! MCP traffic over the control channel and the SDK answers it.
+//!
+//! Run with: cargo run --example `sdk_mcp_server` --features rmcp
+
+#[cfg(feature = "rmcp")]
+mod app {
+    use anthropic_agent_sdk::mcp::{
+        Parameters, RmcpSdkServer, SdkMcpServer, ServerCapabilities, ServerHandler, ServerInfo,
+        ToolRouter, tool, tool_handler, tool_router,
+    };
+    use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient, ContentBlock, Message};
+    use futures::StreamExt;
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Deserialize, JsonSchema)]
+    pub struct SkuParams {
+        /// Product SKU, e.g. "A-100"
+        pub sku: String,
+    }
+
+    /// Inventory tools backed by the application's own state
+    #[derive(Clone)]
+    pub struct Inventory {
+        stock: Arc<Mutex<HashMap<String, u32>>>,
+        tool_router: ToolRouter<Self>,
+    }
+
+    #[tool_router]
+    impl Inventory {
+        pub fn new(stock: Arc<Mutex<HashMap<String, u32>>>) -> Self {
+            Self {
+                stock,
+                tool_router: Self::tool_router(),
+            }
+        }
+
+        #[tool(description = "Get the number of units in stock for a SKU")]
+        fn stock_level(&self, Parameters(params): Parameters<SkuParams>) -> String {
+            let stock = self.stock.lock().expect("stock lock poisoned");
+            match stock.get(&params.sku) {
+                Some(units) => format!("{}: {units} units", params.sku),
+                None => format!("{}: unknown SKU", params.sku),
+            }
+        }
+    }
+
+    #[tool_handler]
+    impl ServerHandler for Inventory {
+        fn get_info(&self) -> ServerInfo {
+            ServerInfo {
+                capabilities: ServerCapabilities::builder().enable_tools().build(),
+                ..Default::default()
+            }
+        }
+    }
+
+    pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+        println!("=== In-process SDK MCP Server Example ===\n");
+
+        // Application state shared with the tool server
+        let stock = Arc::new(Mutex::new(HashMap::from([
+            ("A-100".to_string(), 12),
+            ("B-200".to_string(), 0),
+        ])));
+
+        let mut servers: HashMap<String, Arc<dyn SdkMcpServer>> = HashMap::new();
+        servers.insert(
+            "inventory".to_string(),
+            Arc::new(RmcpSdkServer::new(Inventory::new(stock))),
+        );
+
+        let options = ClaudeAgentOptions::builder()
+            .sdk_mcp_servers(servers)
+            .allowed_tools(vec!["mcp__inventory__stock_level".into()])
+            .max_turns(4)
+            .build();
+
+        let mut client = ClaudeSDKClient::new(options, None).await?;
+        client
+            .send_message(
+                "How many units of SKU A-100 and B-200 are in stock? Use the inventory tool.",
+            )
+            .await?;
+
+        let mut messages = Box::pin(client.receive_response());
+        while let Some(message) = messages.next().await {
+            match message? {
+                Message::Assistant { message, .. } => {
+                    for block in message.content {
+                        match block {
+                            ContentBlock::Text { text } => println!("Claude: {text}"),
+                            ContentBlock::ToolUse { name, input, .. } => {
+                                println!("Tool call: {name} {input}");
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Message::Result { .. } => println!("\nDone."),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rmcp")]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    app::run().await
+}
+
+#[cfg(not(feature = "rmcp"))]
+fn main() {
+    eprintln!("This example requires the 'rmcp' feature.");
+    eprintln!("Run with: cargo run --example sdk_mcp_server --features rmcp");
+    std::process::exit(1);
+}
@@ -61,10 +61,15 @@
 //! }
 //! ```
 
+use std::marker::PhantomData;
+
 use async_trait::async_trait;
 
-use crate::error::Result;
-use crate::types::{HookContext, HookOutput, PermissionResult, ToolPermissionContext};
+use crate::error::{ClaudeError, Result};
+use crate::types::{
+    HookContext, HookEventInput, HookOutput, IntoHookOutput, PermissionResult,
+    ToolPermissionContext,
+};
 
 // ============================================================================
 // Hook Callback Trait
@@ -333,6 +338,90 @@ where
     }
 }
 
+/// Wrapper to convert a closure taking a typed hook input into a `HookCallback`.
+///
+/// The raw input is deserialized into `I` (e.g. `PreToolUseHookInput`) and the
+/// typed output is converted with [`IntoHookOutput`] for `I::EVENT`. Usually
+/// created through [`HookManager::typed_callback`](crate::hooks::HookManager::typed_callback).
+///
+/// # Example
+///
+/// ```no_run
+/// use anthropic_agent_sdk::callbacks::{HookCallback, TypedHookCallback};
+/// use anthropic_agent_sdk::types::{PreToolUseHookInput, PreToolUseHookOutput};
+/// use std::sync::Arc;
+///
+/// let callback = TypedHookCallback::new(|input: PreToolUseHookInput, _ctx| {
+///     Box::pin(async move {
+///         if input.tool_name == "Bash" {
+///             return Ok(PreToolUseHookOutput::ask("Shell commands need review"));
+///         }
+///         Ok(PreToolUseHookOutput::default())
+///     })
+/// });
+///
+/// let shared: Arc<dyn HookCallback> = Arc::new(callback);
+/// ```
+pub struct TypedHookCallback<I, F>
+where
+    I: HookEventInput,
+    F: Fn(
+            I,
+            HookContext,
+        )
+            -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<I::Output>> + Send>>
+        + Send
+        + Sync,
+{
+    func: F,
+    _input: PhantomData<fn(I)>,
+}
+
+impl<I, F> TypedHookCallback<I, F>
+where
+    I: HookEventInput,
+    F: Fn(
+            I,
+            HookContext,
+        )
+            -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<I::Output>> + Send>>
+        + Send
+        + Sync,
+{
+    /// Create a new typed hook callback.
+    pub fn new(func: F) -> Self {
+        Self {
+            func,
+            _input: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<I, F> HookCallback for TypedHookCallback<I, F>
+where
+    I: HookEventInput,
+    F: Fn(
+            I,
+            HookContext,
+        )
+            -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<I::Output>> + Send>>
+        + Send
+        + Sync,
+{
+    async fn call(
+        &self,
+        input: serde_json::Value,
+        _tool_use_id: Option<String>,
+        context: HookContext,
+    ) -> Result<HookOutput> {
+        let input: I = serde_json::from_value(input)
+            .map_err(|e| ClaudeError::hook(format!("Invalid {:?} hook input: {e}", I::EVENT)))?;
+        let output = (self.func)(input, context).await?;
+        Ok(output.into_hook_output(I::EVENT))
+    }
+}
+
 /// Wrapper to convert a closure into a `PermissionCallback`.
 ///
 /// # Example
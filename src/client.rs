@@ -37,6 +37,9 @@
 //! - Reader task gets receiver once, then releases transport lock
 //! - Writer task locks transport briefly for each write operation
 //! - No contention: reader never blocks writer, writer never blocks reader
+//! - Any [`Transport`] implementation can be plugged in via
+//!   [`ClaudeSDKClient::with_transport`]; [`ClaudeSDKClient::new`] uses
+//!   [`SubprocessTransport`]
 //!
 //! # Example: Basic Usage
 //!
@@ -141,22 +144,47 @@
 //! # }
 //! ```
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{Mutex, mpsc};
 use tokio_util::sync::CancellationToken;
 
-use crate::control::{ControlMessage, ControlRequest, ProtocolHandler};
+use crate::control::{
+    ControlMessage, ControlRequest, ControlResponse, InboundControlRequest, ProtocolHandler,
+};
 use crate::error::{ClaudeError, Result};
 use crate::hooks::HookManager;
+use crate::mcp::{SdkMcpServer, jsonrpc_error};
 use crate::message::parse_message;
 use crate::permissions::PermissionManager;
+use crate::session::store::SessionRecorder;
+use crate::structured_output::{
+    SchemaViolation, StructuredOutputAttempt, StructuredOutputPolicy, result_output, validate,
+};
 use crate::transport::{PromptInput, SubprocessTransport, Transport};
 use crate::types::{
-    AccountInfo, ClaudeAgentOptions, HookEvent, Message, ModelInfo, PermissionRequest, RequestId,
-    SessionId, SessionInfo,
+    AccountInfo, ClaudeAgentOptions, HookEvent, Message, ModelInfo, OutputFormat,
+    PermissionRequest, RequestId, SessionId, SessionInfo, ToolName, ToolPermissionContext, Usage,
+    UsageTracker, UserContentBlock, UserInput,
 };
-use futures::Stream;
+use futures::{Stream, StreamExt};
+
+/// Default time to wait for the CLI to acknowledge a control request
+const DEFAULT_CONTROL_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Longest time client construction waits for the hook registration handshake
+///
+/// Transports and older CLIs that never answer `initialize` fall back to
+/// message-derived hooks after this, instead of after the full control
+/// request timeout.
+const HOOK_REGISTRATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest time `close()` waits for the session recorder to finish writing
+///
+/// The recording only ends with the reader, so a transport whose stream
+/// outlives `close()` or a blocked store would otherwise hang it.
+const SESSION_RECORDER_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// A buffered message with its associated session ID for security validation
 type BufferedMessage = (Option<SessionId>, String);
@@ -164,15 +192,29 @@ type BufferedMessage = (Option<SessionId>, String);
 /// Thread-safe queue for buffering messages during streaming
 type MessageBuffer = Arc<std::sync::Mutex<VecDeque<BufferedMessage>>>;
 
+/// Transport shared between the reader and writer tasks
+type SharedTransport = Arc<Mutex<Box<dyn Transport>>>;
+
+/// Handlers for control requests sent by the CLI
+#[derive(Clone)]
+struct ControlRequestHandlers {
+    hook_manager: Option<Arc<Mutex<HookManager>>>,
+    permission_manager: Option<Arc<Mutex<PermissionManager>>>,
+    sdk_mcp_servers: HashMap<String, Arc<dyn SdkMcpServer>>,
+    cancellation_token: CancellationToken,
+}
+
 /// Context for the message reader background task
 struct MessageReaderContext {
-    transport: Arc<Mutex<SubprocessTransport>>,
+    transport: SharedTransport,
     protocol: Arc<Mutex<ProtocolHandler>>,
     message_tx: mpsc::UnboundedSender<Result<Message>>,
     session_id: Arc<std::sync::Mutex<Option<SessionId>>>,
     session_info: Arc<std::sync::Mutex<Option<SessionInfo>>>,
     bound_session_id: Arc<std::sync::Mutex<Option<SessionId>>>,
     hook_manager: Option<Arc<Mutex<HookManager>>>,
+    control_handlers: ControlRequestHandlers,
+    session_recorder: Option<mpsc::UnboundedSender<Message>>,
     is_resume: bool,
 }
 
@@ -203,13 +245,15 @@ struct MessageReaderContext {
 /// ```
 pub struct ClaudeSDKClient {
     /// Transport layer
-    transport: Arc<Mutex<SubprocessTransport>>,
+    transport: SharedTransport,
     /// Control protocol handler
     protocol: Arc<Mutex<ProtocolHandler>>,
     /// Message stream receiver
     message_rx: mpsc::UnboundedReceiver<Result<Message>>,
     /// Control message sender
     control_tx: mpsc::UnboundedSender<ControlRequest>,
+    /// How long to wait for the CLI to acknowledge a control request
+    control_request_timeout: Duration,
     /// Hook event receiver (if not using automatic handler)
     hook_rx: Option<mpsc::UnboundedReceiver<(String, HookEvent)>>,
     /// Permission request receiver (if not using automatic handler)
@@ -236,6 +280,14 @@ pub struct ClaudeSDKClient {
     message_buffer: MessageBuffer,
     /// Bound session ID - if set, all sends validate against this
     bound_session_id: Arc<std::sync::Mutex<Option<SessionId>>>,
+    /// Running token usage of the turn being read
+    turn_usage: UsageTracker,
+    /// Output format the CLI was started with
+    output_format: Option<OutputFormat>,
+    /// Validation and repair of structured output
+    structured_output_policy: Option<StructuredOutputPolicy>,
+    /// Task writing messages to the session store
+    session_recorder_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl ClaudeSDKClient {
@@ -254,6 +306,64 @@ impl ClaudeSDKClient {
         // Create cancellation token (like AbortController in JavaScript)
         let cancellation_token = CancellationToken::new();
 
+        // Create transport with streaming mode and pass child cancellation token
+        let transport = SubprocessTransport::with_cancellation_token(
+            PromptInput::Stream,
+            options.clone(),
+            cli_path,
+            Some(cancellation_token.child_token()),
+        )?;
+
+        Self::connect_with(Box::new(transport), options, cancellation_token).await
+    }
+
+    /// Create a new `ClaudeSDKClient` on top of a custom [`Transport`]
+    ///
+    /// Use this to run the client over something other than a local CLI
+    /// subprocess (a remote or containerized CLI, an in-memory transport for
+    /// tests, ...). The transport must speak the stream-json protocol in both
+    /// directions. Hooks, permissions, session binding and introspection work
+    /// exactly as with [`ClaudeSDKClient::new`].
+    ///
+    /// The transport is connected by this method; `options` configures the
+    /// client side only (hooks, `can_use_tool`, resume detection).
+    ///
+    /// # Arguments
+    /// * `transport` - Transport to communicate through (not yet connected)
+    /// * `options` - Configuration options
+    ///
+    /// # Errors
+    /// Returns error if the transport fails to connect
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use anthropic_agent_sdk::{
+    ///     ClaudeAgentOptions, ClaudeSDKClient, PromptInput, SubprocessTransport,
+    /// };
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let options = ClaudeAgentOptions::default();
+    /// let transport = SubprocessTransport::new(PromptInput::Stream, options.clone(), None)?;
+    /// let mut client = ClaudeSDKClient::with_transport(transport, options).await?;
+    /// client.send_message("Hello, Claude!").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_transport(
+        transport: impl Transport + 'static,
+        options: ClaudeAgentOptions,
+    ) -> Result<Self> {
+        Self::connect_with(Box::new(transport), options, CancellationToken::new()).await
+    }
+
+    /// Connect the transport and spawn the background tasks
+    #[allow(clippy::too_many_lines)]
+    async fn connect_with(
+        mut transport: Box<dyn Transport>,
+        options: ClaudeAgentOptions,
+        cancellation_token: CancellationToken,
+    ) -> Result<Self> {
         // Initialize hook manager if hooks are configured
         let (hook_manager, hook_rx) = if let Some(ref hooks_config) = options.hooks {
             let mut manager = HookManager::from_hooks_config(hooks_config.clone());
@@ -270,7 +380,8 @@ impl ClaudeSDKClient {
             if let Some(callback) = options.can_use_tool.clone() {
                 manager.set_callback(callback);
             }
-            manager.set_allowed_tools(Some(options.allowed_tools.clone()));
+            // allowed_tools only auto-approves tools in the CLI; anything else is
+            // still asked about, so it must not become a deny list here
             manager.set_disallowed_tools(options.disallowed_tools.clone());
             (Some(Arc::new(Mutex::new(manager))), None)
         } else {
@@ -279,15 +390,9 @@ impl ClaudeSDKClient {
 
         // Check if this is a resume session (for SessionStart hook)
         let is_resume = options.resume.is_some();
-
-        // Create transport with streaming mode and pass child cancellation token
-        let prompt_input = PromptInput::Stream;
-        let mut transport = SubprocessTransport::with_cancellation_token(
-            prompt_input,
-            options,
-            cli_path,
-            Some(cancellation_token.child_token()),
-        )?;
+        let control_request_timeout = options
+            .control_request_timeout_secs
+            .map_or(DEFAULT_CONTROL_REQUEST_TIMEOUT, Duration::from_secs);
 
         // Connect transport
         transport.connect().await?;
@@ -315,6 +420,12 @@ impl ClaudeSDKClient {
         let session_info = Arc::new(std::sync::Mutex::new(None));
         let bound_session_id = Arc::new(std::sync::Mutex::new(None));
 
+        let (session_recorder, session_recorder_task) = options
+            .session_store
+            .clone()
+            .map(|store| SessionRecorder::new(store, &options).spawn())
+            .unzip();
+
         // Spawn message reader task
         let reader_ctx = MessageReaderContext {
             transport: transport.clone(),
@@ -324,6 +435,13 @@ impl ClaudeSDKClient {
             session_info: session_info.clone(),
             bound_session_id: bound_session_id.clone(),
             hook_manager: hook_manager.clone(),
+            control_handlers: ControlRequestHandlers {
+                hook_manager: hook_manager.clone(),
+                permission_manager: permission_manager.clone(),
+                sdk_mcp_servers: options.sdk_mcp_servers.clone(),
+                cancellation_token: cancellation_token.child_token(),
+            },
+            session_recorder,
             is_resume,
         };
         tokio::spawn(async move {
@@ -360,11 +478,12 @@ impl ClaudeSDKClient {
             });
         }
 
-        Ok(Self {
+        let client = Self {
             transport,
             protocol,
             message_rx,
             control_tx,
+            control_request_timeout,
             hook_rx,
             permission_rx,
             hook_manager,
@@ -377,7 +496,52 @@ impl ClaudeSDKClient {
             runtime_max_thinking_tokens: Arc::new(std::sync::Mutex::new(None)),
             message_buffer: Arc::new(std::sync::Mutex::new(VecDeque::new())),
             bound_session_id,
-        })
+            turn_usage: UsageTracker::new(),
+            output_format: options.output_format.clone(),
+            structured_output_policy: options.structured_output_policy.clone(),
+            session_recorder_task,
+        };
+
+        client.register_cli_hooks().await;
+
+        Ok(client)
+    }
+
+    /// Register hook callbacks with the CLI via the `initialize` handshake
+    ///
+    /// On success the CLI invokes these hooks through `hook_callback` control
+    /// requests inside the agent loop. If the CLI rejects the handshake or does
+    /// not answer within [`HOOK_REGISTRATION_TIMEOUT`], hooks keep being
+    /// derived from the message stream.
+    async fn register_cli_hooks(&self) {
+        let Some(ref manager) = self.hook_manager else {
+            return;
+        };
+        let Some(hooks) = manager.lock().await.cli_hook_registrations() else {
+            return;
+        };
+
+        let request = self
+            .protocol
+            .lock()
+            .await
+            .create_initialize_request(Some(hooks));
+        let timeout = self.control_request_timeout.min(HOOK_REGISTRATION_TIMEOUT);
+        match self
+            .send_control_request_with_timeout(request, timeout)
+            .await
+        {
+            Ok(_) => {
+                manager.lock().await.set_cli_delegation(true);
+                tracing::debug!("Registered hook callbacks with CLI");
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    "CLI did not accept hook registration, deriving hooks from messages"
+                );
+            }
+        }
     }
 
     /// Message reader task - reads from transport and processes messages
@@ -394,6 +558,8 @@ impl ClaudeSDKClient {
             session_info,
             bound_session_id,
             hook_manager,
+            control_handlers,
+            session_recorder,
             is_resume,
         } = ctx;
         // Get the message receiver from the transport without holding the lock
@@ -433,19 +599,34 @@ impl ClaudeSDKClient {
                     }
                     drop(protocol_guard);
 
-                    // Check for control_response (ack from CLI for control_request)
-                    // These are internal protocol messages, not user-facing
-                    if let Some(msg_type) = value.get("type").and_then(|v| v.as_str()) {
-                        if msg_type == "control_response" {
-                            tracing::debug!(
-                                request_id = %value.get("response")
-                                    .and_then(|r| r.get("request_id"))
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("unknown"),
-                                "Received control_response (interrupt/setModel/etc ack)"
-                            );
-                            continue;
+                    // Answer control requests from the CLI (hook callbacks, permission
+                    // checks, SDK MCP messages) off the reader task, so slow callbacks
+                    // never stall message delivery
+                    if let Some((request_id, request)) =
+                        ProtocolHandler::parse_control_request(&value)
+                    {
+                        tokio::spawn(Self::answer_control_request(
+                            transport.clone(),
+                            control_handlers.clone(),
+                            request_id,
+                            request,
+                        ));
+                        continue;
+                    }
+
+                    // Route control_response (ack from CLI for control_request) to the
+                    // pending request. These are internal protocol messages, not user-facing
+                    if value.get("type").and_then(|v| v.as_str()) == Some("control_response") {
+                        if let Some(response) = ProtocolHandler::parse_control_response(&value) {
+                            tracing::debug!(response = ?response, "Received control_response");
+                            let protocol_guard = protocol.lock().await;
+                            if let Err(e) = protocol_guard.handle_response(response).await {
+                                tracing::warn!(error = %e, "Failed to route control_response");
+                            }
+                        } else {
+                            tracing::debug!(value = %value, "Ignoring malformed control_response");
                         }
+                        continue;
                     }
 
                     // Otherwise parse as regular message
@@ -550,6 +731,10 @@ impl ClaudeSDKClient {
                                 }
                             }
 
+                            if let Some(ref recorder) = session_recorder {
+                                let _ = recorder.send(msg.clone());
+                            }
+
                             if message_tx.send(Ok(msg)).is_err() {
                                 tracing::warn!(
                                     "Failed to send message to channel - receiver dropped"
@@ -569,6 +754,143 @@ impl ClaudeSDKClient {
                 }
             }
         }
+
+        // No more acks can arrive: fail any control requests still waiting
+        protocol.lock().await.clear_pending().await;
+    }
+
+    /// Answer a control request sent by the CLI with a `control_response`
+    async fn answer_control_request(
+        transport: SharedTransport,
+        handlers: ControlRequestHandlers,
+        request_id: RequestId,
+        request: Result<InboundControlRequest>,
+    ) {
+        let result = match request {
+            Ok(InboundControlRequest::HookCallback {
+                callback_id, input, ..
+            }) => {
+                Self::run_hook_callback(handlers.hook_manager.as_ref(), &callback_id, input).await
+            }
+            Ok(InboundControlRequest::CanUseTool {
+                tool_name,
+                input,
+                permission_suggestions,
+                blocked_path,
+                tool_use_id,
+            }) => {
+                let mut context = ToolPermissionContext::with_cancellation(
+                    permission_suggestions,
+                    handlers.cancellation_token.child_token(),
+                );
+                context.blocked_path = blocked_path;
+                context.tool_use_id = tool_use_id;
+                Self::run_permission_check(
+                    handlers.permission_manager.as_ref(),
+                    tool_name,
+                    input,
+                    context,
+                )
+                .await
+            }
+            Ok(InboundControlRequest::McpMessage {
+                server_name,
+                message,
+            }) => {
+                Ok(Self::route_mcp_message(&handlers.sdk_mcp_servers, &server_name, message).await)
+            }
+            Err(e) => Err(e),
+        };
+
+        let response = match result {
+            Ok(payload) => ProtocolHandler::control_success(&request_id, &payload),
+            Err(e) => {
+                tracing::warn!(request_id = %request_id.as_str(), error = %e, "Control request failed");
+                ProtocolHandler::control_error(&request_id, e.to_string())
+            }
+        };
+
+        let line = format!("{response}\n");
+        if let Err(e) = transport.lock().await.write(&line).await {
+            tracing::error!(error = %e, "Failed to write control response to CLI");
+        }
+    }
+
+    /// Invoke a hook callback registered during `initialize`
+    async fn run_hook_callback(
+        hook_manager: Option<&Arc<Mutex<HookManager>>>,
+        callback_id: &str,
+        input: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        // Resolve the callback under the lock, but invoke it without holding it
+        let (hook, context) = match hook_manager {
+            Some(manager) => {
+                let mut guard = manager.lock().await;
+                // A callback from the CLI proves the registrations were accepted,
+                // even if the `initialize` ack arrived after the handshake timed out
+                guard.set_cli_delegation(true);
+                (guard.registered_hook(callback_id), guard.build_context())
+            }
+            None => (None, crate::types::HookContext::default()),
+        };
+        let hook = hook.ok_or_else(|| {
+            ClaudeError::hook(format!("No hook callback registered for '{callback_id}'"))
+        })?;
+
+        let output = hook.invoke(input, context).await?;
+        tracing::debug!(callback_id = callback_id, event = ?hook.event, "Hook callback answered");
+        serde_json::to_value(&output).map_err(|e| ClaudeError::json_encode(e.to_string()))
+    }
+
+    /// Decide a `can_use_tool` request through the permission manager
+    async fn run_permission_check(
+        permission_manager: Option<&Arc<Mutex<PermissionManager>>>,
+        tool_name: String,
+        input: serde_json::Value,
+        context: ToolPermissionContext,
+    ) -> Result<serde_json::Value> {
+        let manager = permission_manager.ok_or_else(|| {
+            ClaudeError::control_protocol("can_use_tool requested but no callback is configured")
+        })?;
+
+        // Snapshot the manager under the lock, but run the callback without
+        // holding it so concurrent requests are answered in parallel
+        let manager = manager.lock().await.clone();
+        let result = manager
+            .can_use_tool(ToolName::new(&tool_name), input.clone(), context)
+            .await?;
+        tracing::debug!(tool_name = %tool_name, result = ?result, "Permission check answered");
+        Ok(result.to_cli_response(&input))
+    }
+
+    /// Route a JSON-RPC message to an in-process SDK MCP server
+    ///
+    /// Failures are reported as JSON-RPC errors inside `mcp_response`, so the
+    /// CLI surfaces them to the model as MCP errors.
+    async fn route_mcp_message(
+        servers: &HashMap<String, Arc<dyn SdkMcpServer>>,
+        server_name: &str,
+        message: serde_json::Value,
+    ) -> serde_json::Value {
+        let Some(server) = servers.get(server_name) else {
+            return serde_json::json!({
+                "mcp_response": jsonrpc_error(
+                    &message,
+                    -32601,
+                    format!("SDK MCP server '{server_name}' not found"),
+                )
+            });
+        };
+
+        match server.handle_message(message.clone()).await {
+            Ok(Some(response)) => serde_json::json!({"mcp_response": response}),
+            // Notifications need no response
+            Ok(None) => serde_json::json!({}),
+            Err(e) => {
+                tracing::warn!(server = server_name, error = %e, "SDK MCP server error");
+                serde_json::json!({"mcp_response": jsonrpc_error(&message, -32603, e.to_string())})
+            }
+        }
     }
 
     /// Control message writer task - writes control requests to transport
@@ -578,23 +900,13 @@ impl ClaudeSDKClient {
     /// {"type": "control_request", "request_id": "...", "request": {"subtype": "..."}}
     /// ```
     async fn control_writer_task(
-        transport: Arc<Mutex<SubprocessTransport>>,
-        _protocol: Arc<Mutex<ProtocolHandler>>,
+        transport: SharedTransport,
+        protocol: Arc<Mutex<ProtocolHandler>>,
         mut control_rx: mpsc::UnboundedReceiver<ControlRequest>,
     ) {
-        use std::sync::atomic::{AtomicU64, Ordering};
-        static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(0);
-
         while let Some(request) = control_rx.recv().await {
-            // Generate unique request ID matching TypeScript SDK format
-            let request_id = format!(
-                "req_{}_{:x}",
-                REQUEST_COUNTER.fetch_add(1, Ordering::SeqCst),
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_nanos()
-            );
+            // The request's own ID lets the reader correlate the CLI's control_response
+            let request_id = request.id().clone();
 
             // Build the inner request object based on control type
             let inner_request = match request {
@@ -642,6 +954,12 @@ impl ClaudeSDKClient {
                         "user_message_uuid": user_message_uuid
                     })
                 }
+                ControlRequest::Initialize { hooks, .. } => {
+                    serde_json::json!({
+                        "subtype": "initialize",
+                        "hooks": hooks
+                    })
+                }
                 _ => {
                     // Other control types not yet supported in stream-json mode
                     tracing::debug!(request = ?request, "Skipping unsupported control request");
@@ -652,7 +970,7 @@ impl ClaudeSDKClient {
             // Wrap in control_request envelope (matches TypeScript SDK protocol)
             let control_json = serde_json::json!({
                 "type": "control_request",
-                "request_id": request_id,
+                "request_id": request_id.as_str(),
                 "request": inner_request
             });
 
@@ -660,8 +978,16 @@ impl ClaudeSDKClient {
                 tracing::debug!(json = %json_str, "Sending control request to CLI");
                 let message_line = format!("{json_str}\n");
                 let mut transport_guard = transport.lock().await;
-                if transport_guard.write(&message_line).await.is_err() {
-                    tracing::error!("Failed to write control request to CLI");
+                if let Err(e) = transport_guard.write(&message_line).await {
+                    drop(transport_guard);
+                    tracing::error!(error = %e, "Failed to write control request to CLI");
+                    // Fail the waiting caller now instead of letting it time out
+                    let failure = ControlResponse::Error {
+                        id: request_id,
+                        message: format!("Failed to write control request: {e}"),
+                        code: None,
+                    };
+                    let _ = protocol.lock().await.handle_response(failure).await;
                     break;
                 }
             } else {
@@ -723,9 +1049,9 @@ impl ClaudeSDKClient {
         mut permission_rx: mpsc::UnboundedReceiver<(RequestId, PermissionRequest)>,
     ) {
         while let Some((request_id, request)) = permission_rx.recv().await {
-            let manager_guard = manager.lock().await;
+            let manager_snapshot = manager.lock().await.clone();
 
-            match manager_guard
+            match manager_snapshot
                 .can_use_tool(
                     request.tool_name.clone(),
                     request.tool_input.clone(),
@@ -734,8 +1060,6 @@ impl ClaudeSDKClient {
                 .await
             {
                 Ok(result) => {
-                    drop(manager_guard);
-
                     // Send permission response
                     let protocol_guard = protocol.lock().await;
                     let _request = protocol_guard
@@ -767,15 +1091,56 @@ impl ClaudeSDKClient {
     /// # Errors
     /// Returns error if message cannot be sent
     pub async fn send_message(&mut self, content: impl Into<String>) -> Result<()> {
+        self.send_user_input(UserInput::Text(content.into())).await
+    }
+
+    /// Send a message made of content blocks (text, images, PDF documents)
+    ///
+    /// Every block is validated (supported media type matching the data,
+    /// size limits) before anything is written to the CLI. The
+    /// `UserPromptSubmit` hook receives the text blocks joined by newlines.
+    ///
+    /// # Arguments
+    /// * `blocks` - Content blocks of the message
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidContent` if a block fails validation,
+    /// or an error if the message cannot be sent
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_agent_sdk::{ClaudeSDKClient, ClaudeAgentOptions};
+    /// # use anthropic_agent_sdk::types::UserContentBlock;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = ClaudeSDKClient::new(ClaudeAgentOptions::default(), None).await?;
+    /// client
+    ///     .send_content(vec![
+    ///         UserContentBlock::text("What is wrong with this page?"),
+    ///         UserContentBlock::image_file("screenshot.png")?,
+    ///     ])
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_content(&mut self, blocks: Vec<UserContentBlock>) -> Result<()> {
+        let content = UserInput::Blocks(blocks);
+        content.validate()?;
+        self.send_user_input(content).await
+    }
+
+    /// Write a user message to the CLI after session validation and hooks
+    async fn send_user_input(&mut self, content: UserInput) -> Result<()> {
         // Validate session if bound
         self.validate_session()?;
 
-        let content_str = content.into();
-
         // Trigger UserPromptSubmit hook before sending
         if let Some(ref manager) = self.hook_manager {
             let manager_guard = manager.lock().await;
-            if let Err(e) = manager_guard.trigger_user_prompt_submit(&content_str).await {
+            if let Err(e) = manager_guard
+                .trigger_user_prompt_submit(&content.text())
+                .await
+            {
                 tracing::warn!(error = %e, "UserPromptSubmit hook error");
             }
         }
@@ -785,7 +1150,7 @@ impl ClaudeSDKClient {
             "type": "user",
             "message": {
                 "role": "user",
-                "content": content_str
+                "content": content
             }
         });
         let message_json = format!("{}\n", serde_json::to_string(&message)?);
@@ -929,7 +1294,7 @@ impl ClaudeSDKClient {
     /// # }
     /// ```
     pub async fn next_buffered(&mut self) -> Option<Result<Message>> {
-        match self.message_rx.recv().await {
+        match self.recv_message().await {
             Some(result) => {
                 // Check if this is a Result message
                 if let Ok(Message::Result { .. }) = &result {
@@ -953,21 +1318,71 @@ impl ClaudeSDKClient {
 
     /// Send an interrupt signal
     ///
-    /// **Note**: Interrupt functionality via control messages may not be fully supported
-    /// in all Claude CLI versions. The method demonstrates the SDK's bidirectional
-    /// capability and will send the control message without blocking, but the CLI
-    /// may not process it. Check your CLI version for control message support.
+    /// Waits for the CLI to acknowledge the interrupt. Messages keep flowing
+    /// through [`next_message`](Self::next_message) while waiting.
     ///
     /// # Errors
-    /// Returns error if interrupt cannot be sent
+    /// Returns `ControlRequestFailed` if the CLI rejects the interrupt,
+    /// `ControlTimeout` if no acknowledgement arrives in time, or an error if
+    /// the request cannot be sent
     pub async fn interrupt(&mut self) -> Result<()> {
         let protocol = self.protocol.lock().await;
         let request = protocol.create_interrupt_request();
         drop(protocol);
 
-        self.control_tx
-            .send(request)
-            .map_err(|_| ClaudeError::transport("Control channel closed"))
+        self.send_control_request(request).await.map(|_| ())
+    }
+
+    /// Send a control request and wait for the CLI's `control_response`
+    ///
+    /// Returns the payload of a successful response, if any.
+    async fn send_control_request(
+        &self,
+        request: ControlRequest,
+    ) -> Result<Option<serde_json::Value>> {
+        self.send_control_request_with_timeout(request, self.control_request_timeout)
+            .await
+    }
+
+    /// Send a control request and wait up to `timeout` for its acknowledgement
+    async fn send_control_request_with_timeout(
+        &self,
+        request: ControlRequest,
+        timeout: Duration,
+    ) -> Result<Option<serde_json::Value>> {
+        let request_type = request.request_type();
+        let id = request.id().clone();
+        let response_rx = self
+            .protocol
+            .lock()
+            .await
+            .send_request(request.clone())
+            .await?;
+
+        if self.control_tx.send(request).is_err() {
+            self.protocol.lock().await.cancel_request(&id).await;
+            return Err(ClaudeError::transport("Control channel closed"));
+        }
+
+        match tokio::time::timeout(timeout, response_rx).await {
+            Ok(Ok(ControlResponse::Success { data, .. })) => Ok(data),
+            Ok(Ok(ControlResponse::Error { message, .. })) => {
+                Err(ClaudeError::control_request_failed(request_type, message))
+            }
+            Ok(Ok(other)) => Err(ClaudeError::control_protocol(format!(
+                "Unexpected response to {request_type}: {other:?}"
+            ))),
+            Ok(Err(_)) => Err(ClaudeError::control_protocol(format!(
+                "Connection closed before {request_type} was acknowledged"
+            ))),
+            Err(_) => {
+                self.protocol.lock().await.cancel_request(&id).await;
+                Err(ClaudeError::control_timeout(
+                    timeout.as_secs(),
+                    request_type,
+                ))
+            }
+        }
     }
 
     /// Rewind files to a checkpoint
@@ -979,7 +1394,9 @@ impl ClaudeSDKClient {
     /// * `user_message_uuid` - UUID from a User message's `uuid` field
     ///
     /// # Errors
-    /// Returns error if the rewind request cannot be sent
+    /// Returns `ControlRequestFailed` if the CLI rejects the rewind (e.g. unknown
+    /// checkpoint), `ControlTimeout` if no acknowledgement arrives in time, or an
+    /// error if the request cannot be sent
     ///
     /// # Example
     /// ```rust,no_run
@@ -1011,16 +1428,23 @@ impl ClaudeSDKClient {
         let request = protocol.create_rewind_files_request(user_message_uuid);
         drop(protocol);
 
-        self.control_tx
-            .send(request)
-            .map_err(|_| ClaudeError::transport("Control channel closed"))
+        self.send_control_request(request).await.map(|_| ())
     }
 
     /// Get the next message from the stream
     ///
     /// Returns None when the stream ends
     pub async fn next_message(&mut self) -> Option<Result<Message>> {
-        self.message_rx.recv().await
+        self.recv_message().await
+    }
+
+    /// Receive the next message, keeping the turn's token usage current
+    async fn recv_message(&mut self) -> Option<Result<Message>> {
+        let result = self.message_rx.recv().await;
+        if let Some(Ok(ref msg)) = result {
+            self.turn_usage.observe(msg);
+        }
+        result
     }
 
     /// Take the hook event receiver
@@ -1112,7 +1536,7 @@ impl ClaudeSDKClient {
     #[must_use = "receive_response returns a stream that must be consumed to receive messages"]
     pub fn receive_response(&mut self) -> impl Stream<Item = Result<Message>> + '_ {
         async_stream::stream! {
-            while let Some(result) = self.message_rx.recv().await {
+            while let Some(result) = self.recv_message().await {
                 let is_result = matches!(&result, Ok(Message::Result { .. }));
                 yield result;
                 if is_result {
@@ -1122,6 +1546,179 @@ impl ClaudeSDKClient {
         }
     }
 
+    /// Send a prompt and deserialize the turn's structured output into `T`
+    /// (`schemars` feature)
+    ///
+    /// The CLI takes the output schema when it starts, so create the client
+    /// with `output_format` set to [`OutputFormat::for_type::<T>()`](OutputFormat::for_type).
+    ///
+    /// With a `structured_output_policy`, the output is validated against
+    /// the schema and repaired like in [`ask_structured`](Self::ask_structured);
+    /// output that does not deserialize into `T` counts as invalid too.
+    ///
+    /// # Errors
+    /// Returns [`ClaudeError::InvalidConfig`] if the client has no
+    /// `output_format`, any error of the turn, and
+    /// [`ClaudeError::StructuredOutput`] with the raw output if it does not
+    /// match `T` ([`ClaudeError::StructuredOutputInvalid`] with a policy)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_agent_sdk::{ClaudeSDKClient, ClaudeAgentOptions};
+    /// # use anthropic_agent_sdk::types::OutputFormat;
+    /// # use schemars::JsonSchema;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct Review {
+    ///     approved: bool,
+    ///     comments: Vec<String>,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let options = ClaudeAgentOptions::builder()
+    ///     .output_format(OutputFormat::for_type::<Review>())
+    ///     .build();
+    /// let mut client = ClaudeSDKClient::new(options, None).await?;
+    /// let review: Review = client.ask_typed("Review src/main.rs").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "schemars")]
+    pub async fn ask_typed<T>(&mut self, prompt: impl Into<String>) -> Result<T>
+    where
+        T: schemars::JsonSchema + serde::de::DeserializeOwned,
+    {
+        let Some(ref output_format) = self.output_format else {
+            return Err(ClaudeError::invalid_config(format!(
+                "ask_typed::<{}>() requires a client created with output_format",
+                std::any::type_name::<T>()
+            )));
+        };
+        if output_format.schema != OutputFormat::for_type::<T>().schema {
+            tracing::warn!(
+                type_name = std::any::type_name::<T>(),
+                "Client output_format schema differs from the schema of the requested type"
+            );
+        }
+
+        if self.structured_output_policy.is_some() {
+            return self
+                .ask_validated(prompt.into(), |raw| {
+                    T::deserialize(raw).map_err(|e| e.to_string())
+                })
+                .await;
+        }
+        self.send_message(prompt).await?;
+        crate::query::typed_result(self.receive_response()).await
+    }
+
+    /// Send a prompt and return the turn's structured output once it is
+    /// valid against the client's `output_format` schema
+    ///
+    /// Invalid or missing output is answered with a corrective follow-up
+    /// listing the violations, until the attempts allowed by the client's
+    /// `structured_output_policy` run out (one attempt without a policy).
+    /// Messages of a turn that fail to parse count as violations of that
+    /// attempt.
+    ///
+    /// # Errors
+    /// Returns [`ClaudeError::InvalidConfig`] if the client has no
+    /// `output_format`, the first error of a turn that ended without a
+    /// result, and
+    /// [`ClaudeError::StructuredOutputInvalid`] listing every attempt if none
+    /// produced valid output
+    pub async fn ask_structured(&mut self, prompt: impl Into<String>) -> Result<serde_json::Value> {
+        self.ask_validated(prompt.into(), |raw| Ok(raw.clone()))
+            .await
+    }
+
+    /// Validation and repair loop behind `ask_structured` and `ask_typed`
+    async fn ask_validated<T>(
+        &mut self,
+        prompt: String,
+        convert: impl Fn(&serde_json::Value) -> std::result::Result<T, String>,
+    ) -> Result<T> {
+        let Some(schema) = self.output_format.as_ref().map(|f| f.schema.clone()) else {
+            return Err(ClaudeError::invalid_config(
+                "Structured output requires a client created with output_format",
+            ));
+        };
+        let policy = self
+            .structured_output_policy
+            .clone()
+            .unwrap_or_else(|| StructuredOutputPolicy::new(1));
+        let max_attempts = policy.max_attempts.max(1);
+
+        let mut attempts = Vec::new();
+        let mut message = prompt;
+        for attempt in 1..=max_attempts {
+            self.send_message(message).await?;
+
+            // Read the whole turn even past a bad message, so its rest is
+            // not left for the next receive_response()
+            let mut result = None;
+            let mut stream_errors = Vec::new();
+            {
+                let mut stream = Box::pin(self.receive_response());
+                while let Some(msg) = stream.next().await {
+                    match msg {
+                        Ok(msg @ Message::Result { .. }) => result = Some(msg),
+                        Ok(_) => {}
+                        Err(e) => stream_errors.push(e),
+                    }
+                }
+            }
+            let Some(result) = result else {
+                if let Some(e) = stream_errors.into_iter().next() {
+                    return Err(e);
+                }
+                attempts.push(StructuredOutputAttempt {
+                    attempt,
+                    raw: serde_json::Value::Null,
+                    violations: vec![SchemaViolation {
+                        path: "$".to_string(),
+                        message: "message stream ended before the turn's result".to_string(),
+                    }],
+                });
+                break;
+            };
+
+            let (raw, mut violations) = result_output(&result);
+            violations.extend(stream_errors.iter().map(|e| SchemaViolation {
+                path: "$".to_string(),
+                message: e.to_string(),
+            }));
+            if violations.is_empty() {
+                violations = validate(&schema, &raw);
+            }
+            if violations.is_empty() {
+                match convert(&raw) {
+                    Ok(value) => return Ok(value),
+                    Err(e) => violations.push(SchemaViolation {
+                        path: "$".to_string(),
+                        message: e,
+                    }),
+                }
+            }
+
+            tracing::debug!(
+                attempt,
+                max_attempts,
+                violations = violations.len(),
+                "Structured output rejected"
+            );
+            message = policy.repair_message(&violations);
+            attempts.push(StructuredOutputAttempt {
+                attempt,
+                raw,
+                violations,
+            });
+        }
+
+        Err(ClaudeError::structured_output_invalid(attempts))
+    }
+
     /// Check if the client is currently connected.
     ///
     /// Returns `true` if the transport is connected and ready.
@@ -1139,6 +1736,34 @@ impl ClaudeSDKClient {
         self.session_id.lock().ok()?.clone()
     }
 
+    /// Get the running token usage of the current turn.
+    ///
+    /// While a turn is in flight this sums the usage reported by the assistant
+    /// messages read so far and, with `include_partial_messages`, by
+    /// `message_start` and `message_delta` stream events. Once the `Result` has
+    /// been read it is the turn's aggregate usage, until the next turn starts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use anthropic_agent_sdk::{ClaudeSDKClient, ClaudeAgentOptions};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut client = ClaudeSDKClient::new(ClaudeAgentOptions::default(), None).await?;
+    /// client.send_message("Summarize this repository").await?;
+    ///
+    /// while let Some(msg) = client.next_message().await {
+    ///     msg?;
+    ///     let usage = client.current_usage();
+    ///     println!("{} in / {} out", usage.input_tokens, usage.output_tokens);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn current_usage(&self) -> Usage {
+        self.turn_usage.total()
+    }
+
     // ========================================================================
     // Session Binding
     // ========================================================================
@@ -1444,30 +2069,40 @@ impl ClaudeSDKClient {
     // Runtime Setters
     // ========================================================================
 
-    /// Store a model preference locally.
+    /// Switch the model for subsequent turns.
     ///
-    /// **NOTE:** Runtime model switching mid-session is NOT currently supported
-    /// by the Claude CLI's stream-json protocol. This method only stores the value
-    /// locally for SDK reference. To use a different model, start a new session:
+    /// Sends a `set_model` control request and waits for the CLI to accept it.
+    /// On success the value is also stored locally (see
+    /// [`get_runtime_model`](Self::get_runtime_model)).
     ///
     /// ```rust,no_run
     /// use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient};
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// let options = ClaudeAgentOptions::builder()
-    ///     .model("haiku")  // Set model at session start
-    ///     .build();
-    /// let mut client = ClaudeSDKClient::new(options, None).await?;
+    /// let mut client = ClaudeSDKClient::new(ClaudeAgentOptions::default(), None).await?;
+    /// client.set_model("haiku").await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_model(&self, model: impl Into<String>) {
+    ///
+    /// # Errors
+    /// Returns `ControlRequestFailed` if the CLI rejects the model, or
+    /// `ControlTimeout` if no acknowledgement arrives in time
+    pub async fn set_model(&self, model: impl Into<String>) -> Result<()> {
         let model_str = model.into();
-        tracing::debug!(model = %model_str, "set_model: storing locally (runtime switching not supported)");
+        tracing::debug!(model = %model_str, "set_model");
+
+        let request = self
+            .protocol
+            .lock()
+            .await
+            .create_set_model_request(Some(model_str.clone()));
+        self.send_control_request(request).await?;
 
         if let Ok(mut guard) = self.runtime_model.lock() {
             *guard = Some(model_str);
         }
+        Ok(())
     }
 
     /// Get the currently configured runtime model override.
@@ -1478,20 +2113,18 @@ impl ClaudeSDKClient {
         self.runtime_model.lock().ok()?.clone()
     }
 
-    /// Store a permission mode preference locally.
+    /// Switch the permission mode mid-session.
     ///
-    /// **NOTE:** Runtime permission mode switching mid-session is NOT currently
-    /// supported by the Claude CLI's stream-json protocol. This method only stores
-    /// the value locally for SDK reference. To set permission mode, use session options:
+    /// Sends a `set_permission_mode` control request and waits for the CLI to
+    /// accept it. On success the value is also stored locally (see
+    /// [`get_runtime_permission_mode`](Self::get_runtime_permission_mode)).
     ///
     /// ```rust,no_run
     /// use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient, PermissionMode};
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// let options = ClaudeAgentOptions::builder()
-    ///     .permission_mode(PermissionMode::AcceptEdits)  // Set at session start
-    ///     .build();
-    /// let mut client = ClaudeSDKClient::new(options, None).await?;
+    /// let mut client = ClaudeSDKClient::new(ClaudeAgentOptions::default(), None).await?;
+    /// client.set_permission_mode(PermissionMode::AcceptEdits).await?;
     /// # Ok(())
     /// # }
     /// ```
@@ -1501,12 +2134,30 @@ impl ClaudeSDKClient {
     /// - `AcceptEdits`: Auto-approve file edits
     /// - `Plan`: Plan-only mode, no execution
     /// - `BypassPermissions`: Auto-approve all operations (use with caution)
-    pub fn set_permission_mode(&self, mode: crate::types::PermissionMode) {
-        tracing::debug!(mode = ?mode, "set_permission_mode: storing locally (runtime switching not supported)");
+    ///
+    /// # Errors
+    /// Returns `ControlRequestFailed` if the CLI rejects the mode, or
+    /// `ControlTimeout` if no acknowledgement arrives in time
+    pub async fn set_permission_mode(&self, mode: crate::types::PermissionMode) -> Result<()> {
+        tracing::debug!(mode = ?mode, "set_permission_mode");
+
+        let mode_str = match mode {
+            crate::types::PermissionMode::Default => "default",
+            crate::types::PermissionMode::AcceptEdits => "acceptEdits",
+            crate::types::PermissionMode::Plan => "plan",
+            crate::types::PermissionMode::BypassPermissions => "bypassPermissions",
+        };
+        let request = self
+            .protocol
+            .lock()
+            .await
+            .create_set_permission_mode_request(mode_str);
+        self.send_control_request(request).await?;
 
         if let Ok(mut guard) = self.runtime_permission_mode.lock() {
             *guard = Some(mode);
         }
+        Ok(())
     }
 
     /// Get the currently configured runtime permission mode override.
@@ -1517,35 +2168,42 @@ impl ClaudeSDKClient {
         *self.runtime_permission_mode.lock().ok()?
     }
 
-    /// Store a max thinking tokens preference locally.
+    /// Change the maximum thinking tokens mid-session.
     ///
-    /// **NOTE:** Runtime thinking token adjustment mid-session is NOT currently
-    /// supported by the Claude CLI's stream-json protocol. This method only stores
-    /// the value locally for SDK reference. To set thinking tokens, use session options:
+    /// Sends a `set_max_thinking_tokens` control request and waits for the CLI
+    /// to accept it. On success the value is also stored locally (see
+    /// [`get_runtime_max_thinking_tokens`](Self::get_runtime_max_thinking_tokens)).
     ///
     /// ```rust,no_run
     /// use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient};
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-    /// let options = ClaudeAgentOptions::builder()
-    ///     .max_thinking_tokens(20000)  // Set at session start
-    ///     .build();
-    /// let mut client = ClaudeSDKClient::new(options, None).await?;
+    /// let mut client = ClaudeSDKClient::new(ClaudeAgentOptions::default(), None).await?;
+    /// client.set_max_thinking_tokens(20000).await?;
     /// # Ok(())
     /// # }
     /// ```
     ///
     /// Extended thinking allows Claude to "think" before responding,
     /// improving quality for complex tasks.
-    pub fn set_max_thinking_tokens(&self, tokens: u32) {
-        tracing::debug!(
-            tokens = tokens,
-            "set_max_thinking_tokens: storing locally (runtime switching not supported)"
-        );
+    ///
+    /// # Errors
+    /// Returns `ControlRequestFailed` if the CLI rejects the value, or
+    /// `ControlTimeout` if no acknowledgement arrives in time
+    pub async fn set_max_thinking_tokens(&self, tokens: u32) -> Result<()> {
+        tracing::debug!(tokens = tokens, "set_max_thinking_tokens");
+
+        let request = self
+            .protocol
+            .lock()
+            .await
+            .create_set_max_thinking_tokens_request(Some(tokens));
+        self.send_control_request(request).await?;
 
         if let Ok(mut guard) = self.runtime_max_thinking_tokens.lock() {
             *guard = Some(tokens);
         }
+        Ok(())
     }
 
     /// Get the currently configured runtime max thinking tokens override.
@@ -1556,10 +2214,10 @@ impl ClaudeSDKClient {
         *self.runtime_max_thinking_tokens.lock().ok()?
     }
 
-    /// Clear all runtime overrides.
+    /// Clear all locally recorded runtime overrides.
     ///
-    /// This resets model, permission mode, and thinking tokens to their
-    /// original values from the initial options.
+    /// This only forgets the values returned by the `get_runtime_*` accessors;
+    /// it does not send anything to the CLI, which keeps the last values set.
     pub fn clear_runtime_overrides(&self) {
         if let Ok(mut guard) = self.runtime_model.lock() {
             *guard = None;
@@ -1653,8 +2311,16 @@ impl ClaudeSDKClient {
         self.cancellation_token.is_cancelled()
     }
 
+    /// Close the CLI's input stream, letting it exit once the turn is done
+    pub(crate) async fn end_input(&self) -> Result<()> {
+        self.transport.lock().await.end_input().await
+    }
+
     /// Close the client and clean up resources
     ///
+    /// With a `session_store`, waits (up to a few seconds) until the
+    /// recorded messages and the session record are written.
+    ///
     /// # Errors
     /// Returns error if cleanup fails
     pub async fn close(&mut self) -> Result<()> {
@@ -1666,8 +2332,18 @@ impl ClaudeSDKClient {
             }
         }
 
-        let mut transport = self.transport.lock().await;
-        transport.close().await
+        self.transport.lock().await.close().await?;
+
+        // The reader stops with the transport, which ends the recording
+        if let Some(mut task) = self.session_recorder_task.take()
+            && tokio::time::timeout(SESSION_RECORDER_DRAIN_TIMEOUT, &mut task)
+                .await
+                .is_err()
+        {
+            tracing::warn!("Session recorder did not finish in time; abandoning the recording");
+            task.abort();
+        }
+        Ok(())
     }
 }
 
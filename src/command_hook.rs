@@ -0,0 +1,642 @@
+//! Shell-command hooks from Claude Code settings files
+//!
+//! Claude Code users configure hooks as shell commands in `.claude/settings.json`:
+//!
+//! ```json
+//! {
+//!   "hooks": {
+//!     "PreToolUse": [
+//!       { "matcher": "Bash", "hooks": [{ "type": "command", "command": "./check.sh", "timeout": 30 }] }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! [`CommandHook`] runs such a command as a [`HookCallback`] with the same
+//! contract as the CLI: the hook input JSON is piped to stdin, and the result
+//! is read from the exit code and stdout:
+//!
+//! - exit 0: stdout that parses as a JSON object is the [`HookOutput`]
+//!   (`decision`, `continue`, `hookSpecificOutput`, ...). Plain stdout is
+//!   added as context for `UserPromptSubmit` and `SessionStart`, and ignored
+//!   otherwise.
+//! - exit 2: a blocking error. stderr is the reason; for `PreToolUse` it
+//!   denies the tool use, for events that cannot be blocked it becomes a
+//!   system message.
+//! - any other exit code: a non-blocking error, logged and ignored.
+//!
+//! [`load_settings_hooks`] reads the `hooks` section of settings files into
+//! `HookMatcher`s for [`ClaudeAgentOptions::hooks`](crate::types::ClaudeAgentOptions::hooks).
+//! The SDK starts the CLI with no setting sources unless
+//! `setting_sources` is set, so the CLI does not run these hooks itself;
+//! don't load the same files through both.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::command_hook::load_settings_hooks;
+//! use anthropic_agent_sdk::types::{ClaudeAgentOptions, SettingSource};
+//!
+//! # async fn example() -> anthropic_agent_sdk::Result<()> {
+//! let project_dir = std::env::current_dir()?;
+//! let hooks = load_settings_hooks(
+//!     &[SettingSource::User, SettingSource::Project, SettingSource::Local],
+//!     &project_dir,
+//! )
+//! .await?;
+//!
+//! let options = ClaudeAgentOptions::builder()
+//!     .hooks(hooks)
+//!     .cwd(project_dir)
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::callbacks::HookCallback;
+use crate::error::{ClaudeError, Result};
+use crate::types::{
+    ContextHookOutput, HookContext, HookEvent, HookMatcher, HookOutput, IntoHookOutput,
+    PreToolUseHookOutput, SettingSource,
+};
+
+/// Exit code a hook command uses to block the action
+pub const BLOCKING_EXIT_CODE: i32 = 2;
+
+/// Events whose action exit code 2 blocks; for other events stderr is only shown
+const BLOCKING_EVENTS: [HookEvent; 7] = [
+    HookEvent::PreToolUse,
+    HookEvent::PermissionRequest,
+    HookEvent::PostToolUse,
+    HookEvent::PostToolUseFailure,
+    HookEvent::UserPromptSubmit,
+    HookEvent::Stop,
+    HookEvent::SubagentStop,
+];
+
+/// Events whose plain-text stdout is added as context
+const STDOUT_CONTEXT_EVENTS: [HookEvent; 2] =
+    [HookEvent::UserPromptSubmit, HookEvent::SessionStart];
+
+// ============================================================================
+// Command Hook
+// ============================================================================
+
+/// Hook callback that runs a shell command
+///
+/// The command runs through `sh -c` (`cmd /C` on Windows) with
+/// `CLAUDE_PROJECT_DIR` set, in the project directory or else the session's
+/// `cwd`.
+#[derive(Debug, Clone)]
+pub struct CommandHook {
+    command: String,
+    timeout: Duration,
+    project_dir: Option<PathBuf>,
+}
+
+impl CommandHook {
+    /// Default command timeout (60 seconds, matching the CLI)
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Run `command` through the shell
+    #[must_use]
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            timeout: Self::DEFAULT_TIMEOUT,
+            project_dir: None,
+        }
+    }
+
+    /// Kill the command after `timeout`
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run in `dir` and expose it as `CLAUDE_PROJECT_DIR`
+    #[must_use]
+    pub fn project_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.project_dir = Some(dir.into());
+        self
+    }
+
+    /// Shell command
+    #[must_use]
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    fn shell_command(&self) -> Command {
+        #[cfg(windows)]
+        let mut cmd = {
+            let mut cmd = Command::new("cmd");
+            cmd.arg("/C").arg(&self.command);
+            cmd
+        };
+        #[cfg(not(windows))]
+        let mut cmd = {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(&self.command);
+            cmd
+        };
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        cmd
+    }
+
+    /// Directory to run in: the project directory, else the session's `cwd`
+    fn working_dir(&self, input: &Value) -> Option<PathBuf> {
+        self.project_dir.clone().or_else(|| {
+            input
+                .get("cwd")
+                .and_then(Value::as_str)
+                .filter(|cwd| !cwd.is_empty())
+                .map(PathBuf::from)
+                .filter(|cwd| cwd.is_dir())
+        })
+    }
+}
+
+#[async_trait]
+impl HookCallback for CommandHook {
+    async fn call(
+        &self,
+        input: Value,
+        _tool_use_id: Option<String>,
+        _context: HookContext,
+    ) -> Result<HookOutput> {
+        let event = input
+            .get("hook_event_name")
+            .and_then(|name| serde_json::from_value::<HookEvent>(name.clone()).ok());
+
+        let mut cmd = self.shell_command();
+        if let Some(dir) = self.working_dir(&input) {
+            cmd.env("CLAUDE_PROJECT_DIR", &dir).current_dir(dir);
+        }
+        let mut child = cmd.spawn().map_err(|e| {
+            ClaudeError::hook(format!(
+                "Failed to run hook command '{}': {e}",
+                self.command
+            ))
+        })?;
+
+        let payload = serde_json::to_vec(&input)?;
+        let mut stdin = child.stdin.take();
+        let write_input = async move {
+            if let Some(ref mut stdin) = stdin {
+                // Commands that ignore stdin may close it early
+                let _ = stdin.write_all(&payload).await;
+            }
+        };
+
+        let output = match tokio::time::timeout(self.timeout, async {
+            let ((), output) = tokio::join!(write_input, child.wait_with_output());
+            output
+        })
+        .await
+        {
+            Ok(output) => output?,
+            Err(_elapsed) => {
+                tracing::warn!(
+                    command = %self.command,
+                    timeout_secs = self.timeout.as_secs(),
+                    "Hook command timed out, continuing with default output"
+                );
+                return Ok(HookOutput::default());
+            }
+        };
+
+        Ok(interpret_output(
+            &self.command,
+            event,
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+        ))
+    }
+}
+
+/// Translate a command's exit code and output into a `HookOutput`
+fn interpret_output(
+    command: &str,
+    event: Option<HookEvent>,
+    exit_code: Option<i32>,
+    stdout: &str,
+    stderr: &str,
+) -> HookOutput {
+    let stdout = stdout.trim();
+    let stderr = stderr.trim();
+
+    match exit_code {
+        Some(0) => {
+            if stdout.starts_with('{') {
+                match serde_json::from_str::<HookOutput>(stdout) {
+                    Ok(output) => return output,
+                    Err(e) => {
+                        tracing::warn!(command = %command, error = %e, "Hook command printed invalid JSON output");
+                    }
+                }
+            }
+            match event {
+                Some(event) if !stdout.is_empty() && STDOUT_CONTEXT_EVENTS.contains(&event) => {
+                    ContextHookOutput::with_context(stdout).into_hook_output(event)
+                }
+                _ => HookOutput::default(),
+            }
+        }
+        Some(BLOCKING_EXIT_CODE) => {
+            let reason = if stderr.is_empty() {
+                format!("Blocked by hook command '{command}'")
+            } else {
+                stderr.to_string()
+            };
+            match event {
+                Some(HookEvent::PreToolUse) => {
+                    PreToolUseHookOutput::deny(reason).into_hook_output(HookEvent::PreToolUse)
+                }
+                Some(event) if BLOCKING_EVENTS.contains(&event) => HookOutput::block(reason),
+                _ => HookOutput::default().with_system_message(reason),
+            }
+        }
+        code => {
+            tracing::warn!(
+                command = %command,
+                exit_code = ?code,
+                stderr = %stderr,
+                "Hook command failed, continuing"
+            );
+            HookOutput::default()
+        }
+    }
+}
+
+// ============================================================================
+// Settings Loader
+// ============================================================================
+
+/// Path of the settings file for a setting source
+///
+/// `User` is `~/.claude/settings.json`, `Project` is
+/// `<project>/.claude/settings.json` and `Local` is
+/// `<project>/.claude/settings.local.json`. Returns `None` for `User` if the
+/// home directory is unknown.
+#[must_use]
+pub fn settings_path(source: SettingSource, project_dir: &Path) -> Option<PathBuf> {
+    match source {
+        SettingSource::User => dirs::home_dir().map(|home| home.join(".claude/settings.json")),
+        SettingSource::Project => Some(project_dir.join(".claude/settings.json")),
+        SettingSource::Local => Some(project_dir.join(".claude/settings.local.json")),
+    }
+}
+
+/// Load the command hooks of the settings files for `sources`, in order
+///
+/// Missing files are skipped. Commands run in `project_dir`.
+///
+/// # Errors
+///
+/// Returns an error if a settings file cannot be read or its `hooks` section
+/// is malformed.
+pub async fn load_settings_hooks(
+    sources: &[SettingSource],
+    project_dir: &Path,
+) -> Result<HashMap<HookEvent, Vec<HookMatcher>>> {
+    let mut hooks: HashMap<HookEvent, Vec<HookMatcher>> = HashMap::new();
+    for path in sources
+        .iter()
+        .filter_map(|source| settings_path(*source, project_dir))
+    {
+        for (event, matchers) in load_settings_file(&path, Some(project_dir)).await? {
+            hooks.entry(event).or_default().extend(matchers);
+        }
+    }
+    Ok(hooks)
+}
+
+/// Load the command hooks of one settings file (empty if it does not exist)
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, is not JSON, or its `hooks`
+/// section is malformed.
+pub async fn load_settings_file(
+    path: &Path,
+    project_dir: Option<&Path>,
+) -> Result<HashMap<HookEvent, Vec<HookMatcher>>> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let settings: Value = serde_json::from_str(&content).map_err(|e| {
+        ClaudeError::invalid_config(format!("Invalid settings file {}: {e}", path.display()))
+    })?;
+    hooks_from_settings(&settings, project_dir)
+        .map_err(|e| ClaudeError::invalid_config(format!("{}: {e}", path.display())))
+}
+
+/// Build hook matchers from the `hooks` section of a parsed settings file
+///
+/// Unknown events and non-`command` hook types are skipped with a warning.
+/// Each matcher's timeout is the longest timeout of its commands.
+///
+/// # Errors
+///
+/// Returns `ClaudeError::InvalidConfig` if the `hooks` section is malformed.
+pub fn hooks_from_settings(
+    settings: &Value,
+    project_dir: Option<&Path>,
+) -> Result<HashMap<HookEvent, Vec<HookMatcher>>> {
+    let mut hooks: HashMap<HookEvent, Vec<HookMatcher>> = HashMap::new();
+    let Some(section) = settings.get("hooks") else {
+        return Ok(hooks);
+    };
+    let section = section
+        .as_object()
+        .ok_or_else(|| ClaudeError::invalid_config("'hooks' must be an object"))?;
+
+    for (name, entries) in section {
+        let Ok(event) = serde_json::from_value::<HookEvent>(Value::String(name.clone())) else {
+            tracing::warn!(event = %name, "Skipping hooks for unknown event");
+            continue;
+        };
+        let entries = entries
+            .as_array()
+            .ok_or_else(|| ClaudeError::invalid_config(format!("hooks.{name} must be an array")))?;
+
+        for entry in entries {
+            if let Some(matcher) = matcher_from_settings(name, entry, project_dir)? {
+                hooks.entry(event).or_default().push(matcher);
+            }
+        }
+    }
+    Ok(hooks)
+}
+
+/// Build one matcher (`None` if it has no command hooks)
+fn matcher_from_settings(
+    event: &str,
+    entry: &Value,
+    project_dir: Option<&Path>,
+) -> Result<Option<HookMatcher>> {
+    let invalid = |what: &str| ClaudeError::invalid_config(format!("hooks.{event}: {what}"));
+
+    let pattern = match entry.get("matcher") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(pattern)) if pattern.is_empty() => None,
+        Some(Value::String(pattern)) => Some(pattern.clone()),
+        Some(_) => return Err(invalid("matcher must be a string")),
+    };
+    let commands = entry
+        .get("hooks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("each entry needs a 'hooks' array"))?;
+
+    let mut matcher = HookMatcher {
+        matcher: pattern,
+        hooks: Vec::new(),
+        timeout: None,
+    };
+    for command in commands {
+        let hook_type = command.get("type").and_then(Value::as_str);
+        if hook_type != Some("command") {
+            tracing::warn!(event = %event, hook_type = ?hook_type, "Skipping unsupported hook type");
+            continue;
+        }
+        let line = command
+            .get("command")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("command hooks need a 'command' string"))?;
+        let timeout = match command.get("timeout") {
+            None | Some(Value::Null) => CommandHook::DEFAULT_TIMEOUT,
+            Some(value) => Duration::from_secs(
+                value
+                    .as_u64()
+                    .ok_or_else(|| invalid("timeout must be a number of seconds"))?,
+            ),
+        };
+
+        let mut hook = CommandHook::new(line).timeout(timeout);
+        if let Some(dir) = project_dir {
+            hook = hook.project_dir(dir);
+        }
+        matcher.timeout = matcher.timeout.max(Some(timeout));
+        matcher.hooks.push(std::sync::Arc::new(hook));
+    }
+
+    Ok((!matcher.hooks.is_empty()).then_some(matcher))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::HookManager;
+    use crate::types::{HookDecision, PermissionBehavior};
+    use serde_json::json;
+
+    #[test]
+    fn test_interpret_output() {
+        let json_output = interpret_output(
+            "hook",
+            Some(HookEvent::Stop),
+            Some(0),
+            r#"{"decision": "block", "reason": "keep going", "suppressOutput": true}"#,
+            "",
+        );
+        assert_eq!(json_output.decision, Some(HookDecision::Block));
+        assert_eq!(json_output.reason.as_deref(), Some("keep going"));
+        assert_eq!(json_output.suppress_output, Some(true));
+
+        let context = interpret_output(
+            "hook",
+            Some(HookEvent::SessionStart),
+            Some(0),
+            "branch: main\n",
+            "",
+        );
+        assert_eq!(context.additional_context(), Some("branch: main"));
+        assert!(
+            interpret_output("hook", Some(HookEvent::PostToolUse), Some(0), "ok", "").is_empty()
+        );
+
+        let denied = interpret_output(
+            "hook",
+            Some(HookEvent::PreToolUse),
+            Some(2),
+            "",
+            "no force pushes\n",
+        );
+        assert_eq!(denied.permission_decision(), Some(PermissionBehavior::Deny));
+        assert_eq!(
+            denied.hook_specific_output.unwrap()["permissionDecisionReason"],
+            "no force pushes"
+        );
+
+        let blocked = interpret_output("hook", Some(HookEvent::Stop), Some(2), "", "tests fail");
+        assert_eq!(blocked.decision, Some(HookDecision::Block));
+        assert_eq!(blocked.reason.as_deref(), Some("tests fail"));
+
+        let shown = interpret_output("hook", Some(HookEvent::Notification), Some(2), "", "hi");
+        assert!(shown.decision.is_none());
+        assert_eq!(shown.system_message.as_deref(), Some("hi"));
+
+        assert!(
+            interpret_output("hook", Some(HookEvent::PreToolUse), Some(1), "", "oops").is_empty()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_hook_runs_shell_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = json!({
+            "hook_event_name": "PreToolUse",
+            "session_id": "s1",
+            "transcript_path": "",
+            "cwd": "/",
+            "tool_name": "Bash",
+            "tool_input": {"command": "git push --force"}
+        });
+
+        // The input arrives on stdin; CLAUDE_PROJECT_DIR and the working directory are set
+        let hook = CommandHook::new(
+            r#"grep -q -- '--force' && [ "$CLAUDE_PROJECT_DIR" = "$(pwd)" ] && echo "force push from $(basename "$PWD")" >&2 && exit 2; exit 0"#,
+        )
+        .project_dir(dir.path());
+        let output = hook
+            .call(input.clone(), None, HookContext::default())
+            .await
+            .unwrap();
+        let name = dir.path().file_name().unwrap().to_string_lossy();
+        assert_eq!(
+            output.hook_specific_output.unwrap()["permissionDecisionReason"],
+            format!("force push from {name}")
+        );
+
+        let slow = CommandHook::new("sleep 5").timeout(Duration::from_millis(100));
+        assert!(
+            slow.call(input.clone(), None, HookContext::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_settings_matchers_are_regexes() {
+        let settings = json!({
+            "hooks": {
+                "PreToolUse": [
+                    {"matcher": "mcp__.*", "hooks": [{"type": "command", "command": "echo '{\"systemMessage\": \"mcp\"}'"}]},
+                    {"matcher": "Edit|Write", "hooks": [{"type": "command", "command": "echo '{\"systemMessage\": \"edit\"}'"}]}
+                ]
+            }
+        });
+        let manager = HookManager::from_hooks_config(hooks_from_settings(&settings, None).unwrap());
+
+        let run = |tool: &str| {
+            let input = json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s1",
+                "transcript_path": "",
+                "cwd": "/",
+                "tool_name": tool,
+                "tool_input": {}
+            });
+            manager.invoke(
+                HookEvent::PreToolUse,
+                input,
+                Some(tool.to_string()),
+                HookContext::default(),
+            )
+        };
+        assert_eq!(
+            run("mcp__github__create_issue")
+                .await
+                .unwrap()
+                .system_message
+                .as_deref(),
+            Some("mcp")
+        );
+        assert_eq!(
+            run("Write").await.unwrap().system_message.as_deref(),
+            Some("edit")
+        );
+        assert!(run("Bash").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_settings_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".claude")).unwrap();
+        std::fs::write(
+            dir.path().join(".claude/settings.json"),
+            json!({
+                "permissions": {"allow": ["Read"]},
+                "hooks": {
+                    "PreToolUse": [{
+                        "matcher": "Bash",
+                        "hooks": [
+                            {"type": "command", "command": "./check.sh", "timeout": 90},
+                            {"type": "command", "command": "./audit.sh"}
+                        ]
+                    }],
+                    "SessionStart": [{"hooks": [{"type": "command", "command": "git status"}]}],
+                    "FutureEvent": [{"hooks": [{"type": "command", "command": "true"}]}]
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join(".claude/settings.local.json"),
+            json!({"hooks": {"PreToolUse": [{"matcher": "", "hooks": [{"type": "prompt", "prompt": "x"}, {"type": "command", "command": "./local.sh"}]}]}})
+                .to_string(),
+        )
+        .unwrap();
+
+        let hooks =
+            load_settings_hooks(&[SettingSource::Project, SettingSource::Local], dir.path())
+                .await
+                .unwrap();
+
+        assert_eq!(hooks.len(), 2);
+        let pre = &hooks[&HookEvent::PreToolUse];
+        assert_eq!(pre.len(), 2);
+        assert_eq!(pre[0].matcher.as_deref(), Some("Bash"));
+        assert_eq!(pre[0].hooks.len(), 2);
+        assert_eq!(pre[0].timeout, Some(Duration::from_secs(90)));
+        assert_eq!(pre[1].matcher, None);
+        assert_eq!(pre[1].hooks.len(), 1);
+        assert_eq!(hooks[&HookEvent::SessionStart][0].matcher, None);
+
+        assert!(
+            hooks_from_settings(
+                &json!({"hooks": {"Stop": [{"hooks": [{"type": "command"}]}]}}),
+                None
+            )
+            .is_err()
+        );
+        assert!(
+            load_settings_file(&dir.path().join("missing.json"), None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -64,5 +64,6 @@
 pub mod protocol;
 
 pub use protocol::{
-    ControlMessage, ControlRequest, ControlResponse, InitRequest, InitResponse, ProtocolHandler,
+    ControlMessage, ControlRequest, ControlResponse, InboundControlRequest, InitRequest,
+    InitResponse, ProtocolHandler,
 };
@@ -85,7 +85,9 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use tokio::sync::{Mutex, mpsc, oneshot};
 
 use crate::error::{ClaudeError, Result};
-use crate::types::{HookEvent, PermissionRequest, PermissionResult, RequestId};
+use crate::types::{
+    HookEvent, PermissionRequest, PermissionResult, PermissionUpdate, RequestId, UserInput,
+};
 
 /// Control message envelope for all protocol messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,8 +122,8 @@ pub enum ControlRequest {
     SendMessage {
         /// Unique request identifier
         id: RequestId,
-        /// Message content to send
-        content: String,
+        /// Message content to send (text or content blocks)
+        content: UserInput,
     },
     /// Respond to a hook invocation
     #[serde(rename = "hook_response")]
@@ -178,6 +180,113 @@ pub enum ControlRequest {
         /// UUID of the user message checkpoint to rewind to
         user_message_uuid: String,
     },
+    /// Initialize the session, registering SDK hook callbacks with the CLI
+    #[serde(rename = "initialize")]
+    Initialize {
+        /// Unique request identifier
+        id: RequestId,
+        /// Hook registrations by event name (`{"PreToolUse": [{"matcher", "hookCallbackIds"}]}`)
+        hooks: Option<serde_json::Value>,
+    },
+}
+
+impl ControlRequest {
+    /// Request identifier used to correlate the CLI's `control_response`
+    #[must_use]
+    pub fn id(&self) -> &RequestId {
+        match self {
+            Self::Interrupt { id }
+            | Self::SendMessage { id, .. }
+            | Self::HookResponse { id, .. }
+            | Self::PermissionResponse { id, .. }
+            | Self::SetModel { id, .. }
+            | Self::SetPermissionMode { id, .. }
+            | Self::SetMaxThinkingTokens { id, .. }
+            | Self::RewindFiles { id, .. }
+            | Self::Initialize { id, .. } => id,
+        }
+    }
+
+    /// Request type name (the `subtype` used on the wire)
+    #[must_use]
+    pub fn request_type(&self) -> &'static str {
+        match self {
+            Self::Interrupt { .. } => "interrupt",
+            Self::SendMessage { .. } => "send_message",
+            Self::HookResponse { .. } => "hook_response",
+            Self::PermissionResponse { .. } => "permission_response",
+            Self::SetModel { .. } => "set_model",
+            Self::SetPermissionMode { .. } => "set_permission_mode",
+            Self::SetMaxThinkingTokens { .. } => "set_max_thinking_tokens",
+            Self::RewindFiles { .. } => "rewind_files",
+            Self::Initialize { .. } => "initialize",
+        }
+    }
+}
+
+/// Control request sent by the CLI to the SDK
+///
+/// The CLI sends these as
+/// `{"type": "control_request", "request_id": "...", "request": {"subtype": "..."}}`
+/// and blocks the agent loop until the SDK answers with a `control_response`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "subtype", rename_all = "snake_case")]
+pub enum InboundControlRequest {
+    /// Invoke a hook callback registered during `initialize`
+    HookCallback {
+        /// Callback ID assigned at registration (e.g. `hook_0`)
+        callback_id: String,
+        /// Hook input (`hook_event_name`, `tool_name`, `tool_input`, ...)
+        input: serde_json::Value,
+        /// Tool use the hook fires for, if any
+        #[serde(default)]
+        tool_use_id: Option<String>,
+    },
+    /// Ask whether a tool may run (sent with `--permission-prompt-tool stdio`)
+    CanUseTool {
+        /// Name of the tool being requested
+        tool_name: String,
+        /// Tool input parameters
+        input: serde_json::Value,
+        /// Permission updates the CLI suggests, such as "always allow"
+        #[serde(default, deserialize_with = "deserialize_suggestions")]
+        permission_suggestions: Vec<PermissionUpdate>,
+        /// Path that triggered the permission check, if any
+        #[serde(default)]
+        blocked_path: Option<String>,
+        /// Tool use being checked, if known
+        #[serde(default)]
+        tool_use_id: Option<String>,
+    },
+    /// JSON-RPC message for an in-process SDK MCP server
+    McpMessage {
+        /// Name of the SDK MCP server in `--mcp-config`
+        server_name: String,
+        /// JSON-RPC request or notification
+        message: serde_json::Value,
+    },
+}
+
+/// Deserialize permission suggestions, skipping update types this SDK does
+/// not know so that a new suggestion kind never fails the whole request
+fn deserialize_suggestions<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<PermissionUpdate>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = Option::<Vec<serde_json::Value>>::deserialize(deserializer)?;
+    Ok(values
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|value| match serde_json::from_value(value) {
+            Ok(update) => Some(update),
+            Err(e) => {
+                tracing::debug!(error = %e, "Skipping unrecognized permission suggestion");
+                None
+            }
+        })
+        .collect())
 }
 
 /// Response from CLI to SDK
@@ -393,17 +502,115 @@ impl ProtocolHandler {
         Ok(response_rx)
     }
 
+    /// Stop waiting for a response (e.g. after a timeout)
+    pub async fn cancel_request(&self, id: &RequestId) {
+        self.pending_requests.lock().await.remove(id);
+    }
+
+    /// Drop all pending requests
+    ///
+    /// Waiters observe a closed channel; used when the transport goes away.
+    pub async fn clear_pending(&self) {
+        self.pending_requests.lock().await.clear();
+    }
+
     /// Extract request ID from a control request
     fn get_request_id(request: &ControlRequest) -> RequestId {
-        match request {
-            ControlRequest::Interrupt { id }
-            | ControlRequest::SendMessage { id, .. }
-            | ControlRequest::HookResponse { id, .. }
-            | ControlRequest::PermissionResponse { id, .. }
-            | ControlRequest::SetModel { id, .. }
-            | ControlRequest::SetPermissionMode { id, .. }
-            | ControlRequest::SetMaxThinkingTokens { id, .. }
-            | ControlRequest::RewindFiles { id, .. } => id.clone(),
+        request.id().clone()
+    }
+
+    /// Parse a CLI `control_request` line into an [`InboundControlRequest`]
+    ///
+    /// Returns `None` if the value is not a `control_request` with a `request_id`.
+    /// The inner result is an error for unsupported subtypes or malformed
+    /// requests, which should still be answered with an error response.
+    #[must_use]
+    pub fn parse_control_request(
+        value: &serde_json::Value,
+    ) -> Option<(RequestId, Result<InboundControlRequest>)> {
+        if value.get("type").and_then(serde_json::Value::as_str) != Some("control_request") {
+            return None;
+        }
+        let id = RequestId::new(value.get("request_id")?.as_str()?);
+        let request = value.get("request").cloned().unwrap_or_default();
+        let subtype = request
+            .get("subtype")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or("<missing>")
+            .to_string();
+
+        let parsed = serde_json::from_value(request).map_err(|e| {
+            ClaudeError::control_protocol(format!(
+                "Unsupported or malformed control request '{subtype}': {e}"
+            ))
+        });
+        Some((id, parsed))
+    }
+
+    /// Build a success `control_response` envelope answering a CLI request
+    #[must_use]
+    pub fn control_success(
+        request_id: &RequestId,
+        response: &serde_json::Value,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "type": "control_response",
+            "response": {
+                "subtype": "success",
+                "request_id": request_id.as_str(),
+                "response": response
+            }
+        })
+    }
+
+    /// Build an error `control_response` envelope answering a CLI request
+    #[must_use]
+    pub fn control_error(request_id: &RequestId, message: impl Into<String>) -> serde_json::Value {
+        serde_json::json!({
+            "type": "control_response",
+            "response": {
+                "subtype": "error",
+                "request_id": request_id.as_str(),
+                "error": message.into()
+            }
+        })
+    }
+
+    /// Parse a CLI `control_response` line into a [`ControlResponse`]
+    ///
+    /// The CLI acknowledges control requests with:
+    /// ```json
+    /// {"type": "control_response", "response": {"subtype": "success", "request_id": "...", "response": {}}}
+    /// {"type": "control_response", "response": {"subtype": "error", "request_id": "...", "error": "..."}}
+    /// ```
+    ///
+    /// Returns `None` if the value is not a well-formed `control_response`.
+    #[must_use]
+    pub fn parse_control_response(value: &serde_json::Value) -> Option<ControlResponse> {
+        if value.get("type").and_then(serde_json::Value::as_str) != Some("control_response") {
+            return None;
+        }
+        let response = value.get("response")?;
+        let id = RequestId::new(response.get("request_id")?.as_str()?);
+
+        match response
+            .get("subtype")
+            .and_then(serde_json::Value::as_str)?
+        {
+            "success" => Some(ControlResponse::Success {
+                id,
+                data: response.get("response").cloned(),
+            }),
+            "error" => Some(ControlResponse::Error {
+                id,
+                message: response
+                    .get("error")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("Unknown error")
+                    .to_string(),
+                code: None,
+            }),
+            _ => None,
         }
     }
 
@@ -446,7 +653,7 @@ impl ProtocolHandler {
 
     /// Create send message request
     #[must_use]
-    pub fn create_send_message_request(&self, content: impl Into<String>) -> ControlRequest {
+    pub fn create_send_message_request(&self, content: impl Into<UserInput>) -> ControlRequest {
         ControlRequest::SendMessage {
             id: self.next_id(),
             content: content.into(),
@@ -511,6 +718,15 @@ impl ProtocolHandler {
         }
     }
 
+    /// Create an initialize request carrying hook registrations
+    #[must_use]
+    pub fn create_initialize_request(&self, hooks: Option<serde_json::Value>) -> ControlRequest {
+        ControlRequest::Initialize {
+            id: self.next_id(),
+            hooks,
+        }
+    }
+
     /// Create a rewind files request
     ///
     /// Rewinds files to their state at the specified checkpoint.
@@ -768,6 +984,171 @@ mod tests {
         assert!(handler.serialize_message(&msg).is_ok());
     }
 
+    #[test]
+    fn test_parse_control_response() {
+        let success = serde_json::json!({
+            "type": "control_response",
+            "response": {"subtype": "success", "request_id": "req_1", "response": {"mode": "plan"}}
+        });
+        match ProtocolHandler::parse_control_response(&success) {
+            Some(ControlResponse::Success { id, data }) => {
+                assert_eq!(id.as_str(), "req_1");
+                assert_eq!(data.unwrap()["mode"], "plan");
+            }
+            other => panic!("expected success, got {other:?}"),
+        }
+
+        let error = serde_json::json!({
+            "type": "control_response",
+            "response": {"subtype": "error", "request_id": "req_2", "error": "bad mode"}
+        });
+        match ProtocolHandler::parse_control_response(&error) {
+            Some(ControlResponse::Error { id, message, .. }) => {
+                assert_eq!(id.as_str(), "req_2");
+                assert_eq!(message, "bad mode");
+            }
+            other => panic!("expected error, got {other:?}"),
+        }
+
+        let not_response = serde_json::json!({"type": "assistant"});
+        assert!(ProtocolHandler::parse_control_response(&not_response).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pending_request_resolved_by_response() {
+        let handler = ProtocolHandler::new();
+        handler.set_initialized(true);
+
+        let request = handler.create_set_model_request(Some("haiku".to_string()));
+        let id = request.id().clone();
+        assert_eq!(request.request_type(), "set_model");
+        let rx = handler.send_request(request).await.unwrap();
+
+        handler
+            .handle_response(ControlResponse::Success { id, data: None })
+            .await
+            .unwrap();
+        assert!(matches!(rx.await, Ok(ControlResponse::Success { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_clear_pending_closes_waiters() {
+        let handler = ProtocolHandler::new();
+        handler.set_initialized(true);
+
+        let rx = handler
+            .send_request(handler.create_interrupt_request())
+            .await
+            .unwrap();
+        handler.clear_pending().await;
+        assert!(rx.await.is_err());
+    }
+
+    #[test]
+    fn test_parse_hook_callback_request() {
+        let value = serde_json::json!({
+            "type": "control_request",
+            "request_id": "cli-1",
+            "request": {
+                "subtype": "hook_callback",
+                "callback_id": "hook_0",
+                "input": {"hook_event_name": "PreToolUse", "tool_name": "Bash"},
+                "tool_use_id": "toolu_1"
+            }
+        });
+        let (id, request) = ProtocolHandler::parse_control_request(&value).unwrap();
+        assert_eq!(id.as_str(), "cli-1");
+        match request.unwrap() {
+            InboundControlRequest::HookCallback {
+                callback_id,
+                input,
+                tool_use_id,
+            } => {
+                assert_eq!(callback_id, "hook_0");
+                assert_eq!(input["tool_name"], "Bash");
+                assert_eq!(tool_use_id.as_deref(), Some("toolu_1"));
+            }
+            other => {
+                panic!("Expected HookCallback, got {other:?}")
+            }
+        }
+
+        let can_use_tool = serde_json::json!({
+            "type": "control_request",
+            "request_id": "cli-3",
+            "request": {
+                "subtype": "can_use_tool",
+                "tool_name": "Bash",
+                "input": {"command": "touch probe.txt"},
+                "permission_suggestions": [
+                    {
+                        "type": "addRules",
+                        "rules": [{"toolName": "Bash", "ruleContent": "touch probe.txt"}],
+                        "behavior": "allow",
+                        "destination": "localSettings"
+                    },
+                    {"type": "someFutureUpdate"},
+                    {"type": "setMode", "mode": "acceptEdits", "destination": "session"}
+                ],
+                "blocked_path": "/tmp/probe.txt",
+                "tool_use_id": "toolu_2"
+            }
+        });
+        let (_, request) = ProtocolHandler::parse_control_request(&can_use_tool).unwrap();
+        match request.unwrap() {
+            InboundControlRequest::CanUseTool {
+                tool_name,
+                permission_suggestions,
+                blocked_path,
+                ..
+            } => {
+                assert_eq!(tool_name, "Bash");
+                assert_eq!(blocked_path.as_deref(), Some("/tmp/probe.txt"));
+                // The unknown suggestion is skipped, the rest survive
+                assert_eq!(permission_suggestions.len(), 2);
+                match &permission_suggestions[0] {
+                    crate::types::PermissionUpdate::AddRules {
+                        rules, behavior, ..
+                    } => {
+                        let rules = rules.as_ref().unwrap();
+                        assert_eq!(rules[0].tool_name, "Bash");
+                        assert_eq!(rules[0].rule_content.as_deref(), Some("touch probe.txt"));
+                        assert_eq!(*behavior, Some(crate::types::PermissionBehavior::Allow));
+                    }
+                    other => panic!("Expected AddRules, got {other:?}"),
+                }
+            }
+            other => {
+                panic!("Expected CanUseTool, got {other:?}")
+            }
+        }
+
+        let unsupported = serde_json::json!({
+            "type": "control_request",
+            "request_id": "cli-2",
+            "request": {"subtype": "something_new"}
+        });
+        let (_, request) = ProtocolHandler::parse_control_request(&unsupported).unwrap();
+        assert!(request.is_err());
+
+        let not_request = serde_json::json!({"type": "control_response"});
+        assert!(ProtocolHandler::parse_control_request(&not_request).is_none());
+    }
+
+    #[test]
+    fn test_control_response_envelopes() {
+        let id = RequestId::new("cli-1");
+        let ok = ProtocolHandler::control_success(&id, &serde_json::json!({"decision": "block"}));
+        assert_eq!(ok["type"], "control_response");
+        assert_eq!(ok["response"]["subtype"], "success");
+        assert_eq!(ok["response"]["request_id"], "cli-1");
+        assert_eq!(ok["response"]["response"]["decision"], "block");
+
+        let err = ProtocolHandler::control_error(&id, "boom");
+        assert_eq!(err["response"]["subtype"], "error");
+        assert_eq!(err["response"]["error"], "boom");
+    }
+
     #[test]
     fn test_get_request_id() {
         let interrupt = ControlRequest::Interrupt {
@@ -777,7 +1158,7 @@ mod tests {
 
         let send_msg = ControlRequest::SendMessage {
             id: RequestId::new("id2"),
-            content: "test".to_string(),
+            content: "test".into(),
         };
         assert_eq!(ProtocolHandler::get_request_id(&send_msg).as_str(), "id2");
 
@@ -69,6 +69,10 @@ pub enum ClaudeError {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
+    /// Invalid message content (unsupported media type, size limit exceeded, ...)
+    #[error("Invalid content: {0}")]
+    InvalidContent(String),
+
     /// CLI version is below minimum required
     #[error(
         "Claude Code version {found} is below minimum required version {minimum}. Update with: npm update -g @anthropic-ai/claude-code"
@@ -89,6 +93,15 @@ pub enum ClaudeError {
         request_type: String,
     },
 
+    /// Control request rejected by the CLI
+    #[error("Control request {request_type} failed: {message}")]
+    ControlRequestFailed {
+        /// Type of request that failed
+        request_type: String,
+        /// Error message reported by the CLI
+        message: String,
+    },
+
     /// Client is not connected
     #[error("Not connected. Call connect() or create client before operations.")]
     NotConnected,
@@ -113,6 +126,33 @@ pub enum ClaudeError {
     /// Network error during API request
     #[error("Network error: {0}")]
     NetworkError(String),
+
+    /// Session store error
+    #[error("Session store error: {0}")]
+    Storage(String),
+
+    /// Structured output missing or not matching the requested type
+    #[error("Structured output is not a valid {type_name}: {message}")]
+    StructuredOutput {
+        /// Rust type the output was deserialized into
+        type_name: String,
+        /// What went wrong
+        message: String,
+        /// Output as returned by the CLI (`null` if there was none)
+        raw: serde_json::Value,
+    },
+
+    /// Structured output still invalid after every attempt allowed by the
+    /// `StructuredOutputPolicy`
+    #[error(
+        "Structured output invalid after {} attempt(s): {}",
+        attempts.len(),
+        crate::structured_output::last_violations(attempts)
+    )]
+    StructuredOutputInvalid {
+        /// Every attempt with its output and violations
+        attempts: Vec<crate::structured_output::StructuredOutputAttempt>,
+    },
 }
 
 /// Result type alias for Claude SDK operations
@@ -204,6 +244,11 @@ impl ClaudeError {
         Self::InvalidConfig(msg.into())
     }
 
+    /// Create an invalid content error
+    pub fn invalid_content(msg: impl Into<String>) -> Self {
+        Self::InvalidContent(msg.into())
+    }
+
     /// Create a CLI version too old error
     pub fn cli_version_too_old(found: impl Into<String>, minimum: impl Into<String>) -> Self {
         Self::CliVersionTooOld {
@@ -220,6 +265,17 @@ impl ClaudeError {
         }
     }
 
+    /// Create a control request failed error
+    pub fn control_request_failed(
+        request_type: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::ControlRequestFailed {
+            request_type: request_type.into(),
+            message: message.into(),
+        }
+    }
+
     /// Create a not connected error
     #[must_use]
     pub fn not_connected() -> Self {
@@ -249,4 +305,30 @@ impl ClaudeError {
     pub fn network(msg: impl Into<String>) -> Self {
         Self::NetworkError(msg.into())
     }
+
+    /// Create a session store error
+    pub fn storage(msg: impl Into<String>) -> Self {
+        Self::Storage(msg.into())
+    }
+
+    /// Create a structured output error
+    pub fn structured_output(
+        type_name: impl Into<String>,
+        message: impl Into<String>,
+        raw: serde_json::Value,
+    ) -> Self {
+        Self::StructuredOutput {
+            type_name: type_name.into(),
+            message: message.into(),
+            raw,
+        }
+    }
+
+    /// Create a structured output invalid error
+    #[must_use]
+    pub fn structured_output_invalid(
+        attempts: Vec<crate::structured_output::StructuredOutputAttempt>,
+    ) -> Self {
+        Self::StructuredOutputInvalid { attempts }
+    }
 }
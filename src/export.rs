@@ -0,0 +1,795 @@
+//! Conversation export to Markdown and HTML
+//!
+//! Renders a sequence of [`Message`]s (collected from a client, a
+//! [`query()`](crate::query()) stream, or a [`Transcript`](crate::transcript::Transcript))
+//! as a report that can be attached to a pull request or archived:
+//!
+//! - user prompts and assistant text in order
+//! - thinking blocks, collapsed
+//! - tool calls with a one-line summary, their input, and their result
+//! - subagent conversations nested under the `Task` call that started them
+//!   (via `parent_tool_use_id`)
+//! - a footer with duration, cost and token usage of every `Result`
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::export::{ExportOptions, Exporter};
+//! use anthropic_agent_sdk::transcript::TranscriptStore;
+//! use anthropic_agent_sdk::SessionId;
+//!
+//! # fn main() -> anthropic_agent_sdk::Result<()> {
+//! let transcript = TranscriptStore::new().load(&SessionId::new("abc123"))?;
+//! let exporter = Exporter::new(ExportOptions {
+//!     title: Some("Agent run".to_string()),
+//!     ..ExportOptions::default()
+//! });
+//! std::fs::write("run.md", exporter.markdown(transcript.messages()))?;
+//! std::fs::write("run.html", exporter.html(transcript.messages()))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use crate::types::{BuiltinToolInput, ContentBlock, ContentValue, Message, Usage, UserContent};
+
+/// Options for [`Exporter`]
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Report title (defaults to "Conversation")
+    pub title: Option<String>,
+    /// Render thinking blocks (collapsed)
+    pub include_thinking: bool,
+    /// Render tool results under their tool calls
+    pub include_tool_results: bool,
+    /// Truncate tool inputs and results longer than this many characters
+    pub max_tool_output_chars: Option<usize>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            include_thinking: true,
+            include_tool_results: true,
+            max_tool_output_chars: Some(4000),
+        }
+    }
+}
+
+/// Renders conversations to Markdown or standalone HTML
+#[derive(Debug, Clone, Default)]
+pub struct Exporter {
+    options: ExportOptions,
+}
+
+/// Render messages to Markdown with default options
+pub fn to_markdown<'a>(messages: impl IntoIterator<Item = &'a Message>) -> String {
+    Exporter::default().markdown(messages)
+}
+
+/// Render messages to a standalone HTML page with default options
+pub fn to_html<'a>(messages: impl IntoIterator<Item = &'a Message>) -> String {
+    Exporter::default().html(messages)
+}
+
+/// A rendered piece of the conversation, scoped to the subagent that produced it
+#[derive(Debug)]
+enum Item {
+    User(String),
+    Text(String),
+    Thinking(String),
+    ToolCall {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+}
+
+/// Result of a tool call
+#[derive(Debug)]
+struct ToolResult {
+    text: String,
+    is_error: bool,
+}
+
+/// Per-turn metrics from a `Result` message
+#[derive(Debug)]
+struct TurnSummary {
+    subtype: String,
+    is_error: bool,
+    num_turns: u32,
+    duration_ms: u64,
+    total_cost_usd: Option<f64>,
+    usage: Option<Usage>,
+}
+
+/// Messages folded into items, tool results and turn summaries
+#[derive(Debug, Default)]
+struct Conversation {
+    /// Items with the `parent_tool_use_id` of the message they came from
+    items: Vec<(Option<String>, Item)>,
+    results: HashMap<String, ToolResult>,
+    turns: Vec<TurnSummary>,
+}
+
+impl Conversation {
+    fn collect<'a>(messages: impl IntoIterator<Item = &'a Message>) -> Self {
+        let mut conversation = Self::default();
+        for message in messages {
+            match message {
+                Message::User {
+                    message,
+                    parent_tool_use_id,
+                    ..
+                } => match &message.content {
+                    Some(UserContent::String(text)) => {
+                        conversation.push(parent_tool_use_id, Item::User(text.clone()));
+                    }
+                    Some(UserContent::Blocks(blocks)) => {
+                        for block in blocks {
+                            conversation.add_user_block(parent_tool_use_id, block);
+                        }
+                    }
+                    None => {}
+                },
+                Message::Assistant {
+                    message,
+                    parent_tool_use_id,
+                    ..
+                } => {
+                    for block in &message.content {
+                        conversation.add_assistant_block(parent_tool_use_id, block);
+                    }
+                }
+                Message::Result {
+                    subtype,
+                    is_error,
+                    num_turns,
+                    duration_ms,
+                    total_cost_usd,
+                    usage,
+                    ..
+                } => conversation.turns.push(TurnSummary {
+                    subtype: subtype.clone(),
+                    is_error: *is_error,
+                    num_turns: *num_turns,
+                    duration_ms: *duration_ms,
+                    total_cost_usd: *total_cost_usd,
+                    usage: usage.clone(),
+                }),
+                Message::System { .. } | Message::StreamEvent { .. } | Message::Unknown { .. } => {}
+            }
+        }
+        conversation
+    }
+
+    fn push(&mut self, parent: &Option<String>, item: Item) {
+        self.items.push((parent.clone(), item));
+    }
+
+    fn add_user_block(&mut self, parent: &Option<String>, block: &ContentBlock) {
+        match block {
+            ContentBlock::Text { text } => self.push(parent, Item::User(text.clone())),
+            ContentBlock::Image { .. } => self.push(parent, Item::User("[image]".to_string())),
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                self.results.insert(
+                    tool_use_id.clone(),
+                    ToolResult {
+                        text: content.as_ref().map(content_text).unwrap_or_default(),
+                        is_error: is_error.unwrap_or(false),
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn add_assistant_block(&mut self, parent: &Option<String>, block: &ContentBlock) {
+        match block {
+            ContentBlock::Text { text } => self.push(parent, Item::Text(text.clone())),
+            ContentBlock::Thinking { thinking, .. } => {
+                self.push(parent, Item::Thinking(thinking.clone()));
+            }
+            ContentBlock::ToolUse { id, name, input }
+            | ContentBlock::ServerToolUse { id, name, input } => self.push(
+                parent,
+                Item::ToolCall {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input: input.clone(),
+                },
+            ),
+            ContentBlock::WebSearchToolResult {
+                tool_use_id,
+                content,
+            } => {
+                self.results.insert(
+                    tool_use_id.clone(),
+                    ToolResult {
+                        text: serde_json::to_string_pretty(content).unwrap_or_default(),
+                        is_error: false,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Items produced directly in `scope` (`None` for the main conversation)
+    fn items_in<'a>(&'a self, scope: Option<&'a str>) -> impl Iterator<Item = &'a Item> + 'a {
+        self.items
+            .iter()
+            .filter(move |(parent, _)| parent.as_deref() == scope)
+            .map(|(_, item)| item)
+    }
+
+    fn has_items_in(&self, scope: &str) -> bool {
+        self.items_in(Some(scope)).next().is_some()
+    }
+}
+
+/// Text of a tool result's content
+fn content_text(content: &ContentValue) -> String {
+    match content {
+        ContentValue::String(text) => text.clone(),
+        ContentValue::Blocks(blocks) => blocks
+            .iter()
+            .map(|block| match block.get("text").and_then(|t| t.as_str()) {
+                Some(text) => text.to_string(),
+                None => format!(
+                    "[{}]",
+                    block
+                        .get("type")
+                        .and_then(|t| t.as_str())
+                        .unwrap_or("block")
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// One-line description of a tool call (command, path, pattern, ...)
+fn tool_summary(name: &str, input: &serde_json::Value) -> Option<String> {
+    let summary = match BuiltinToolInput::parse(name, input).ok()? {
+        BuiltinToolInput::Bash(input) => input.command,
+        BuiltinToolInput::Glob(input) => input.pattern,
+        BuiltinToolInput::Grep(input) => input.pattern,
+        BuiltinToolInput::WebFetch(input) => input.url,
+        BuiltinToolInput::WebSearch(input) => input.query,
+        BuiltinToolInput::Task(input) => format!("{} ({})", input.description, input.subagent_type),
+        other => other.file_path()?.to_string(),
+    };
+    // Keep the summary on one line
+    Some(summary.lines().next().unwrap_or_default().to_string())
+}
+
+impl Exporter {
+    /// Create an exporter
+    #[must_use]
+    pub fn new(options: ExportOptions) -> Self {
+        Self { options }
+    }
+
+    /// The export options
+    #[must_use]
+    pub fn options(&self) -> &ExportOptions {
+        &self.options
+    }
+
+    fn title(&self) -> &str {
+        self.options.title.as_deref().unwrap_or("Conversation")
+    }
+
+    fn truncate(&self, text: &str) -> String {
+        match self.options.max_tool_output_chars {
+            Some(max) if text.chars().count() > max => {
+                let kept: String = text.chars().take(max).collect();
+                let omitted = text.chars().count() - max;
+                format!("{kept}\n… ({omitted} more characters)")
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    fn tool_input_text(&self, input: &serde_json::Value) -> String {
+        self.truncate(&serde_json::to_string_pretty(input).unwrap_or_default())
+    }
+
+    // ------------------------------------------------------------------------
+    // Markdown
+    // ------------------------------------------------------------------------
+
+    /// Render messages to Markdown (GitHub flavored; collapsible sections use
+    /// `<details>`)
+    pub fn markdown<'a>(&self, messages: impl IntoIterator<Item = &'a Message>) -> String {
+        let conversation = Conversation::collect(messages);
+        let mut out = format!("# {}\n\n", self.title());
+        self.markdown_scope(&conversation, None, &mut out);
+        if !conversation.turns.is_empty() {
+            out.push_str("---\n\n");
+            markdown_footer(&conversation.turns, &mut out);
+        }
+        out
+    }
+
+    fn markdown_scope(&self, conversation: &Conversation, scope: Option<&str>, out: &mut String) {
+        for item in conversation.items_in(scope) {
+            match item {
+                Item::User(text) => {
+                    let _ = write!(out, "### User\n\n{}\n\n", text.trim_end());
+                }
+                Item::Text(text) => {
+                    let _ = write!(out, "### Assistant\n\n{}\n\n", text.trim_end());
+                }
+                Item::Thinking(text) => {
+                    if self.options.include_thinking {
+                        let _ = write!(
+                            out,
+                            "<details>\n<summary>Thinking</summary>\n\n{}\n\n</details>\n\n",
+                            text.trim_end()
+                        );
+                    }
+                }
+                Item::ToolCall { id, name, input } => {
+                    self.markdown_tool_call(conversation, id, name, input, out);
+                }
+            }
+        }
+    }
+
+    fn markdown_tool_call(
+        &self,
+        conversation: &Conversation,
+        id: &str,
+        name: &str,
+        input: &serde_json::Value,
+        out: &mut String,
+    ) {
+        match tool_summary(name, input) {
+            Some(summary) => {
+                let _ = writeln!(out, "**Tool: {name}** {}\n", inline_code(&summary));
+            }
+            None => {
+                let _ = writeln!(out, "**Tool: {name}**\n");
+            }
+        }
+        let _ = write!(
+            out,
+            "<details>\n<summary>Input</summary>\n\n{}\n</details>\n\n",
+            fenced(&self.tool_input_text(input), "json")
+        );
+
+        if conversation.has_items_in(id) {
+            let mut nested = String::new();
+            self.markdown_scope(conversation, Some(id), &mut nested);
+            let _ = writeln!(out, "> **Subagent**\n>");
+            for line in nested.trim_end().lines() {
+                if line.is_empty() {
+                    out.push_str(">\n");
+                } else {
+                    let _ = writeln!(out, "> {line}");
+                }
+            }
+            out.push('\n');
+        }
+
+        if self.options.include_tool_results {
+            if let Some(result) = conversation.results.get(id) {
+                let label = if result.is_error { "Error" } else { "Result" };
+                let _ = write!(
+                    out,
+                    "<details>\n<summary>{label}</summary>\n\n{}\n</details>\n\n",
+                    fenced(&self.truncate(&result.text), "")
+                );
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // HTML
+    // ------------------------------------------------------------------------
+
+    /// Render messages to a standalone HTML page (inline CSS, no scripts)
+    pub fn html<'a>(&self, messages: impl IntoIterator<Item = &'a Message>) -> String {
+        let conversation = Conversation::collect(messages);
+        let title = escape_html(self.title());
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        self.html_scope(&conversation, None, &mut out);
+        if !conversation.turns.is_empty() {
+            html_footer(&conversation.turns, &mut out);
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn html_scope(&self, conversation: &Conversation, scope: Option<&str>, out: &mut String) {
+        for item in conversation.items_in(scope) {
+            match item {
+                Item::User(text) => {
+                    let _ = writeln!(
+                        out,
+                        "<div class=\"message user\"><div class=\"role\">User</div><pre>{}</pre></div>",
+                        escape_html(text.trim_end())
+                    );
+                }
+                Item::Text(text) => {
+                    let _ = writeln!(
+                        out,
+                        "<div class=\"message assistant\"><div class=\"role\">Assistant</div><pre>{}</pre></div>",
+                        escape_html(text.trim_end())
+                    );
+                }
+                Item::Thinking(text) => {
+                    if self.options.include_thinking {
+                        let _ = writeln!(
+                            out,
+                            "<details class=\"thinking\"><summary>Thinking</summary><pre>{}</pre></details>",
+                            escape_html(text.trim_end())
+                        );
+                    }
+                }
+                Item::ToolCall { id, name, input } => {
+                    self.html_tool_call(conversation, id, name, input, out);
+                }
+            }
+        }
+    }
+
+    fn html_tool_call(
+        &self,
+        conversation: &Conversation,
+        id: &str,
+        name: &str,
+        input: &serde_json::Value,
+        out: &mut String,
+    ) {
+        let summary = tool_summary(name, input)
+            .map(|s| format!(" <code>{}</code>", escape_html(&s)))
+            .unwrap_or_default();
+        let _ = write!(
+            out,
+            "<div class=\"tool\"><div class=\"tool-name\">{}{summary}</div>\n\
+             <details><summary>Input</summary><pre>{}</pre></details>\n",
+            escape_html(name),
+            escape_html(&self.tool_input_text(input))
+        );
+
+        if conversation.has_items_in(id) {
+            out.push_str("<div class=\"subagent\"><div class=\"role\">Subagent</div>\n");
+            self.html_scope(conversation, Some(id), out);
+            out.push_str("</div>\n");
+        }
+
+        if self.options.include_tool_results {
+            if let Some(result) = conversation.results.get(id) {
+                let (class, label) = if result.is_error {
+                    ("result error", "Error")
+                } else {
+                    ("result", "Result")
+                };
+                let _ = writeln!(
+                    out,
+                    "<details class=\"{class}\"><summary>{label}</summary><pre>{}</pre></details>",
+                    escape_html(&self.truncate(&result.text))
+                );
+            }
+        }
+        out.push_str("</div>\n");
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Footer
+// ----------------------------------------------------------------------------
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map_or_else(|| "-".to_string(), |c| format!("${c:.4}"))
+}
+
+/// Cost of each turn
+///
+/// Results carry the session's running total, so a turn costs the
+/// difference to the previous total. A total lower than the previous one
+/// comes from a new CLI process (a resumed session) and is the turn's cost.
+fn turn_costs(turns: &[TurnSummary]) -> Vec<Option<f64>> {
+    let mut previous = 0.0;
+    turns
+        .iter()
+        .map(|turn| {
+            let total = turn.total_cost_usd?;
+            let cost = if total >= previous {
+                total - previous
+            } else {
+                total
+            };
+            previous = total;
+            Some(cost)
+        })
+        .collect()
+}
+
+/// Table rows: turn, status, API turns, duration, cost, input, output, cache read
+fn footer_rows(turns: &[TurnSummary]) -> Vec<[String; 8]> {
+    turns
+        .iter()
+        .zip(turn_costs(turns))
+        .enumerate()
+        .map(|(i, (turn, cost))| {
+            let usage = turn.usage.clone().unwrap_or_default();
+            let status = if turn.is_error {
+                format!("error ({})", turn.subtype)
+            } else {
+                turn.subtype.clone()
+            };
+            #[allow(clippy::cast_precision_loss)]
+            let seconds = turn.duration_ms as f64 / 1000.0;
+            [
+                (i + 1).to_string(),
+                status,
+                turn.num_turns.to_string(),
+                format!("{seconds:.1}s"),
+                format_cost(cost),
+                usage.input_tokens.to_string(),
+                usage.output_tokens.to_string(),
+                usage.cache_read_input_tokens.to_string(),
+            ]
+        })
+        .collect()
+}
+
+const FOOTER_HEADERS: [&str; 8] = [
+    "Turn",
+    "Status",
+    "API turns",
+    "Duration",
+    "Cost",
+    "Input tokens",
+    "Output tokens",
+    "Cache read",
+];
+
+/// Cost and token totals over all turns
+fn totals(turns: &[TurnSummary]) -> (Option<f64>, Usage) {
+    let mut usage = Usage::default();
+    for turn_usage in turns.iter().filter_map(|turn| turn.usage.as_ref()) {
+        usage.add(turn_usage);
+    }
+    let cost = turn_costs(turns)
+        .into_iter()
+        .flatten()
+        .reduce(|total, cost| total + cost);
+    (cost, usage)
+}
+
+fn markdown_footer(turns: &[TurnSummary], out: &mut String) {
+    out.push_str("## Usage\n\n");
+    let _ = writeln!(out, "| {} |", FOOTER_HEADERS.join(" | "));
+    let _ = writeln!(out, "|{}", " --- |".repeat(FOOTER_HEADERS.len()));
+    for row in footer_rows(turns) {
+        let _ = writeln!(out, "| {} |", row.join(" | "));
+    }
+    let (cost, usage) = totals(turns);
+    let _ = writeln!(
+        out,
+        "\n**Total:** {} · {} input / {} output tokens",
+        format_cost(cost),
+        usage.input_tokens,
+        usage.output_tokens
+    );
+}
+
+fn html_footer(turns: &[TurnSummary], out: &mut String) {
+    out.push_str("<footer>\n<h2>Usage</h2>\n<table>\n<tr>");
+    for header in FOOTER_HEADERS {
+        let _ = write!(out, "<th>{header}</th>");
+    }
+    out.push_str("</tr>\n");
+    for row in footer_rows(turns) {
+        out.push_str("<tr>");
+        for cell in row {
+            let _ = write!(out, "<td>{}</td>", escape_html(&cell));
+        }
+        out.push_str("</tr>\n");
+    }
+    let (cost, usage) = totals(turns);
+    let _ = writeln!(
+        out,
+        "</table>\n<p><strong>Total:</strong> {} · {} input / {} output tokens</p>\n</footer>",
+        format_cost(cost),
+        usage.input_tokens,
+        usage.output_tokens
+    );
+}
+
+// ----------------------------------------------------------------------------
+// Escaping
+// ----------------------------------------------------------------------------
+
+/// Escape text for HTML element content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Longest run of backticks in `text`
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Fenced code block whose fence cannot be closed by the content
+fn fenced(text: &str, lang: &str) -> String {
+    let fence = "`".repeat((longest_backtick_run(text) + 1).max(3));
+    format!("{fence}{lang}\n{}\n{fence}\n", text.trim_end())
+}
+
+/// Inline code span that survives backticks in the content
+fn inline_code(text: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(text) + 1);
+    format!("{ticks} {text} {ticks}")
+}
+
+const HTML_STYLE: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:960px;\
+margin:2em auto;padding:0 1em;color:#1f2328;line-height:1.5}\
+pre{white-space:pre-wrap;word-wrap:break-word;margin:.25em 0}\
+.message{border-radius:6px;padding:.5em 1em;margin:1em 0}\
+.user{background:#ddf4ff}.assistant{background:#f6f8fa}\
+.role{font-weight:600;font-size:.85em;color:#57606a}\
+.thinking{color:#57606a;font-style:italic;margin:.5em 0}\
+.tool{border-left:3px solid #8c959f;padding:.25em 1em;margin:1em 0}\
+.tool-name{font-weight:600}\
+.subagent{border:1px dashed #8c959f;border-radius:6px;padding:.25em 1em;margin:.5em 0}\
+.error summary{color:#cf222e}\
+summary{cursor:pointer;color:#57606a}\
+table{border-collapse:collapse;font-size:.9em}\
+th,td{border:1px solid #d0d7de;padding:.25em .75em;text-align:right}\
+footer{margin-top:2em;border-top:1px solid #d0d7de}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(value: serde_json::Value) -> Message {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn sample_conversation() -> Vec<Message> {
+        vec![
+            message(
+                json!({"type": "user", "message": {"role": "user", "content": "Find the TODOs"}}),
+            ),
+            message(
+                json!({"type": "assistant", "message": {"model": "m", "content": [
+                    {"type": "thinking", "thinking": "Delegate to a subagent", "signature": "sig"},
+                    {"type": "tool_use", "id": "task_1", "name": "Task", "input": {
+                        "description": "Search TODOs", "prompt": "grep", "subagent_type": "general-purpose"
+                    }}
+                ]}}),
+            ),
+            message(
+                json!({"type": "assistant", "parent_tool_use_id": "task_1", "message": {"model": "m", "content": [
+                    {"type": "tool_use", "id": "grep_1", "name": "Grep", "input": {"pattern": "TODO"}}
+                ]}}),
+            ),
+            message(
+                json!({"type": "user", "parent_tool_use_id": "task_1", "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "grep_1", "content": "src/lib.rs <b>"}
+                ]}}),
+            ),
+            message(
+                json!({"type": "user", "message": {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "task_1", "content": [{"type": "text", "text": "1 TODO"}]}
+                ]}}),
+            ),
+            message(
+                json!({"type": "assistant", "message": {"model": "m", "content": [
+                    {"type": "text", "text": "There is one TODO in src/lib.rs."}
+                ]}}),
+            ),
+            message(json!({
+                "type": "result", "subtype": "success", "duration_ms": 2500, "duration_api_ms": 2000,
+                "is_error": false, "num_turns": 3, "session_id": "s1", "total_cost_usd": 0.0123,
+                "usage": {"input_tokens": 100, "output_tokens": 20}
+            })),
+        ]
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let markdown = to_markdown(&sample_conversation());
+
+        assert!(markdown.starts_with("# Conversation\n"));
+        assert!(markdown.contains("### User\n\nFind the TODOs"));
+        assert!(markdown.contains("<summary>Thinking</summary>\n\nDelegate to a subagent"));
+        assert!(markdown.contains("**Tool: Task** ` Search TODOs (general-purpose) `"));
+        // The subagent's tool call is nested under the Task call, with its result
+        assert!(markdown.contains("> **Tool: Grep** ` TODO `"));
+        assert!(markdown.contains("> src/lib.rs <b>"));
+        assert!(markdown.contains("1 TODO"));
+        assert!(markdown.contains("There is one TODO"));
+        assert!(markdown.contains("| 1 | success | 3 | 2.5s | $0.0123 | 100 | 20 | 0 |"));
+
+        let task_pos = markdown.find("**Tool: Task**").unwrap();
+        let grep_pos = markdown.find("> **Tool: Grep**").unwrap();
+        let answer_pos = markdown.find("There is one TODO").unwrap();
+        assert!(task_pos < grep_pos && grep_pos < answer_pos);
+    }
+
+    #[test]
+    fn test_footer_costs_from_running_totals() {
+        let mut messages = sample_conversation();
+        messages.push(message(json!({
+            "type": "result", "subtype": "success", "duration_ms": 1000, "duration_api_ms": 800,
+            "is_error": false, "num_turns": 1, "session_id": "s1", "total_cost_usd": 0.0200,
+            "usage": {"input_tokens": 50, "output_tokens": 10}
+        })));
+
+        let markdown = to_markdown(&messages);
+        assert!(markdown.contains("| 1 | success | 3 | 2.5s | $0.0123 | 100 | 20 | 0 |"));
+        assert!(markdown.contains("| 2 | success | 1 | 1.0s | $0.0077 | 50 | 10 | 0 |"));
+        assert!(markdown.contains("**Total:** $0.0200 · 150 input / 30 output tokens"));
+    }
+
+    #[test]
+    fn test_html_export_escapes_and_nests() {
+        let html = Exporter::new(ExportOptions {
+            title: Some("Run <1>".to_string()),
+            ..ExportOptions::default()
+        })
+        .html(&sample_conversation());
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Run &lt;1&gt;</title>"));
+        assert!(html.contains("<details class=\"thinking\">"));
+        assert!(html.contains("<div class=\"subagent\">"));
+        assert!(html.contains("src/lib.rs &lt;b&gt;"));
+        assert!(!html.contains("<b>"));
+        assert!(html.contains("<td>$0.0123</td>"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_options_and_fences() {
+        let messages = sample_conversation();
+        let markdown = Exporter::new(ExportOptions {
+            include_thinking: false,
+            include_tool_results: false,
+            ..ExportOptions::default()
+        })
+        .markdown(&messages);
+        assert!(!markdown.contains("Thinking"));
+        assert!(!markdown.contains("<summary>Result</summary>"));
+
+        let truncated = Exporter::new(ExportOptions {
+            max_tool_output_chars: Some(3),
+            ..ExportOptions::default()
+        })
+        .truncate("abcdef");
+        assert_eq!(truncated, "abc\n… (3 more characters)");
+
+        assert_eq!(fenced("a ``` b", ""), "````\na ``` b\n````\n");
+        assert_eq!(inline_code("a`b"), "`` a`b ``");
+    }
+}
@@ -9,7 +9,10 @@
 //! - Hooks are registered by event type (`PreToolUse`, `PostToolUse`, etc.)
 //! - Each event type can have multiple matchers with patterns
 //! - Matchers filter by tool name (e.g., "Bash", "Write|Edit", "*")
-//! - Callbacks receive typed input and return `HookOutput`
+//! - Callbacks receive the raw input and return `HookOutput`; typed callbacks
+//!   ([`HookManager::on_pre_tool_use`], [`HookManager::typed_callback`]) receive
+//!   e.g. `PreToolUseHookInput` and return a per-event output such as
+//!   `PreToolUseHookOutput`
 //!
 //! # Example
 //!
@@ -32,16 +35,27 @@
 //!     HookMatcherBuilder::new(Some("Bash")).add_hook(hook).build(),
 //! );
 //! ```
+//!
+//! # CLI Hook Callbacks
+//!
+//! When used through [`ClaudeSDKClient`](crate::ClaudeSDKClient), hooks for the
+//! events in [`HookManager::CLI_HOOK_EVENTS`] are registered with the CLI during
+//! the `initialize` handshake. The CLI then calls them through `hook_callback`
+//! control requests *inside* the agent loop, so a `PreToolUse` block actually
+//! stops the tool and `hookSpecificOutput` (e.g. `updatedInput`) takes effect.
+//! Other events are still derived from the message stream.
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 
-use crate::callbacks::{FnHookCallback, HookCallback};
+use crate::callbacks::{FnHookCallback, HookCallback, TypedHookCallback};
 use crate::error::Result;
 use crate::types::{
-    ContentBlock, ContentValue, HookContext, HookDecision, HookEvent, HookMatcher, HookOutput,
-    Message,
+    ContentBlock, ContentValue, ContextHookOutput, HookContext, HookEvent, HookEventInput,
+    HookMatcher, HookOutput, Message, PostToolUseHookInput, PreToolUseHookInput,
+    PreToolUseHookOutput, SessionStartHookInput, SystemEvent, UserPromptSubmitHookInput,
 };
 
 // ============================================================================
@@ -79,10 +93,134 @@ pub struct PendingToolUse {
     pub is_task: bool,
 }
 
+// ============================================================================
+// CLI Hook Registration
+// ============================================================================
+
+/// A hook callback registered with the CLI during the `initialize` handshake
+#[derive(Clone)]
+pub struct RegisteredHook {
+    /// Event the callback was registered for
+    pub event: HookEvent,
+    /// The callback to invoke
+    pub hook: Arc<dyn HookCallback>,
+    /// Timeout for the callback
+    pub timeout: Duration,
+}
+
+impl RegisteredHook {
+    /// Invoke the callback with the hook input sent by the CLI
+    ///
+    /// Applies the matcher timeout; a timed out callback yields a default
+    /// `HookOutput` so the agent is not blocked.
+    ///
+    /// # Errors
+    ///
+    /// Propagates errors from the hook callback.
+    pub async fn invoke(
+        &self,
+        input: serde_json::Value,
+        context: HookContext,
+    ) -> Result<HookOutput> {
+        let tool_name = input
+            .get("tool_name")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+
+        match tokio::time::timeout(self.timeout, self.hook.call(input, tool_name, context)).await {
+            Ok(result) => result,
+            Err(_elapsed) => {
+                tracing::warn!(
+                    event = ?self.event,
+                    timeout_secs = self.timeout.as_secs(),
+                    "Hook callback timed out, continuing with default output"
+                );
+                Ok(HookOutput::default())
+            }
+        }
+    }
+}
+
 // ============================================================================
 // Hook Manager
 // ============================================================================
 
+// ============================================================================
+// Tool Name Patterns
+// ============================================================================
+
+/// Tool name pattern of a registered matcher, compiled once at registration
+///
+/// Patterns follow the CLI: `*` matches every tool, and any other pattern
+/// is a regex that must match the whole name (`Bash`, `Write|Edit`,
+/// `mcp__github__.*`). A pattern that is not a valid regex only matches a
+/// name equal to it or to one of its `|`-separated alternatives.
+#[derive(Debug)]
+enum ToolPattern {
+    /// No matcher: every invocation, with or without a tool
+    All,
+    /// `*`: every tool
+    AnyTool,
+    /// Exact tool names
+    Names(Vec<String>),
+    /// Regex anchored to the whole name
+    Regex(regex::Regex),
+}
+
+impl ToolPattern {
+    fn compile(matcher: Option<&str>) -> Self {
+        let Some(pattern) = matcher else {
+            return Self::All;
+        };
+        if pattern == "*" {
+            return Self::AnyTool;
+        }
+        let names = || Self::Names(pattern.split('|').map(String::from).collect());
+        let is_plain = pattern
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '|'));
+        if is_plain {
+            return names();
+        }
+        match regex::Regex::new(&format!("^(?:{pattern})$")) {
+            Ok(regex) => Self::Regex(regex),
+            Err(e) => {
+                tracing::warn!(
+                    pattern = %pattern,
+                    error = %e,
+                    "Invalid hook matcher regex, matching tool names exactly"
+                );
+                names()
+            }
+        }
+    }
+
+    fn matches(&self, tool_name: Option<&str>) -> bool {
+        match (self, tool_name) {
+            (Self::All, _) => true,
+            (_, None) => false,
+            (Self::AnyTool, Some(_)) => true,
+            (Self::Names(names), Some(name)) => names.iter().any(|n| n == name),
+            (Self::Regex(regex), Some(name)) => regex.is_match(name),
+        }
+    }
+}
+
+/// A registered matcher with its compiled tool name pattern
+struct CompiledHookMatcher {
+    matcher: HookMatcher,
+    tool: ToolPattern,
+}
+
+impl From<HookMatcher> for CompiledHookMatcher {
+    fn from(matcher: HookMatcher) -> Self {
+        Self {
+            tool: ToolPattern::compile(matcher.matcher.as_deref()),
+            matcher,
+        }
+    }
+}
+
 /// Hook manager for registering and invoking hooks by event type
 ///
 /// Mirrors the TypeScript SDK structure:
@@ -91,7 +229,7 @@ pub struct PendingToolUse {
 /// ```
 pub struct HookManager {
     /// Hooks registered by event type
-    hooks_by_event: HashMap<HookEvent, Vec<HookMatcher>>,
+    hooks_by_event: HashMap<HookEvent, Vec<CompiledHookMatcher>>,
     /// Pending tool uses awaiting results (`tool_use_id` -> info)
     pending_tools: HashMap<String, PendingToolUse>,
     /// Session context for constructing hook inputs
@@ -105,6 +243,11 @@ pub struct HookManager {
     /// When a message with `parent_tool_use_id=None` arrives after this is set,
     /// the subagent has completed and we trigger `SubagentStop`.
     current_subagent: Option<String>,
+    /// Callbacks registered with the CLI, by callback ID
+    registered_hooks: HashMap<String, RegisteredHook>,
+    /// Whether the CLI accepted the hook registrations (CLI-delegated events
+    /// are then no longer derived from messages)
+    cli_delegation: bool,
 }
 
 impl HookManager {
@@ -118,6 +261,8 @@ impl HookManager {
             cwd: None,
             cancellation_token: None,
             current_subagent: None,
+            registered_hooks: HashMap::new(),
+            cli_delegation: false,
         }
     }
 
@@ -127,12 +272,17 @@ impl HookManager {
     #[must_use]
     pub fn from_hooks_config(config: HashMap<HookEvent, Vec<HookMatcher>>) -> Self {
         Self {
-            hooks_by_event: config,
+            hooks_by_event: config
+                .into_iter()
+                .map(|(event, matchers)| (event, matchers.into_iter().map(Into::into).collect()))
+                .collect(),
             pending_tools: HashMap::new(),
             session_id: None,
             cwd: None,
             cancellation_token: None,
             current_subagent: None,
+            registered_hooks: HashMap::new(),
+            cli_delegation: false,
         }
     }
 
@@ -158,8 +308,14 @@ impl HookManager {
     }
 
     /// Register a hook matcher for a specific event type
+    ///
+    /// The matcher's tool name pattern is compiled here; an invalid regex is
+    /// logged once and then matches tool names exactly.
     pub fn register_for_event(&mut self, event: HookEvent, matcher: HookMatcher) {
-        self.hooks_by_event.entry(event).or_default().push(matcher);
+        self.hooks_by_event
+            .entry(event)
+            .or_default()
+            .push(matcher.into());
     }
 
     /// Register a hook with a matcher (legacy API - registers for all events)
@@ -172,6 +328,93 @@ impl HookManager {
     /// Default timeout for hook callbacks (60 seconds, matching TypeScript SDK)
     pub const DEFAULT_HOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
 
+    /// Events whose hooks are registered with the CLI and invoked through
+    /// `hook_callback` control requests
+    pub const CLI_HOOK_EVENTS: [HookEvent; 4] = [
+        HookEvent::PreToolUse,
+        HookEvent::PostToolUse,
+        HookEvent::PostToolUseFailure,
+        HookEvent::PermissionRequest,
+    ];
+
+    /// Build the `hooks` payload for the CLI `initialize` request
+    ///
+    /// Assigns a callback ID (`hook_0`, `hook_1`, ...) to every callback
+    /// registered for one of [`CLI_HOOK_EVENTS`](Self::CLI_HOOK_EVENTS).
+    /// Returns `None` if there is nothing to register.
+    pub fn cli_hook_registrations(&mut self) -> Option<serde_json::Value> {
+        self.registered_hooks.clear();
+        let mut registrations = serde_json::Map::new();
+
+        for event in Self::CLI_HOOK_EVENTS {
+            let Some(matchers) = self.hooks_by_event.get(&event) else {
+                continue;
+            };
+
+            let mut entries = Vec::new();
+            for matcher in matchers
+                .iter()
+                .map(|m| &m.matcher)
+                .filter(|m| !m.hooks.is_empty())
+            {
+                let timeout = matcher.timeout.unwrap_or(Self::DEFAULT_HOOK_TIMEOUT);
+                let mut callback_ids = Vec::new();
+                for hook in &matcher.hooks {
+                    let callback_id = format!("hook_{}", self.registered_hooks.len());
+                    self.registered_hooks.insert(
+                        callback_id.clone(),
+                        RegisteredHook {
+                            event,
+                            hook: hook.clone(),
+                            timeout,
+                        },
+                    );
+                    callback_ids.push(callback_id);
+                }
+
+                let mut entry = serde_json::json!({
+                    "matcher": matcher.matcher,
+                    "hookCallbackIds": callback_ids,
+                });
+                if let Some(timeout) = matcher.timeout {
+                    entry["timeout"] = serde_json::json!(timeout.as_secs());
+                }
+                entries.push(entry);
+            }
+
+            if !entries.is_empty() {
+                let name = serde_json::to_value(event)
+                    .ok()
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_default();
+                registrations.insert(name, serde_json::Value::Array(entries));
+            }
+        }
+
+        (!registrations.is_empty()).then_some(serde_json::Value::Object(registrations))
+    }
+
+    /// Look up a callback registered with the CLI by its callback ID
+    #[must_use]
+    pub fn registered_hook(&self, callback_id: &str) -> Option<RegisteredHook> {
+        self.registered_hooks.get(callback_id).cloned()
+    }
+
+    /// Enable or disable CLI delegation of [`CLI_HOOK_EVENTS`](Self::CLI_HOOK_EVENTS)
+    ///
+    /// Set once the CLI has accepted the `initialize` registrations. While
+    /// enabled, [`process_message`](Self::process_message) no longer invokes
+    /// hooks for those events, since the CLI calls them directly.
+    pub fn set_cli_delegation(&mut self, enabled: bool) {
+        self.cli_delegation = enabled;
+    }
+
+    /// Whether hooks for `event` are invoked by the CLI rather than derived from messages
+    #[must_use]
+    pub fn is_cli_delegated(&self, event: HookEvent) -> bool {
+        self.cli_delegation && Self::CLI_HOOK_EVENTS.contains(&event)
+    }
+
     /// Invoke hooks for a specific event type
     ///
     /// # Arguments
@@ -181,7 +424,10 @@ impl HookManager {
     /// * `context` - Hook context
     ///
     /// # Returns
-    /// Combined hook output from all matching hooks
+    /// Combined hook output from all matching hooks, merged in registration
+    /// order with [`HookOutput::merge`]: a block or deny wins and stops the
+    /// remaining hooks, `continue: false` wins, and messages and
+    /// `additionalContext` are concatenated.
     ///
     /// # Timeout Behavior
     /// Each hook matcher has a configurable timeout (default: 60 seconds).
@@ -206,8 +452,8 @@ impl HookManager {
         };
 
         // Find matching hooks
-        for matcher in matchers {
-            if Self::matches(matcher.matcher.as_ref(), tool_name.as_ref()) {
+        for CompiledHookMatcher { matcher, tool } in matchers {
+            if tool.matches(tool_name.as_deref()) {
                 let timeout = matcher.timeout.unwrap_or(Self::DEFAULT_HOOK_TIMEOUT);
 
                 // Invoke each hook callback with timeout
@@ -231,19 +477,10 @@ impl HookManager {
                         }
                     };
 
-                    // Merge hook results
-                    if result.decision.is_some() {
-                        output.decision = result.decision;
-                    }
-                    if result.system_message.is_some() {
-                        output.system_message = result.system_message;
-                    }
-                    if result.hook_specific_output.is_some() {
-                        output.hook_specific_output = result.hook_specific_output;
-                    }
+                    output.merge(result);
 
-                    // If decision is Block, stop processing
-                    if matches!(output.decision, Some(HookDecision::Block)) {
+                    // A block or deny is final, skip the remaining hooks
+                    if output.is_blocking() {
                         return Ok(output);
                     }
                 }
@@ -314,10 +551,7 @@ impl HookManager {
                     )
                     .await?;
 
-                if output.decision.is_some()
-                    || output.system_message.is_some()
-                    || output.hook_specific_output.is_some()
-                {
+                if !output.is_empty() {
                     outputs.push(output);
                 }
 
@@ -360,21 +594,20 @@ impl HookManager {
                             "tool_input": input,
                         });
 
-                        // Invoke PreToolUse hooks
-                        let output = self
-                            .invoke(
-                                HookEvent::PreToolUse,
-                                hook_input,
-                                Some(name.clone()),
-                                context.clone(),
-                            )
-                            .await?;
-
-                        if output.decision.is_some()
-                            || output.system_message.is_some()
-                            || output.hook_specific_output.is_some()
-                        {
-                            outputs.push(output);
+                        // Invoke PreToolUse hooks (unless the CLI already called them)
+                        if !self.is_cli_delegated(HookEvent::PreToolUse) {
+                            let output = self
+                                .invoke(
+                                    HookEvent::PreToolUse,
+                                    hook_input,
+                                    Some(name.clone()),
+                                    context.clone(),
+                                )
+                                .await?;
+
+                            if !output.is_empty() {
+                                outputs.push(output);
+                            }
                         }
 
                         // For Task tool, also invoke SubagentStart
@@ -397,10 +630,7 @@ impl HookManager {
                                 )
                                 .await?;
 
-                            if output.decision.is_some()
-                                || output.system_message.is_some()
-                                || output.hook_specific_output.is_some()
-                            {
+                            if !output.is_empty() {
                                 outputs.push(output);
                             }
                         }
@@ -422,7 +652,9 @@ impl HookManager {
                             if let Some(pending) = self.pending_tools.remove(tool_use_id) {
                                 let is_failure = is_error.unwrap_or(false);
 
-                                if is_failure {
+                                if is_failure
+                                    && !self.is_cli_delegated(HookEvent::PostToolUseFailure)
+                                {
                                     // PostToolUseFailure
                                     let hook_input = serde_json::json!({
                                         "hook_event_name": "PostToolUseFailure",
@@ -444,39 +676,35 @@ impl HookManager {
                                         )
                                         .await?;
 
-                                    if output.decision.is_some()
-                                        || output.system_message.is_some()
-                                        || output.hook_specific_output.is_some()
-                                    {
+                                    if !output.is_empty() {
                                         outputs.push(output);
                                     }
-                                } else {
-                                    // PostToolUse
-                                    let hook_input = serde_json::json!({
-                                        "hook_event_name": "PostToolUse",
-                                        "session_id": self.session_id.as_deref().unwrap_or(""),
-                                        "cwd": self.cwd.as_deref().unwrap_or(""),
-                                        "transcript_path": "",
-                                        "tool_name": pending.tool_name,
-                                        "tool_input": pending.tool_input,
-                                        "tool_response": content,
-                                        "tool_use_id": tool_use_id,
-                                    });
+                                } else if !is_failure {
+                                    // PostToolUse (unless the CLI already called it)
+                                    if !self.is_cli_delegated(HookEvent::PostToolUse) {
+                                        let hook_input = serde_json::json!({
+                                            "hook_event_name": "PostToolUse",
+                                            "session_id": self.session_id.as_deref().unwrap_or(""),
+                                            "cwd": self.cwd.as_deref().unwrap_or(""),
+                                            "transcript_path": "",
+                                            "tool_name": pending.tool_name,
+                                            "tool_input": pending.tool_input,
+                                            "tool_response": content,
+                                            "tool_use_id": tool_use_id,
+                                        });
 
-                                    let output = self
-                                        .invoke(
-                                            HookEvent::PostToolUse,
-                                            hook_input,
-                                            Some(pending.tool_name.clone()),
-                                            context.clone(),
-                                        )
-                                        .await?;
+                                        let output = self
+                                            .invoke(
+                                                HookEvent::PostToolUse,
+                                                hook_input,
+                                                Some(pending.tool_name.clone()),
+                                                context.clone(),
+                                            )
+                                            .await?;
 
-                                    if output.decision.is_some()
-                                        || output.system_message.is_some()
-                                        || output.hook_specific_output.is_some()
-                                    {
-                                        outputs.push(output);
+                                        if !output.is_empty() {
+                                            outputs.push(output);
+                                        }
                                     }
 
                                     // For Task tool, also invoke SubagentStop
@@ -500,10 +728,7 @@ impl HookManager {
                                             )
                                             .await?;
 
-                                        if output.decision.is_some()
-                                            || output.system_message.is_some()
-                                            || output.hook_specific_output.is_some()
-                                        {
+                                        if !output.is_empty() {
                                             outputs.push(output);
                                         }
                                     }
@@ -531,22 +756,15 @@ impl HookManager {
                     .invoke(HookEvent::Stop, stop_input, None, context.clone())
                     .await?;
 
-                if output.decision.is_some()
-                    || output.system_message.is_some()
-                    || output.hook_specific_output.is_some()
-                {
+                if !output.is_empty() {
                     outputs.push(output);
                 }
             }
 
-            Message::System { subtype, data } => {
+            Message::System { .. } => {
                 // Handle compact_boundary for PreCompact hook
-                if subtype == "compact_boundary" {
-                    let trigger = data
-                        .get("compact_metadata")
-                        .and_then(|m| m.get("trigger"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("auto");
+                if let Some(SystemEvent::CompactBoundary(boundary)) = msg.as_system_event() {
+                    let trigger = boundary.trigger;
 
                     let precompact_input = serde_json::json!({
                         "hook_event_name": "PreCompact",
@@ -566,16 +784,13 @@ impl HookManager {
                         )
                         .await?;
 
-                    if output.decision.is_some()
-                        || output.system_message.is_some()
-                        || output.hook_specific_output.is_some()
-                    {
+                    if !output.is_empty() {
                         outputs.push(output);
                     }
                 }
             }
 
-            Message::StreamEvent { .. } => {}
+            Message::StreamEvent { .. } | Message::Unknown { .. } => {}
         }
 
         Ok(outputs)
@@ -619,10 +834,7 @@ impl HookManager {
             .invoke(HookEvent::SessionStart, session_start_input, None, context)
             .await?;
 
-        if output.decision.is_some()
-            || output.system_message.is_some()
-            || output.hook_specific_output.is_some()
-        {
+        if !output.is_empty() {
             outputs.push(output);
         }
 
@@ -655,10 +867,7 @@ impl HookManager {
             .invoke(HookEvent::SessionEnd, session_end_input, None, context)
             .await?;
 
-        if output.decision.is_some()
-            || output.system_message.is_some()
-            || output.hook_specific_output.is_some()
-        {
+        if !output.is_empty() {
             outputs.push(output);
         }
 
@@ -699,10 +908,7 @@ impl HookManager {
             )
             .await?;
 
-        if output.decision.is_some()
-            || output.system_message.is_some()
-            || output.hook_specific_output.is_some()
-        {
+        if !output.is_empty() {
             outputs.push(output);
         }
 
@@ -741,10 +947,7 @@ impl HookManager {
             .invoke(HookEvent::Notification, notification_input, None, context)
             .await?;
 
-        if output.decision.is_some()
-            || output.system_message.is_some()
-            || output.hook_specific_output.is_some()
-        {
+        if !output.is_empty() {
             outputs.push(output);
         }
 
@@ -791,42 +994,13 @@ impl HookManager {
             )
             .await?;
 
-        if output.decision.is_some()
-            || output.system_message.is_some()
-            || output.hook_specific_output.is_some()
-        {
+        if !output.is_empty() {
             outputs.push(output);
         }
 
         Ok(outputs)
     }
 
-    // ========================================================================
-    // Pattern Matching
-    // ========================================================================
-
-    /// Check if a matcher matches a tool name
-    ///
-    /// # Security Note
-    /// This uses simple pattern matching with pipe-separated alternatives.
-    /// For production use with untrusted patterns, consider using a proper
-    /// glob or regex library with safety guarantees (e.g., `globset` crate).
-    fn matches(matcher: Option<&String>, tool_name: Option<&String>) -> bool {
-        match (matcher, tool_name) {
-            (None, _) => true, // No matcher = match all
-            (Some(pattern), Some(name)) => {
-                // Simple wildcard matching
-                if pattern == "*" {
-                    return true;
-                }
-                // Exact match or simple pipe-separated pattern
-                // Note: This doesn't handle edge cases like pipe characters in tool names
-                pattern == name || pattern.split('|').any(|p| p == name)
-            }
-            (Some(_), None) => false,
-        }
-    }
-
     // ========================================================================
     // Callback Helpers
     // ========================================================================
@@ -854,6 +1028,106 @@ impl HookManager {
             move |event_data, tool_name, context| Box::pin(f(event_data, tool_name, context)),
         ))
     }
+
+    /// Create a hook callback from a closure taking a typed hook input
+    ///
+    /// The input type picks the event: the raw input is deserialized into it
+    /// and the closure returns the event's typed output (see
+    /// [`HookEventInput`]), which is serialized into the matching
+    /// `hookSpecificOutput` shape.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use anthropic_agent_sdk::HookManager;
+    /// use anthropic_agent_sdk::types::{PostToolUseHookInput, ContextHookOutput};
+    ///
+    /// let hook = HookManager::typed_callback(|input: PostToolUseHookInput, _ctx| async move {
+    ///     Ok(ContextHookOutput::with_context(format!("{} finished", input.tool_name)))
+    /// });
+    /// ```
+    pub fn typed_callback<I, F, Fut>(f: F) -> Arc<dyn HookCallback>
+    where
+        I: HookEventInput,
+        F: Fn(I, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<I::Output>> + Send + 'static,
+    {
+        Arc::new(TypedHookCallback::new(move |input: I, context| {
+            Box::pin(f(input, context))
+        }))
+    }
+
+    /// Register a typed hook for the event of its input type
+    ///
+    /// # Arguments
+    /// * `matcher` - Matcher pattern (None for all, or specific tool name/pattern)
+    /// * `f` - Closure receiving the typed input and returning the typed output
+    pub fn on<I, F, Fut>(&mut self, matcher: Option<&str>, f: F) -> &mut Self
+    where
+        I: HookEventInput,
+        F: Fn(I, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<I::Output>> + Send + 'static,
+    {
+        self.register_for_event(
+            I::EVENT,
+            HookMatcherBuilder::new(matcher)
+                .add_hook(Self::typed_callback(f))
+                .build(),
+        );
+        self
+    }
+
+    /// Register a typed `PreToolUse` hook
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use anthropic_agent_sdk::HookManager;
+    /// use anthropic_agent_sdk::types::PreToolUseHookOutput;
+    ///
+    /// let mut manager = HookManager::new();
+    /// manager.on_pre_tool_use(Some("Bash"), |input, _ctx| async move {
+    ///     let command = input.tool_input["command"].as_str().unwrap_or_default();
+    ///     if command.contains("rm -rf") {
+    ///         return Ok(PreToolUseHookOutput::deny("Destructive command"));
+    ///     }
+    ///     Ok(PreToolUseHookOutput::allow())
+    /// });
+    /// ```
+    pub fn on_pre_tool_use<F, Fut>(&mut self, matcher: Option<&str>, f: F) -> &mut Self
+    where
+        F: Fn(PreToolUseHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<PreToolUseHookOutput>> + Send + 'static,
+    {
+        self.on(matcher, f)
+    }
+
+    /// Register a typed `PostToolUse` hook
+    pub fn on_post_tool_use<F, Fut>(&mut self, matcher: Option<&str>, f: F) -> &mut Self
+    where
+        F: Fn(PostToolUseHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<ContextHookOutput>> + Send + 'static,
+    {
+        self.on(matcher, f)
+    }
+
+    /// Register a typed `UserPromptSubmit` hook
+    pub fn on_user_prompt_submit<F, Fut>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(UserPromptSubmitHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<ContextHookOutput>> + Send + 'static,
+    {
+        self.on(None, f)
+    }
+
+    /// Register a typed `SessionStart` hook
+    pub fn on_session_start<F, Fut>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(SessionStartHookInput, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<ContextHookOutput>> + Send + 'static,
+    {
+        self.on(None, f)
+    }
 }
 
 impl Default for HookManager {
@@ -889,6 +1163,19 @@ impl HookMatcherBuilder {
         self
     }
 
+    /// Add a typed hook callback (see [`HookManager::typed_callback`])
+    ///
+    /// The matcher must be registered for the event of the input type `I`.
+    #[must_use]
+    pub fn add_typed_hook<I, F, Fut>(self, f: F) -> Self
+    where
+        I: HookEventInput,
+        F: Fn(I, HookContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<I::Output>> + Send + 'static,
+    {
+        self.add_hook(HookManager::typed_callback(f))
+    }
+
     /// Set timeout for all hooks in this matcher
     ///
     /// Default is 60 seconds if not specified.
@@ -923,6 +1210,7 @@ impl HookMatcherBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::HookDecision;
 
     #[tokio::test]
     async fn test_hook_manager() {
@@ -948,6 +1236,65 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_cli_hook_registrations() {
+        let mut manager = HookManager::new();
+        let block = HookManager::callback(|_input, _tool_name, _ctx| async {
+            Ok(HookOutput {
+                decision: Some(HookDecision::Block),
+                ..Default::default()
+            })
+        });
+        let noop =
+            HookManager::callback(|_input, _tool_name, _ctx| async { Ok(HookOutput::default()) });
+
+        manager.register_for_event(
+            HookEvent::PreToolUse,
+            HookMatcherBuilder::new(Some("Bash"))
+                .add_hook(block)
+                .timeout(Duration::from_secs(5))
+                .build(),
+        );
+        // Not a CLI-delegated event: stays message-derived
+        manager.register_for_event(
+            HookEvent::SessionStart,
+            HookMatcherBuilder::new(None::<String>)
+                .add_hook(noop)
+                .build(),
+        );
+
+        let registrations = manager.cli_hook_registrations().unwrap();
+        assert_eq!(
+            registrations,
+            serde_json::json!({
+                "PreToolUse": [{"matcher": "Bash", "hookCallbackIds": ["hook_0"], "timeout": 5}]
+            })
+        );
+
+        let hook = manager.registered_hook("hook_0").unwrap();
+        assert_eq!(hook.event, HookEvent::PreToolUse);
+        let output = hook
+            .invoke(
+                serde_json::json!({"tool_name": "Bash"}),
+                HookContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.decision, Some(HookDecision::Block));
+        assert!(manager.registered_hook("hook_1").is_none());
+
+        assert!(!manager.is_cli_delegated(HookEvent::PreToolUse));
+        manager.set_cli_delegation(true);
+        assert!(manager.is_cli_delegated(HookEvent::PreToolUse));
+        assert!(!manager.is_cli_delegated(HookEvent::SessionStart));
+    }
+
+    #[test]
+    fn test_cli_hook_registrations_empty() {
+        let mut manager = HookManager::new();
+        assert!(manager.cli_hook_registrations().is_none());
+    }
+
     #[tokio::test]
     async fn test_hook_manager_by_event() {
         let mut manager = HookManager::new();
@@ -1019,43 +1366,61 @@ mod tests {
         assert!(result.system_message.is_none());
     }
 
+    fn matches(pattern: Option<&str>, name: Option<&str>) -> bool {
+        ToolPattern::compile(pattern).matches(name)
+    }
+
     #[test]
     fn test_matcher_wildcard() {
-        assert!(HookManager::matches(
-            Some("*".to_string()).as_ref(),
-            Some("any_tool".to_string()).as_ref()
-        ));
-        assert!(HookManager::matches(
-            None,
-            Some("any_tool".to_string()).as_ref()
-        ));
+        assert!(matches(Some("*"), Some("any_tool")));
+        assert!(matches(None, Some("any_tool")));
+        assert!(matches(None, None));
+        assert!(!matches(Some("*"), None));
     }
 
     #[test]
     fn test_matcher_specific() {
-        assert!(HookManager::matches(
-            Some("Bash".to_string()).as_ref(),
-            Some("Bash".to_string()).as_ref()
-        ));
-        assert!(!HookManager::matches(
-            Some("Bash".to_string()).as_ref(),
-            Some("Write".to_string()).as_ref()
-        ));
+        assert!(matches(Some("Bash"), Some("Bash")));
+        assert!(!matches(Some("Bash"), Some("Write")));
     }
 
     #[test]
     fn test_matcher_pattern() {
-        assert!(HookManager::matches(
-            Some("Write|Edit".to_string()).as_ref(),
-            Some("Write".to_string()).as_ref()
+        assert!(matches(Some("Write|Edit"), Some("Write")));
+        assert!(matches(Some("Write|Edit"), Some("Edit")));
+        assert!(!matches(Some("Write|Edit"), Some("Bash")));
+    }
+
+    #[test]
+    fn test_matcher_regex() {
+        let matches = |pattern: &str, name: &str| matches(Some(pattern), Some(name));
+        assert!(matches("Edit|Write", "Write"));
+        assert!(!matches("Edit|Write", "MultiEdit"));
+        assert!(matches("mcp__.*", "mcp__github__create_issue"));
+        assert!(matches("mcp__github__.*", "mcp__github__create_issue"));
+        assert!(!matches("mcp__github__.*", "mcp__slack__post"));
+        assert!(matches("Notebook.*", "NotebookEdit"));
+        // Regexes must match the whole name
+        assert!(!matches("Note", "NotebookEdit"));
+        assert!(!matches("Bash.*", "BigBash"));
+        // Invalid regexes fall back to exact comparison
+        assert!(matches("Bash(", "Bash("));
+        assert!(!matches("Bash(", "Bash"));
+    }
+
+    #[test]
+    fn test_matcher_compiled_at_registration() {
+        assert!(matches!(
+            ToolPattern::compile(Some("mcp__.*")),
+            ToolPattern::Regex(_)
         ));
-        assert!(HookManager::matches(
-            Some("Write|Edit".to_string()).as_ref(),
-            Some("Edit".to_string()).as_ref()
+        assert!(matches!(
+            ToolPattern::compile(Some("Bash(")),
+            ToolPattern::Names(_)
         ));
-        assert!(!HookManager::matches(
-            Some("Write|Edit".to_string()).as_ref(),
-            Some("Bash".to_string()).as_ref()
+        assert!(matches!(
+            ToolPattern::compile(Some("Edit|Write")),
+            ToolPattern::Names(_)
         ));
     }
 
@@ -1255,4 +1620,147 @@ mod tests {
         assert!(ctx.cancellation_token.is_none());
         assert!(!ctx.is_cancelled()); // No token means not cancelled
     }
+
+    #[tokio::test]
+    async fn test_typed_pre_tool_use_hook() {
+        let mut manager = HookManager::new();
+        manager.on_pre_tool_use(Some("Bash"), |input, _ctx| async move {
+            if input.tool_input["command"] == "rm -rf /" {
+                return Ok(PreToolUseHookOutput::deny("destructive"));
+            }
+            Ok(PreToolUseHookOutput::allow())
+        });
+
+        let input = |command: &str| {
+            serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s1",
+                "transcript_path": "",
+                "cwd": "/tmp",
+                "tool_name": "Bash",
+                "tool_input": {"command": command},
+            })
+        };
+
+        let denied = manager
+            .invoke(
+                HookEvent::PreToolUse,
+                input("rm -rf /"),
+                Some("Bash".to_string()),
+                HookContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(denied.decision, Some(HookDecision::Block));
+        assert_eq!(
+            denied.hook_specific_output.unwrap()["permissionDecisionReason"],
+            "destructive"
+        );
+
+        let allowed = manager
+            .invoke(
+                HookEvent::PreToolUse,
+                input("ls"),
+                Some("Bash".to_string()),
+                HookContext::default(),
+            )
+            .await
+            .unwrap();
+        assert!(allowed.decision.is_none());
+        assert_eq!(
+            allowed.hook_specific_output.unwrap()["permissionDecision"],
+            "allow"
+        );
+
+        // Input that does not match the typed schema is a hook error
+        let invalid = manager
+            .invoke(
+                HookEvent::PreToolUse,
+                serde_json::json!({"tool_name": "Bash"}),
+                Some("Bash".to_string()),
+                HookContext::default(),
+            )
+            .await;
+        assert!(invalid.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_typed_session_start_hook() {
+        let mut manager = HookManager::new();
+        manager.on_session_start(|input, _ctx| async move {
+            Ok(ContextHookOutput::with_context(format!(
+                "started from {:?}",
+                input.source
+            )))
+        });
+        manager.set_session_context("s1".to_string(), Some("/tmp".to_string()));
+
+        let outputs = manager.trigger_session_start("resume").await.unwrap();
+        assert_eq!(outputs.len(), 1);
+        let specific = outputs[0].hook_specific_output.as_ref().unwrap();
+        assert_eq!(specific["hookEventName"], "SessionStart");
+        assert_eq!(specific["additionalContext"], "started from Resume");
+    }
+
+    #[tokio::test]
+    async fn test_invoke_merges_hooks_until_deny() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let mut manager = HookManager::new();
+        manager.on_pre_tool_use(None, |_input, _ctx| async move {
+            Ok(PreToolUseHookOutput::allow().with_additional_context("audited"))
+        });
+        manager.register_for_event(
+            HookEvent::PreToolUse,
+            HookMatcherBuilder::new(Some("Bash"))
+                .add_hook(HookManager::callback(|_input, _tool, _ctx| async move {
+                    Ok(HookOutput::default().with_system_message("bash is watched"))
+                }))
+                .build(),
+        );
+        manager.on_pre_tool_use(Some("Bash"), |_input, _ctx| async move {
+            Ok(PreToolUseHookOutput::deny("no shell"))
+        });
+        let reached = Arc::new(AtomicBool::new(false));
+        let reached_clone = reached.clone();
+        manager.register_for_event(
+            HookEvent::PreToolUse,
+            HookMatcherBuilder::new(None::<String>)
+                .add_hook(HookManager::callback(move |_input, _tool, _ctx| {
+                    let reached = reached_clone.clone();
+                    async move {
+                        reached.store(true, Ordering::SeqCst);
+                        Ok(HookOutput::approve())
+                    }
+                }))
+                .build(),
+        );
+
+        let output = manager
+            .invoke(
+                HookEvent::PreToolUse,
+                serde_json::json!({
+                    "hook_event_name": "PreToolUse",
+                    "session_id": "s1",
+                    "transcript_path": "",
+                    "cwd": "/tmp",
+                    "tool_name": "Bash",
+                    "tool_input": {"command": "ls"},
+                }),
+                Some("Bash".to_string()),
+                HookContext::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(output.is_blocking());
+        assert_eq!(output.decision, Some(HookDecision::Block));
+        assert_eq!(
+            output.permission_decision(),
+            Some(crate::types::PermissionBehavior::Deny)
+        );
+        assert_eq!(output.additional_context(), Some("audited"));
+        assert_eq!(output.system_message.as_deref(), Some("bash is watched"));
+        assert!(!reached.load(Ordering::SeqCst));
+    }
 }
@@ -116,7 +116,18 @@
 //! }
 //! ```
 //!
-//! See `examples/mcp_server.rs` for a complete demo.
+//! Register the server in-process with `RmcpSdkServer` and `sdk_mcp_servers`; the CLI
+//! then reaches it over the control channel, with no child process:
+//!
+//! ```ignore
+//! use anthropic_agent_sdk::mcp::{RmcpSdkServer, SdkMcpServer};
+//!
+//! let mut servers: HashMap<String, Arc<dyn SdkMcpServer>> = HashMap::new();
+//! servers.insert("calc".into(), Arc::new(RmcpSdkServer::new(Calculator::new())));
+//! let options = ClaudeAgentOptions::builder().sdk_mcp_servers(servers).build();
+//! ```
+//!
+//! See `examples/mcp_server.rs` and `examples/sdk_mcp_server.rs` for complete demos.
 //!
 //! ### 4. Hooks for Custom Behavior
 //!
@@ -189,9 +200,16 @@
 //! - [`client`]: Interactive bidirectional client
 //! - [`mcp`]: SDK MCP server for custom tools
 //! - [`hooks`]: Hook system for intercepting events
+//! - [`command_hook`]: Shell-command hooks from Claude Code settings files
 //! - [`permissions`]: Permission control for tool usage
-//! - [`transport`]: Communication layer with Claude Code CLI
+//! - `policy`: Declarative hook and permission policies (`policy` feature)
+//! - [`transport`]: Communication layer with Claude Code CLI (pluggable via the
+//!   [`Transport`] trait, see [`ClaudeSDKClient::with_transport`])
 //! - [`control`]: Control protocol handler
+//! - [`transcript`]: Reader for the CLI's session transcripts
+//! - [`session`]: Session trees (branches, forks, pruning) and session stores
+//! - [`export`]: Conversation export to Markdown and HTML
+//! - [`structured_output`]: Structured output validation and repair
 //! - [`message`]: Message parsing and types
 //! - [`error`]: Error types and handling
 //!
@@ -200,6 +218,13 @@
 //! This crate supports the following feature flags:
 //!
 //! - `rmcp` - Enables SDK MCP server support via the official rmcp crate
+//! - `testing` - Enables the `testing` module with `MockTransport`
+//!   for deterministic tests without the CLI
+//! - `sqlite` - Enables `SqliteSessionStore`, a SQLite-backed session store
+//! - `schemars` - Enables typed structured output: `query_typed()`,
+//!   `ClaudeSDKClient::ask_typed()` and `OutputFormat::for_type()`
+//! - `policy` - Enables the `policy` module: declarative hook and permission
+//!   rules loaded from TOML/JSON files, with hot reload
 //!
 //! ## Logging
 //!
@@ -218,9 +243,12 @@
 //! - `bidirectional_demo.rs` - Concurrent operations
 //! - `hooks_demo.rs` - Hook system for tool interception
 //! - `permissions_demo.rs` - Permission control for tools
+//! - `policy_demo.rs` - Declarative policy file with hot reload (requires `--features policy`)
 //! - `mcp_server.rs` - MCP server with custom tools (requires `--features rmcp`)
 //! - `mcp_integration.rs` - Full E2E with Claude using MCP tools
+//! - `sdk_mcp_server.rs` - In-process SDK MCP server (requires `--features rmcp`)
 //! - `introspection_demo.rs` - Session info, models, commands, MCP status
+//! - `partial_messages_demo.rs` - Typed stream events and partial message accumulation
 //! - `plan_mode_demo.rs` - Plan mode with approval workflow
 //! - `oauth_demo.rs` - OAuth authentication with PKCE
 //!
@@ -293,13 +321,22 @@
 pub mod auth;
 pub mod callbacks;
 pub mod client;
+pub mod command_hook;
 pub mod control;
 pub mod error;
+pub mod export;
 pub mod hooks;
 pub mod mcp;
 pub mod message;
 pub mod permissions;
+#[cfg(feature = "policy")]
+pub mod policy;
 pub mod query;
+pub mod session;
+pub mod structured_output;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transcript;
 pub mod transport;
 pub mod types;
 pub mod utils;
@@ -307,27 +344,33 @@ pub mod utils;
 // Re-export commonly used types
 pub use callbacks::{
     FnHookCallback, FnPermissionCallback, HookCallback, PermissionCallback, SharedHookCallback,
-    SharedPermissionCallback,
+    SharedPermissionCallback, TypedHookCallback,
 };
 pub use client::ClaudeSDKClient;
 pub use error::{ClaudeError, Result};
 pub use futures::StreamExt;
 pub use hooks::{HookManager, HookMatcherBuilder};
-pub use message::parse_message;
+pub use message::{PartialMessageAccumulator, parse_message};
 pub use permissions::{PermissionManager, PermissionManagerBuilder};
-pub use query::query;
+#[cfg(feature = "schemars")]
+pub use query::query_typed;
+pub use query::{query, query_with_transport};
+#[cfg(feature = "schemars")]
+pub use schemars;
 pub use transport::{
-    MIN_CLI_VERSION, PromptInput, SubprocessTransport, Transport, check_claude_version,
+    MIN_CLI_VERSION, PromptInput, RecordingTransport, ReplaySpeed, ReplayTransport,
+    SubprocessTransport, Transport, check_claude_version,
 };
 pub use types::{
     AgentDefinition, CanUseToolCallback, ClaudeAgentOptions, ClaudeAgentOptionsBuilder,
-    ContentBlock, ContentValue, HookContext, HookDecision, HookEvent, HookMatcher, HookOutput,
-    McpHttpServerConfig, McpServerConfig, McpServers, McpSseServerConfig, McpStdioServerConfig,
-    Message, OutputFormat, PermissionBehavior, PermissionMode, PermissionRequest, PermissionResult,
-    PermissionResultAllow, PermissionResultDeny, PermissionRuleValue, PermissionUpdate,
-    PermissionUpdateDestination, RequestId, SdkMcpServerConfig, SessionId, SettingSource,
-    SystemPrompt, SystemPromptPreset, ToolName, ToolPermissionContext, UsageData, UsageLimit,
-    UserContent,
+    ContentBlock, ContentValue, ContextHookOutput, HookContext, HookDecision, HookEvent,
+    HookMatcher, HookOutput, McpHttpServerConfig, McpServerConfig, McpServers, McpSseServerConfig,
+    McpStdioServerConfig, Message, OutputFormat, PermissionBehavior, PermissionMode,
+    PermissionRequest, PermissionResult, PermissionResultAllow, PermissionResultDeny,
+    PermissionRuleValue, PermissionUpdate, PermissionUpdateDestination, PreToolUseHookOutput,
+    RequestId, SdkMcpServerConfig, SessionId, SettingSource, SystemPrompt, SystemPromptPreset,
+    ToolName, ToolPermissionContext, Usage, UsageData, UsageLimit, UsageTracker, UserContent,
+    UserContentBlock,
 };
 
 /// Version of the SDK
@@ -12,7 +12,14 @@
 //! - [`McpHttpServerConfig`] - Connect via HTTP
 //! - [`SdkMcpServerConfig`] - In-process SDK server (requires `rmcp` feature)
 //!
-//! # SDK MCP Servers (requires `rmcp` feature)
+//! # In-process SDK MCP Servers
+//!
+//! [`SdkMcpServer`] implementations registered on
+//! `ClaudeAgentOptions::sdk_mcp_servers` run inside the application. The CLI
+//! reaches them through `mcp_message` control requests instead of a child
+//! process, so tools can read application state directly.
+//!
+//! # SDK MCP Servers with rmcp (requires `rmcp` feature)
 //!
 //! Enable the `rmcp` feature to create in-process MCP servers using the official
 //! [rmcp](https://crates.io/crates/rmcp) crate:
@@ -70,6 +77,8 @@
 //! - **Type safety** - Compile-time schema validation via schemars
 //! - **Ergonomic macros** - `#[tool]`, `#[tool_router]`, `#[tool_handler]`
 //! - **Full MCP support** - Resources, prompts, sampling, and more via rmcp
+//!
+//! Wrap a handler in `RmcpSdkServer` to register it as an in-process server.
 
 // Re-export configuration types (always available)
 pub use crate::types::mcp::{
@@ -77,6 +86,10 @@ pub use crate::types::mcp::{
     SdkMcpServerConfig,
 };
 
+mod server;
+pub use server::SdkMcpServer;
+pub(crate) use server::jsonrpc_error;
+
 // SDK MCP server support via rmcp (optional)
 #[cfg(feature = "rmcp")]
 mod sdk;
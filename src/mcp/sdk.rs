@@ -43,6 +43,18 @@
 //! }
 //! ```
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_trait::async_trait;
+use rmcp::service::{NotificationContext, ServiceRole};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::task::JoinHandle;
+
+use super::SdkMcpServer;
+use crate::error::{ClaudeError, Result};
+
 // Re-export rmcp macros for tool definition
 pub use rmcp::{tool, tool_handler, tool_router};
 
@@ -84,9 +96,430 @@ pub use rmcp::transport::io::stdio;
 // Re-export service traits for serving
 pub use rmcp::ServiceExt;
 
-/// Marker trait for SDK MCP servers
+// ============================================================================
+// rmcp Adapter
+// ============================================================================
+
+/// Buffer size of the in-memory pipe between the adapter and the rmcp service
+const PIPE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Adapts an `rmcp::ServerHandler` into an [`SdkMcpServer`]
+///
+/// The handler is served by rmcp over an in-memory pipe, started on the first
+/// message, so the CLI drives the full MCP lifecycle (`initialize`,
+/// `tools/list`, `tools/call`, ...) exactly as it would for a child process.
+/// A new `initialize` (the CLI reconnecting) or a stopped service starts a
+/// fresh pipe; every connection shares the same handler.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+/// use anthropic_agent_sdk::ClaudeAgentOptions;
+/// use anthropic_agent_sdk::mcp::{RmcpSdkServer, SdkMcpServer};
+///
+/// let mut servers: HashMap<String, Arc<dyn SdkMcpServer>> = HashMap::new();
+/// servers.insert("calc".to_string(), Arc::new(RmcpSdkServer::new(Calculator::new())));
+///
+/// let options = ClaudeAgentOptions::builder()
+///     .sdk_mcp_servers(servers)
+///     .allowed_tools(vec!["mcp__calc__add".into()])
+///     .build();
+/// ```
+pub struct RmcpSdkServer<S> {
+    handler: Arc<S>,
+    connection: tokio::sync::Mutex<Option<Arc<RmcpConnection>>>,
+}
+
+/// Client side of the pipe to a running rmcp service
 ///
-/// Any type implementing `rmcp::ServerHandler` automatically implements this trait,
-/// making it usable as an SDK MCP server.
-pub trait SdkMcpServer: rmcp::ServerHandler {}
-impl<T: rmcp::ServerHandler> SdkMcpServer for T {}
+/// Dropping it stops the service.
+struct RmcpConnection {
+    writer: tokio::sync::Mutex<tokio::io::WriteHalf<tokio::io::DuplexStream>>,
+    pending: PendingResponses,
+    closed: Arc<AtomicBool>,
+    service_task: JoinHandle<()>,
+    reader_task: JoinHandle<()>,
+}
+
+impl RmcpConnection {
+    /// Register a request awaiting its response
+    ///
+    /// Checked under the `pending` lock, so a request either sees the
+    /// connection closed or is failed when it closes.
+    fn expect_response(
+        &self,
+        id: &serde_json::Value,
+    ) -> Result<tokio::sync::oneshot::Receiver<serde_json::Value>> {
+        let mut guard = self
+            .pending
+            .lock()
+            .map_err(|_| ClaudeError::mcp("SDK MCP server state poisoned"))?;
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(ClaudeError::mcp("SDK MCP server stopped before responding"));
+        }
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        guard.insert(id.to_string(), tx);
+        Ok(rx)
+    }
+}
+
+impl Drop for RmcpConnection {
+    fn drop(&mut self) {
+        self.service_task.abort();
+        self.reader_task.abort();
+    }
+}
+
+type PendingResponses =
+    Arc<std::sync::Mutex<HashMap<String, tokio::sync::oneshot::Sender<serde_json::Value>>>>;
+
+/// Serves a shared handler, since rmcp takes its service by value
+struct SharedHandler<S>(Arc<S>);
+
+impl<S: rmcp::ServerHandler> rmcp::Service<RoleServer> for SharedHandler<S> {
+    fn handle_request(
+        &self,
+        request: <RoleServer as ServiceRole>::PeerReq,
+        context: RequestContext<RoleServer>,
+    ) -> impl Future<Output = std::result::Result<<RoleServer as ServiceRole>::Resp, McpError>> + Send + '_
+    {
+        rmcp::Service::<RoleServer>::handle_request(self.0.as_ref(), request, context)
+    }
+
+    fn handle_notification(
+        &self,
+        notification: <RoleServer as ServiceRole>::PeerNot,
+        context: NotificationContext<RoleServer>,
+    ) -> impl Future<Output = std::result::Result<(), McpError>> + Send + '_ {
+        rmcp::Service::<RoleServer>::handle_notification(self.0.as_ref(), notification, context)
+    }
+
+    fn get_info(&self) -> <RoleServer as ServiceRole>::Info {
+        rmcp::Service::<RoleServer>::get_info(self.0.as_ref())
+    }
+}
+
+impl<S: rmcp::ServerHandler> RmcpSdkServer<S> {
+    /// Wrap an rmcp server handler
+    #[must_use]
+    pub fn new(handler: S) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            connection: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Connection for `message`, starting the rmcp service if there is none,
+    /// the previous one stopped, or the CLI starts over with `initialize`
+    async fn connection(&self, message: &serde_json::Value) -> Arc<RmcpConnection> {
+        let is_initialize =
+            message.get("method").and_then(serde_json::Value::as_str) == Some("initialize");
+        let mut guard = self.connection.lock().await;
+        match guard.as_ref() {
+            Some(connection) if !is_initialize && !connection.closed.load(Ordering::SeqCst) => {
+                connection.clone()
+            }
+            _ => {
+                let connection = Arc::new(Self::start(SharedHandler(self.handler.clone())));
+                *guard = Some(connection.clone());
+                connection
+            }
+        }
+    }
+
+    fn start(handler: SharedHandler<S>) -> RmcpConnection {
+        let (client_io, server_io) = tokio::io::duplex(PIPE_BUFFER_SIZE);
+
+        // rmcp completes the initialize handshake inside serve(), driven by
+        // the CLI's own initialize request, so it must run in the background
+        let service_task = tokio::spawn(async move {
+            match handler.serve(server_io).await {
+                Ok(running) => {
+                    if let Err(e) = running.waiting().await {
+                        tracing::warn!(error = %e, "SDK MCP server task failed");
+                    }
+                }
+                Err(e) => tracing::warn!(error = %e, "SDK MCP server initialization failed"),
+            }
+        });
+
+        let (reader, writer) = tokio::io::split(client_io);
+        let pending: PendingResponses = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let pending_clone = pending.clone();
+        let closed_clone = closed.clone();
+        let reader_task = tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+                    tracing::debug!(line = %line, "Ignoring malformed SDK MCP server output");
+                    continue;
+                };
+                let is_response = message.get("result").is_some() || message.get("error").is_some();
+                let sender = match message.get("id") {
+                    Some(id) if is_response => pending_clone
+                        .lock()
+                        .ok()
+                        .and_then(|mut guard| guard.remove(&id.to_string())),
+                    _ => None,
+                };
+                if let Some(tx) = sender {
+                    let _ = tx.send(message);
+                } else {
+                    // Server-initiated requests and notifications cannot be
+                    // relayed to the CLI over mcp_message
+                    tracing::debug!(message = %message, "Dropping SDK MCP server message");
+                }
+            }
+            // Service stopped: fail everything still waiting, and start over
+            // on the next message
+            let guard = pending_clone.lock();
+            closed_clone.store(true, Ordering::SeqCst);
+            if let Ok(mut guard) = guard {
+                guard.clear();
+            }
+        });
+
+        RmcpConnection {
+            writer: tokio::sync::Mutex::new(writer),
+            pending,
+            closed,
+            service_task,
+            reader_task,
+        }
+    }
+}
+
+#[async_trait]
+impl<S: rmcp::ServerHandler> SdkMcpServer for RmcpSdkServer<S> {
+    async fn handle_message(
+        &self,
+        message: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>> {
+        let connection = self.connection(&message).await;
+
+        // Only requests (a method plus an id) get a response
+        let response_rx = match message.get("id") {
+            Some(id) if message.get("method").is_some() => Some(connection.expect_response(id)?),
+            _ => None,
+        };
+
+        let line = format!("{message}\n");
+        {
+            let mut writer = connection.writer.lock().await;
+            writer
+                .write_all(line.as_bytes())
+                .await
+                .map_err(|e| ClaudeError::mcp(format!("Failed to write to SDK MCP server: {e}")))?;
+            writer
+                .flush()
+                .await
+                .map_err(|e| ClaudeError::mcp(format!("Failed to flush SDK MCP server: {e}")))?;
+        }
+
+        match response_rx {
+            Some(rx) => rx
+                .await
+                .map(Some)
+                .map_err(|_| ClaudeError::mcp("SDK MCP server stopped before responding")),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Explicit imports: the rmcp macros expand to a bare `Result`
+    use super::{
+        Parameters, RmcpSdkServer, SdkMcpServer, ServerCapabilities, ServerHandler, ServerInfo,
+        ToolRouter, tool, tool_handler, tool_router,
+    };
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[derive(Deserialize, schemars::JsonSchema)]
+    struct AddParams {
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Clone)]
+    struct Calculator {
+        tool_router: ToolRouter<Self>,
+    }
+
+    #[tool_router]
+    impl Calculator {
+        fn new() -> Self {
+            Self {
+                tool_router: Self::tool_router(),
+            }
+        }
+
+        #[tool(description = "Add two numbers")]
+        #[allow(clippy::unused_self)]
+        fn add(&self, Parameters(params): Parameters<AddParams>) -> String {
+            (params.a + params.b).to_string()
+        }
+
+        #[tool(description = "Never returns")]
+        #[allow(clippy::unused_self)]
+        async fn hang(&self) -> String {
+            std::future::pending().await
+        }
+    }
+
+    #[tool_handler]
+    impl ServerHandler for Calculator {
+        fn get_info(&self) -> ServerInfo {
+            ServerInfo {
+                capabilities: ServerCapabilities::builder().enable_tools().build(),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rmcp_server_lifecycle() {
+        let server = RmcpSdkServer::new(Calculator::new());
+
+        let init = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "test", "version": "1.0"}
+                }
+            }))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(init["id"], 0);
+        assert!(init["result"]["capabilities"]["tools"].is_object());
+
+        let notified = server
+            .handle_message(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await
+            .unwrap();
+        assert!(notified.is_none());
+
+        let tools = server
+            .handle_message(json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}))
+            .await
+            .unwrap()
+            .unwrap();
+        let tools = tools["result"]["tools"].as_array().unwrap();
+        assert!(tools.iter().any(|tool| tool["name"] == "add"));
+
+        let call = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": "call-2",
+                "method": "tools/call",
+                "params": {"name": "add", "arguments": {"a": 2, "b": 40}}
+            }))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(call["id"], "call-2");
+        assert_eq!(call["result"]["content"][0]["text"], "42");
+
+        // The CLI reconnecting starts a new session with the same handler
+        let reinit = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 3,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {},
+                    "clientInfo": {"name": "test", "version": "1.0"}
+                }
+            }))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(reinit["result"]["capabilities"]["tools"].is_object());
+        server
+            .handle_message(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await
+            .unwrap();
+        let call = server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "tools/call",
+                "params": {"name": "add", "arguments": {"a": 1, "b": 1}}
+            }))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(call["result"]["content"][0]["text"], "2");
+    }
+
+    fn initialize(id: i64) -> serde_json::Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "1.0"}
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_request_fails_when_the_service_stops() {
+        let server = Arc::new(RmcpSdkServer::new(Calculator::new()));
+        server.handle_message(initialize(0)).await.unwrap();
+        server
+            .handle_message(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await
+            .unwrap();
+
+        let call = tokio::spawn({
+            let server = server.clone();
+            async move {
+                server
+                    .handle_message(json!({
+                        "jsonrpc": "2.0",
+                        "id": 1,
+                        "method": "tools/call",
+                        "params": {"name": "hang", "arguments": {}}
+                    }))
+                    .await
+            }
+        });
+        let connection = loop {
+            let connection = server.connection.lock().await.clone().unwrap();
+            if !connection.pending.lock().unwrap().is_empty() {
+                break connection;
+            }
+            tokio::task::yield_now().await;
+        };
+
+        // Dropping the service mid-call fails the call instead of hanging it
+        connection.service_task.abort();
+        let result = tokio::time::timeout(Duration::from_secs(2), call)
+            .await
+            .expect("the call outlived the service")
+            .unwrap();
+        assert!(result.is_err());
+
+        // A request registered after the pipe closed fails too
+        while !connection.closed.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
+        }
+        assert!(connection.expect_response(&json!(2)).is_err());
+    }
+}
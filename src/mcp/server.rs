@@ -0,0 +1,68 @@
+//! In-process SDK MCP servers
+//!
+//! An SDK MCP server runs inside the application instead of as a child
+//! process. The CLI lists it in `--mcp-config` as `{"type": "sdk"}` and sends
+//! its JSON-RPC traffic over the control channel as `mcp_message` requests,
+//! which the client routes to the registered [`SdkMcpServer`].
+
+use async_trait::async_trait;
+
+use crate::error::Result;
+
+/// MCP server that answers JSON-RPC messages in-process
+///
+/// Register instances on [`ClaudeAgentOptions::sdk_mcp_servers`](crate::ClaudeAgentOptions)
+/// under the name the CLI should expose them as (tools appear as
+/// `mcp__<name>__<tool>`). With the `rmcp` feature, any `rmcp::ServerHandler`
+/// can be adapted with `mcp::RmcpSdkServer`.
+///
+/// # Example
+///
+/// ```
+/// use anthropic_agent_sdk::mcp::SdkMcpServer;
+/// use async_trait::async_trait;
+/// use serde_json::{Value, json};
+///
+/// struct Echo;
+///
+/// #[async_trait]
+/// impl SdkMcpServer for Echo {
+///     async fn handle_message(
+///         &self,
+///         message: Value,
+///     ) -> anthropic_agent_sdk::Result<Option<Value>> {
+///         // Notifications carry no id and get no response
+///         let Some(id) = message.get("id").cloned() else {
+///             return Ok(None);
+///         };
+///         Ok(Some(json!({"jsonrpc": "2.0", "id": id, "result": {}})))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait SdkMcpServer: Send + Sync {
+    /// Handle one JSON-RPC message from the CLI
+    ///
+    /// Returns the JSON-RPC response for requests, or `None` for
+    /// notifications.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be processed; the client
+    /// reports it to the CLI as a JSON-RPC internal error.
+    async fn handle_message(&self, message: serde_json::Value)
+    -> Result<Option<serde_json::Value>>;
+}
+
+/// JSON-RPC error response for a message that could not be handled
+pub(crate) fn jsonrpc_error(
+    message: &serde_json::Value,
+    code: i64,
+    error: impl Into<String>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": message.get("id").cloned().unwrap_or(serde_json::Value::Null),
+        "error": {"code": code, "message": error.into()}
+    })
+}
@@ -0,0 +1,374 @@
+//! Accumulator folding stream events into a growing assistant message
+
+use std::collections::HashMap;
+
+use crate::types::{AssistantMessageContent, ContentBlock, ContentDelta, Message, StreamEvent};
+
+/// Builds an [`AssistantMessageContent`] from partial-message stream events
+///
+/// Feed it every message (or typed [`StreamEvent`]) received with
+/// `include_partial_messages` enabled. Text, thinking and signature deltas are
+/// appended to their blocks as they arrive; tool call input JSON is kept as a
+/// raw string while it streams (see [`partial_tool_input`](Self::partial_tool_input))
+/// and parsed into the `tool_use` block's `input` when the block stops.
+///
+/// A `message_start` event begins a new message. Subagent events carry a
+/// `parent_tool_use_id`; use one accumulator per parent to keep them apart.
+///
+/// # Example
+///
+/// ```no_run
+/// # use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient};
+/// # use anthropic_agent_sdk::message::PartialMessageAccumulator;
+/// # use anthropic_agent_sdk::types::ContentBlock;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let options = ClaudeAgentOptions::builder()
+///     .include_partial_messages(true)
+///     .build();
+/// let mut client = ClaudeSDKClient::new(options, None).await?;
+/// client.send_message("Write a haiku").await?;
+///
+/// let mut partial = PartialMessageAccumulator::new();
+/// while let Some(message) = client.next_message().await {
+///     if partial.observe(&message?) {
+///         if let Some(ContentBlock::Text { text }) =
+///             partial.message().and_then(|m| m.content.last())
+///         {
+///             println!("so far: {text}");
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PartialMessageAccumulator {
+    /// Message being built
+    message: Option<AssistantMessageContent>,
+    /// Position in `message.content` of each stream block index
+    positions: HashMap<usize, usize>,
+    /// Raw input JSON streamed so far, by stream block index
+    tool_inputs: HashMap<usize, String>,
+    /// Stop reason from `message_delta`
+    stop_reason: Option<String>,
+    /// Whether `message_stop` has been received
+    complete: bool,
+}
+
+impl PartialMessageAccumulator {
+    /// Create an empty accumulator
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a message if it is a stream event
+    ///
+    /// Returns `true` if the message was a stream event and was applied.
+    pub fn observe(&mut self, message: &Message) -> bool {
+        match message.stream_event() {
+            Some(event) => {
+                self.apply(&event);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply a typed stream event
+    pub fn apply(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::MessageStart { message } => {
+                self.reset();
+                self.message = Some(AssistantMessageContent {
+                    id: message.id.clone(),
+                    model: message.model.clone(),
+                    content: Vec::new(),
+                    usage: message.usage.clone(),
+                });
+                for (index, block) in message.content.iter().enumerate() {
+                    self.start_block(index, block.clone());
+                }
+            }
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => self.start_block(*index, content_block.clone()),
+            StreamEvent::ContentBlockDelta { index, delta } => self.apply_delta(*index, delta),
+            StreamEvent::ContentBlockStop { index } => self.finish_block(*index),
+            StreamEvent::MessageDelta { delta, usage } => {
+                if delta.stop_reason.is_some() {
+                    self.stop_reason.clone_from(&delta.stop_reason);
+                }
+                if let Some(usage) = usage {
+                    self.message_mut()
+                        .usage
+                        .get_or_insert_with(Default::default)
+                        .update(usage);
+                }
+            }
+            StreamEvent::MessageStop => self.complete = true,
+            StreamEvent::Ping | StreamEvent::Error { .. } | StreamEvent::Unknown => {}
+        }
+    }
+
+    /// The message built so far
+    #[must_use]
+    pub fn message(&self) -> Option<&AssistantMessageContent> {
+        self.message.as_ref()
+    }
+
+    /// Raw input JSON streamed so far for the tool call with `tool_use_id`
+    ///
+    /// The string is usually incomplete JSON until the block has stopped.
+    #[must_use]
+    pub fn partial_tool_input(&self, tool_use_id: &str) -> Option<&str> {
+        let content = &self.message.as_ref()?.content;
+        self.tool_inputs.iter().find_map(|(index, json)| {
+            match content.get(*self.positions.get(index)?)? {
+                ContentBlock::ToolUse { id, .. } if id == tool_use_id => Some(json.as_str()),
+                _ => None,
+            }
+        })
+    }
+
+    /// Stop reason reported by `message_delta`, if any
+    #[must_use]
+    pub fn stop_reason(&self) -> Option<&str> {
+        self.stop_reason.as_deref()
+    }
+
+    /// Whether `message_stop` has been received for the current message
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Take the message built so far, leaving the accumulator empty
+    pub fn take(&mut self) -> Option<AssistantMessageContent> {
+        let message = self.message.take();
+        self.reset();
+        message
+    }
+
+    /// Discard the current message
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Current message, created if a block arrives without `message_start`
+    fn message_mut(&mut self) -> &mut AssistantMessageContent {
+        self.message.get_or_insert_with(|| AssistantMessageContent {
+            id: None,
+            model: String::new(),
+            content: Vec::new(),
+            usage: None,
+        })
+    }
+
+    fn start_block(&mut self, index: usize, block: ContentBlock) {
+        if matches!(block, ContentBlock::ToolUse { .. }) {
+            self.tool_inputs.insert(index, String::new());
+        }
+        let existing = self.positions.get(&index).copied();
+        let content = &mut self.message_mut().content;
+        match existing {
+            Some(position) => content[position] = block,
+            None => {
+                content.push(block);
+                let position = content.len() - 1;
+                self.positions.insert(index, position);
+            }
+        }
+    }
+
+    fn block_mut(&mut self, index: usize) -> Option<&mut ContentBlock> {
+        let position = *self.positions.get(&index)?;
+        self.message.as_mut()?.content.get_mut(position)
+    }
+
+    fn apply_delta(&mut self, index: usize, delta: &ContentDelta) {
+        if let ContentDelta::InputJsonDelta { partial_json } = delta {
+            self.tool_inputs
+                .entry(index)
+                .or_default()
+                .push_str(partial_json);
+            return;
+        }
+
+        let Some(block) = self.block_mut(index) else {
+            tracing::debug!(index, "Stream delta for unknown content block");
+            return;
+        };
+        match (block, delta) {
+            (ContentBlock::Text { text }, ContentDelta::TextDelta { text: fragment }) => {
+                text.push_str(fragment);
+            }
+            (
+                ContentBlock::Thinking { thinking, .. },
+                ContentDelta::ThinkingDelta { thinking: fragment },
+            ) => thinking.push_str(fragment),
+            (
+                ContentBlock::Thinking { signature, .. },
+                ContentDelta::SignatureDelta {
+                    signature: streamed,
+                },
+            ) => signature.clone_from(streamed),
+            (block, delta) => {
+                tracing::debug!(?block, ?delta, "Ignoring stream delta for content block");
+            }
+        }
+    }
+
+    fn finish_block(&mut self, index: usize) {
+        let Some(json) = self.tool_inputs.get(&index).cloned() else {
+            return;
+        };
+        let Some(ContentBlock::ToolUse { input, .. }) = self.block_mut(index) else {
+            return;
+        };
+        if json.trim().is_empty() {
+            return;
+        }
+        match serde_json::from_str(&json) {
+            Ok(parsed) => *input = parsed,
+            Err(e) => tracing::debug!(error = %e, "Streamed tool input is not valid JSON"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn apply_all(accumulator: &mut PartialMessageAccumulator, events: &[serde_json::Value]) {
+        for event in events {
+            accumulator.apply(&serde_json::from_value(event.clone()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_accumulates_text_and_thinking() {
+        let mut accumulator = PartialMessageAccumulator::new();
+        apply_all(
+            &mut accumulator,
+            &[
+                json!({"type": "message_start", "message": {
+                    "id": "msg_1", "model": "claude-sonnet-4-5", "content": [],
+                    "usage": {"input_tokens": 9, "output_tokens": 1}
+                }}),
+                json!({"type": "content_block_start", "index": 0,
+                    "content_block": {"type": "thinking", "thinking": ""}}),
+                json!({"type": "content_block_delta", "index": 0,
+                    "delta": {"type": "thinking_delta", "thinking": "Let me "}}),
+                json!({"type": "content_block_delta", "index": 0,
+                    "delta": {"type": "thinking_delta", "thinking": "think"}}),
+                json!({"type": "content_block_delta", "index": 0,
+                    "delta": {"type": "signature_delta", "signature": "sig"}}),
+                json!({"type": "content_block_stop", "index": 0}),
+                json!({"type": "content_block_start", "index": 1,
+                    "content_block": {"type": "text", "text": ""}}),
+                json!({"type": "content_block_delta", "index": 1,
+                    "delta": {"type": "text_delta", "text": "Hello"}}),
+                json!({"type": "content_block_delta", "index": 1,
+                    "delta": {"type": "text_delta", "text": " world"}}),
+            ],
+        );
+
+        let message = accumulator.message().unwrap();
+        assert_eq!(message.id.as_deref(), Some("msg_1"));
+        assert_eq!(message.content.len(), 2);
+        match &message.content[0] {
+            ContentBlock::Thinking {
+                thinking,
+                signature,
+            } => {
+                assert_eq!(thinking, "Let me think");
+                assert_eq!(signature, "sig");
+            }
+            other => panic!("unexpected block {other:?}"),
+        }
+        assert!(
+            matches!(&message.content[1], ContentBlock::Text { text } if text == "Hello world")
+        );
+        assert!(!accumulator.is_complete());
+
+        apply_all(
+            &mut accumulator,
+            &[
+                json!({"type": "content_block_stop", "index": 1}),
+                json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"},
+                    "usage": {"output_tokens": 12}}),
+                json!({"type": "message_stop"}),
+            ],
+        );
+        assert!(accumulator.is_complete());
+        assert_eq!(accumulator.stop_reason(), Some("end_turn"));
+        let message = accumulator.take().unwrap();
+        let usage = message.usage.unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (9, 12));
+        assert!(accumulator.message().is_none());
+    }
+
+    #[test]
+    fn test_streams_tool_input_json() {
+        let mut accumulator = PartialMessageAccumulator::new();
+        apply_all(
+            &mut accumulator,
+            &[
+                json!({"type": "message_start", "message": {"id": "msg_2", "model": "m"}}),
+                json!({"type": "content_block_start", "index": 0, "content_block":
+                    {"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {}}}),
+                json!({"type": "content_block_delta", "index": 0,
+                    "delta": {"type": "input_json_delta", "partial_json": "{\"command\": "}}),
+            ],
+        );
+        assert_eq!(
+            accumulator.partial_tool_input("toolu_1"),
+            Some("{\"command\": ")
+        );
+        assert!(accumulator.partial_tool_input("toolu_other").is_none());
+
+        apply_all(
+            &mut accumulator,
+            &[
+                json!({"type": "content_block_delta", "index": 0,
+                    "delta": {"type": "input_json_delta", "partial_json": "\"ls -la\"}"}}),
+                json!({"type": "content_block_stop", "index": 0}),
+            ],
+        );
+        match &accumulator.message().unwrap().content[0] {
+            ContentBlock::ToolUse { input, .. } => assert_eq!(input["command"], "ls -la"),
+            other => panic!("unexpected block {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_observe_ignores_other_messages_and_restarts_on_message_start() {
+        let mut accumulator = PartialMessageAccumulator::new();
+        let text: Message = serde_json::from_value(json!({
+            "type": "assistant",
+            "message": {"model": "m", "content": []}
+        }))
+        .unwrap();
+        assert!(!accumulator.observe(&text));
+
+        let event = |event: serde_json::Value| -> Message {
+            serde_json::from_value(json!({
+                "type": "stream_event", "uuid": "u", "session_id": "s", "event": event
+            }))
+            .unwrap()
+        };
+        assert!(
+            accumulator.observe(&event(json!({"type": "content_block_start", "index": 0,
+            "content_block": {"type": "text", "text": "a"}})))
+        );
+        assert_eq!(accumulator.message().unwrap().content.len(), 1);
+
+        assert!(accumulator.observe(&event(
+            json!({"type": "message_start", "message": {"id": "msg_3", "model": "m"}})
+        )));
+        assert!(accumulator.message().unwrap().content.is_empty());
+    }
+}
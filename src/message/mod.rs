@@ -1,8 +1,11 @@
 //! Message parsing and handling
 //!
 //! This module provides functionality for parsing JSON messages from the Claude Code CLI
-//! into typed Rust structures.
+//! into typed Rust structures, and for folding partial-message stream events
+//! into assistant messages.
 
+pub mod accumulator;
 pub mod parser;
 
+pub use accumulator::PartialMessageAccumulator;
 pub use parser::parse_message;
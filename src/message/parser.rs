@@ -3,16 +3,21 @@
 use crate::error::{ClaudeError, Result};
 use crate::types::Message;
 
+/// Message `type` tags with a typed `Message` variant
+const KNOWN_MESSAGE_TYPES: [&str; 5] = ["user", "assistant", "system", "result", "stream_event"];
+
 /// Parse a JSON value into a typed Message
 ///
 /// # Arguments
 /// * `data` - Raw JSON value from CLI output
 ///
 /// # Returns
-/// Parsed Message object or error
+/// Parsed Message object or error. Message types this SDK version does not
+/// know parse as `Message::Unknown`.
 ///
 /// # Errors
-/// Returns `ClaudeError::MessageParse` if the JSON cannot be parsed into a valid Message
+/// Returns `ClaudeError::MessageParse` if the JSON is not an object, or if a
+/// known message type has a payload that does not match its variant
 ///
 /// # Security Note
 /// For additional security against deeply nested JSON attacks, consider using
@@ -29,14 +34,40 @@ pub fn parse_message(data: serde_json::Value) -> Result<Message> {
         );
     }
 
-    serde_json::from_value(data.clone()).map_err(|e| {
-        ClaudeError::message_parse(format!("Failed to parse message: {e}"), Some(data))
-    })
+    if !data.is_object() {
+        return Err(ClaudeError::message_parse(
+            "Failed to parse message: expected a JSON object",
+            Some(data),
+        ));
+    }
+
+    let message_type = data.get("type").and_then(|t| t.as_str());
+    if !message_type.is_some_and(|t| KNOWN_MESSAGE_TYPES.contains(&t)) {
+        return Ok(Message::Unknown { raw: data });
+    }
+
+    match serde_json::from_value(data.clone()) {
+        // The untagged `Unknown` variant also accepts a known type whose
+        // payload failed to match; that is a parse error, not a new type
+        Ok(Message::Unknown { .. }) => Err(ClaudeError::message_parse(
+            format!(
+                "Failed to parse message: unexpected payload for type '{}'",
+                message_type.unwrap_or_default()
+            ),
+            Some(data),
+        )),
+        Ok(message) => Ok(message),
+        Err(e) => Err(ClaudeError::message_parse(
+            format!("Failed to parse message: {e}"),
+            Some(data),
+        )),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::ContentBlock;
     use serde_json::json;
 
     #[test]
@@ -54,13 +85,92 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_invalid_message() {
+    fn test_parse_unknown_message_type() {
         let data = json!({
             "type": "invalid_type",
             "data": "some data"
         });
 
-        let result = parse_message(data);
-        assert!(result.is_err());
+        match parse_message(data.clone()).unwrap() {
+            Message::Unknown { raw } => assert_eq!(raw, data),
+            other => panic!("unexpected message {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_malformed_result_is_an_error() {
+        // `duration_api_ms` missing and `usage` not an object
+        let data = json!({
+            "type": "result",
+            "subtype": "success",
+            "duration_ms": 100,
+            "is_error": false,
+            "num_turns": 1,
+            "session_id": "s1",
+            "usage": "lots"
+        });
+
+        let err = parse_message(data).unwrap_err();
+        assert!(matches!(err, ClaudeError::MessageParse { .. }));
+        assert!(err.to_string().contains("result"));
+    }
+
+    #[test]
+    fn test_parse_malformed_content_block_is_an_error() {
+        // A `tool_use` block without its `input`
+        let data = json!({
+            "type": "assistant",
+            "message": {
+                "model": "claude-sonnet-4-5",
+                "content": [{"type": "tool_use", "id": "t1", "name": "Bash"}]
+            }
+        });
+
+        let err = parse_message(data).unwrap_err();
+        assert!(matches!(err, ClaudeError::MessageParse { .. }));
+    }
+
+    #[test]
+    fn test_parse_newer_content_blocks() {
+        let data = json!({
+            "type": "assistant",
+            "message": {
+                "model": "claude-sonnet-4-5",
+                "content": [
+                    {"type": "redacted_thinking", "data": "EmwKAhgB"},
+                    {"type": "server_tool_use", "id": "srvtoolu_1", "name": "web_search",
+                     "input": {"query": "rust"}},
+                    {"type": "web_search_tool_result", "tool_use_id": "srvtoolu_1",
+                     "content": [{"type": "web_search_result", "url": "https://www.rust-lang.org"}]},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBO"}},
+                    {"type": "container_upload", "file_id": "file_1"}
+                ]
+            }
+        });
+
+        let Message::Assistant { message, .. } = parse_message(data).unwrap() else {
+            panic!("expected assistant message");
+        };
+        assert!(matches!(
+            message.content[0],
+            ContentBlock::RedactedThinking { .. }
+        ));
+        assert!(matches!(
+            &message.content[1],
+            ContentBlock::ServerToolUse { name, .. } if name == "web_search"
+        ));
+        assert!(matches!(
+            message.content[2],
+            ContentBlock::WebSearchToolResult { .. }
+        ));
+        assert!(matches!(message.content[3], ContentBlock::Image { .. }));
+        match &message.content[4] {
+            ContentBlock::Unknown { raw } => {
+                assert_eq!(raw["type"], "container_upload");
+                // Unknown blocks serialize back to the JSON they came from
+                assert_eq!(serde_json::to_value(&message.content[4]).unwrap(), *raw);
+            }
+            other => panic!("unexpected block {other:?}"),
+        }
     }
 }
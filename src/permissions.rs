@@ -12,6 +12,9 @@ use crate::types::{
 };
 
 /// Permission manager for tool access control
+///
+/// Cloning is cheap and shares the callback.
+#[derive(Clone)]
 pub struct PermissionManager {
     /// Tool permission callback
     callback: Option<CanUseToolCallback>,
@@ -0,0 +1,1083 @@
+//! Declarative hook and permission policies
+//!
+//! A [`Policy`] describes hook rules and tool permissions in a TOML or JSON
+//! file instead of Rust closures, so agent policy can be owned and changed
+//! without recompiling. [`PolicyEngine`] compiles it into [`HookMatcher`]s and
+//! a [`CanUseToolCallback`], and can reload it when the file changes.
+//!
+//! # Format
+//!
+//! ```toml
+//! # Decision for tool uses no permission rule matches ("allow" or "deny")
+//! default_permission = "allow"
+//!
+//! [[rules]]
+//! name = "no-recursive-delete"
+//! event = "PreToolUse"            # default
+//! tool = "Bash"                   # regex, must match the whole tool name
+//! when = [{ pointer = "/tool_input/command", matches = "rm\\s+-rf" }]
+//! action = "block"
+//! message = "Recursive deletes are not allowed"
+//!
+//! [[rules]]
+//! tool = "Write|Edit"
+//! action = "rewrite"
+//! set = { "/tool_input/file_path" = "/sandbox/out.txt" }
+//!
+//! [[rules]]
+//! event = "PostToolUse"
+//! action = "add_context"
+//! context = "Run the test suite after editing files"
+//!
+//! [[rules]]
+//! event = "PostToolUseFailure"
+//! action = "log"
+//!
+//! [[permissions]]
+//! tool = "Write"
+//! when = [{ pointer = "/tool_input/file_path", matches = "/etc/.*" }]
+//! decision = "deny"
+//! message = "System files are read-only"
+//! ```
+//!
+//! JSON pointers address the hook input (`/tool_input/command`, `/prompt`);
+//! permission rules see `tool_name` and `tool_input`. A predicate holds when
+//! every condition it sets (`equals`, `matches`, `exists`) holds; with none
+//! set it checks that the value exists.
+//!
+//! All hook rules matching an event apply in order and their outputs are
+//! merged with [`HookOutput::merge`]; a `block` stops evaluation. The first
+//! matching permission rule decides.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::policy::PolicyEngine;
+//! use anthropic_agent_sdk::types::ClaudeAgentOptions;
+//! use std::time::Duration;
+//!
+//! # async fn example() -> anthropic_agent_sdk::Result<()> {
+//! let engine = PolicyEngine::load("agent-policy.toml").await?;
+//! let _watcher = engine.watch(Duration::from_secs(2))?;
+//!
+//! let options = ClaudeAgentOptions::builder()
+//!     .hooks(engine.hooks())
+//!     .can_use_tool(engine.can_use_tool())
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{ClaudeError, Result};
+use crate::hooks::{HookManager, HookMatcherBuilder};
+use crate::permissions::PermissionManager;
+use crate::types::{
+    CanUseToolCallback, ContextHookOutput, HookEvent, HookMatcher, HookOutput, IntoHookOutput,
+    PermissionResult, PermissionResultAllow, PermissionResultDeny, PreToolUseHookOutput,
+};
+
+// ============================================================================
+// Policy Format
+// ============================================================================
+
+/// Declarative hook and permission policy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Decision for tool uses no permission rule matches
+    #[serde(default)]
+    pub default_permission: PolicyDecision,
+    /// Hook rules, applied in order
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+    /// Permission rules; the first match decides
+    #[serde(default)]
+    pub permissions: Vec<PermissionRule>,
+}
+
+impl Policy {
+    /// Parse a TOML policy
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::InvalidConfig` if the document is not a valid policy.
+    pub fn from_toml_str(source: &str) -> Result<Self> {
+        toml::from_str(source)
+            .map_err(|e| ClaudeError::invalid_config(format!("Invalid policy: {e}")))
+    }
+
+    /// Parse a JSON policy
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::InvalidConfig` if the document is not a valid policy.
+    pub fn from_json_str(source: &str) -> Result<Self> {
+        serde_json::from_str(source)
+            .map_err(|e| ClaudeError::invalid_config(format!("Invalid policy: {e}")))
+    }
+
+    /// Read a policy file (`.json` is parsed as JSON, anything else as TOML)
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the file cannot be read, or
+    /// `ClaudeError::InvalidConfig` if it is not a valid policy.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = tokio::fs::read_to_string(path).await?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let policy = if is_json {
+            Self::from_json_str(&source)
+        } else {
+            Self::from_toml_str(&source)
+        };
+        policy.map_err(|e| ClaudeError::invalid_config(format!("{}: {e}", path.display())))
+    }
+}
+
+/// Permission decision of a policy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyDecision {
+    /// Allow the tool use
+    #[default]
+    Allow,
+    /// Deny the tool use
+    Deny,
+}
+
+/// Hook rule: when the event, tool and predicates match, apply the action
+///
+/// The action's fields sit next to the rule's own keys. Keys that are
+/// neither rule keys nor fields of the rule's action are rejected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawPolicyRule")]
+pub struct PolicyRule {
+    /// Name used in logs and errors
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Event the rule applies to
+    #[serde(default = "default_event")]
+    pub event: HookEvent,
+    /// Regex that must match the whole tool name (`None` matches any input)
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Predicates on the hook input, all of which must hold
+    #[serde(default)]
+    pub when: Vec<InputPredicate>,
+    /// What to do when the rule matches
+    #[serde(flatten)]
+    pub action: PolicyAction,
+}
+
+fn default_event() -> HookEvent {
+    HookEvent::PreToolUse
+}
+
+/// Action tag of a hook rule
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ActionKind {
+    Block,
+    AddContext,
+    Rewrite,
+    Log,
+}
+
+/// Hook rule as written, with every action field optional
+///
+/// `#[serde(flatten)]` cannot be combined with `deny_unknown_fields`, so the
+/// rule is read flat and the action assembled in [`PolicyRule::try_from`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicyRule {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default = "default_event")]
+    event: HookEvent,
+    #[serde(default)]
+    tool: Option<String>,
+    #[serde(default)]
+    when: Vec<InputPredicate>,
+    action: ActionKind,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    context: Option<String>,
+    #[serde(default)]
+    set: Option<BTreeMap<String, Value>>,
+}
+
+impl TryFrom<RawPolicyRule> for PolicyRule {
+    type Error = String;
+
+    fn try_from(raw: RawPolicyRule) -> std::result::Result<Self, Self::Error> {
+        let (action_name, field) = match raw.action {
+            ActionKind::Block => ("block", "message"),
+            ActionKind::AddContext => ("add_context", "context"),
+            ActionKind::Rewrite => ("rewrite", "set"),
+            ActionKind::Log => ("log", "message"),
+        };
+        let present = [
+            ("message", raw.message.is_some()),
+            ("context", raw.context.is_some()),
+            ("set", raw.set.is_some()),
+        ];
+        if let Some((other, _)) = present
+            .iter()
+            .find(|(name, is_set)| *is_set && *name != field)
+        {
+            return Err(format!(
+                "field `{other}` is not valid for action `{action_name}`"
+            ));
+        }
+        let missing = || format!("action `{action_name}` requires `{field}`");
+
+        let action = match raw.action {
+            ActionKind::Block => PolicyAction::Block {
+                message: raw.message.ok_or_else(missing)?,
+            },
+            ActionKind::AddContext => PolicyAction::AddContext {
+                context: raw.context.ok_or_else(missing)?,
+            },
+            ActionKind::Rewrite => PolicyAction::Rewrite {
+                set: raw.set.ok_or_else(missing)?,
+            },
+            ActionKind::Log => PolicyAction::Log {
+                message: raw.message,
+            },
+        };
+
+        Ok(Self {
+            name: raw.name,
+            event: raw.event,
+            tool: raw.tool,
+            when: raw.when,
+            action,
+        })
+    }
+}
+
+/// Action of a hook rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// Block the action (`PreToolUse`: deny the tool use) with a message for the model
+    Block {
+        /// Reason given to the model
+        message: String,
+    },
+    /// Add context for the model
+    AddContext {
+        /// Context text
+        context: String,
+    },
+    /// Rewrite the tool input (`PreToolUse` only) by setting values at JSON pointers
+    Rewrite {
+        /// Pointer (under `/tool_input`) to new value
+        set: BTreeMap<String, Value>,
+    },
+    /// Log the event through `tracing`
+    Log {
+        /// Log message (defaults to "Policy rule matched")
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+/// Predicate on a value addressed by a JSON pointer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputPredicate {
+    /// JSON pointer into the input, e.g. `/tool_input/command`
+    pub pointer: String,
+    /// The value must equal this
+    #[serde(default)]
+    pub equals: Option<Value>,
+    /// Regex the value must contain a match of (non-strings are matched as JSON)
+    #[serde(default)]
+    pub matches: Option<String>,
+    /// Whether the value must exist (`true`) or be absent (`false`)
+    #[serde(default)]
+    pub exists: Option<bool>,
+}
+
+/// Permission rule answering `can_use_tool` requests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionRule {
+    /// Name used in logs and errors
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Regex that must match the whole tool name (`None` matches every tool)
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Predicates on `{tool_name, tool_input}`, all of which must hold
+    #[serde(default)]
+    pub when: Vec<InputPredicate>,
+    /// Allow or deny
+    pub decision: PolicyDecision,
+    /// Message for the model when denying
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Interrupt the conversation when denying
+    #[serde(default)]
+    pub interrupt: bool,
+}
+
+// ============================================================================
+// Compiled Policy
+// ============================================================================
+
+/// Events whose hook output can carry `additionalContext`
+const CONTEXT_EVENTS: [HookEvent; 6] = [
+    HookEvent::PreToolUse,
+    HookEvent::PostToolUse,
+    HookEvent::PostToolUseFailure,
+    HookEvent::UserPromptSubmit,
+    HookEvent::SessionStart,
+    HookEvent::SubagentStart,
+];
+
+/// Pointer prefix of the tool input inside a hook input
+const TOOL_INPUT_POINTER: &str = "/tool_input";
+
+struct CompiledPredicate {
+    pointer: String,
+    equals: Option<Value>,
+    matches: Option<Regex>,
+    exists: Option<bool>,
+}
+
+impl CompiledPredicate {
+    fn compile(rule: &str, predicate: &InputPredicate) -> Result<Self> {
+        if !predicate.pointer.is_empty() && !predicate.pointer.starts_with('/') {
+            return Err(ClaudeError::invalid_config(format!(
+                "Policy rule {rule}: pointer '{}' must start with '/'",
+                predicate.pointer
+            )));
+        }
+        let matches = predicate
+            .matches
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| ClaudeError::invalid_config(format!("Policy rule {rule}: {e}")))?;
+        Ok(Self {
+            pointer: predicate.pointer.clone(),
+            equals: predicate.equals.clone(),
+            matches,
+            exists: predicate.exists,
+        })
+    }
+
+    fn holds(&self, input: &Value) -> bool {
+        let value = input.pointer(&self.pointer);
+        if self.equals.is_none() && self.matches.is_none() && self.exists.is_none() {
+            return value.is_some();
+        }
+        if let Some(exists) = self.exists {
+            if value.is_some() != exists {
+                return false;
+            }
+        }
+        if let Some(ref expected) = self.equals {
+            if value != Some(expected) {
+                return false;
+            }
+        }
+        if let Some(ref regex) = self.matches {
+            let matched = match value {
+                Some(Value::String(s)) => regex.is_match(s),
+                Some(other) => regex.is_match(&other.to_string()),
+                None => false,
+            };
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Tool-name regex and input predicates shared by hook and permission rules
+struct Matcher {
+    tool: Option<Regex>,
+    when: Vec<CompiledPredicate>,
+}
+
+impl Matcher {
+    fn compile(rule: &str, tool: Option<&str>, when: &[InputPredicate]) -> Result<Self> {
+        let tool = tool
+            .map(|pattern| Regex::new(&format!("^(?:{pattern})$")))
+            .transpose()
+            .map_err(|e| ClaudeError::invalid_config(format!("Policy rule {rule}: {e}")))?;
+        let when = when
+            .iter()
+            .map(|predicate| CompiledPredicate::compile(rule, predicate))
+            .collect::<Result<_>>()?;
+        Ok(Self { tool, when })
+    }
+
+    fn matches(&self, input: &Value) -> bool {
+        if let Some(ref tool) = self.tool {
+            let name = input.get("tool_name").and_then(Value::as_str);
+            if !name.is_some_and(|name| tool.is_match(name)) {
+                return false;
+            }
+        }
+        self.when.iter().all(|predicate| predicate.holds(input))
+    }
+}
+
+struct CompiledRule {
+    name: String,
+    event: HookEvent,
+    matcher: Matcher,
+    action: PolicyAction,
+}
+
+struct CompiledPermission {
+    name: String,
+    matcher: Matcher,
+    rule: PermissionRule,
+}
+
+/// A validated policy with compiled regexes
+struct CompiledPolicy {
+    default_permission: PolicyDecision,
+    rules: Vec<CompiledRule>,
+    permissions: Vec<CompiledPermission>,
+}
+
+impl CompiledPolicy {
+    fn compile(policy: &Policy) -> Result<Self> {
+        let rules = policy
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let name = rule.name.clone().unwrap_or_else(|| format!("rules[{i}]"));
+                Self::check_action(&name, rule)?;
+                Ok(CompiledRule {
+                    matcher: Matcher::compile(&name, rule.tool.as_deref(), &rule.when)?,
+                    name,
+                    event: rule.event,
+                    action: rule.action.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let permissions = policy
+            .permissions
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let name = rule
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("permissions[{i}]"));
+                Ok(CompiledPermission {
+                    matcher: Matcher::compile(&name, rule.tool.as_deref(), &rule.when)?,
+                    name,
+                    rule: rule.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            default_permission: policy.default_permission,
+            rules,
+            permissions,
+        })
+    }
+
+    /// Reject actions the rule's event cannot carry out
+    fn check_action(name: &str, rule: &PolicyRule) -> Result<()> {
+        match &rule.action {
+            PolicyAction::AddContext { .. } if !CONTEXT_EVENTS.contains(&rule.event) => {
+                Err(ClaudeError::invalid_config(format!(
+                    "Policy rule {name}: add_context is not supported for {:?}",
+                    rule.event
+                )))
+            }
+            PolicyAction::Rewrite { .. } if rule.event != HookEvent::PreToolUse => {
+                Err(ClaudeError::invalid_config(format!(
+                    "Policy rule {name}: rewrite is only supported for PreToolUse"
+                )))
+            }
+            PolicyAction::Rewrite { set } => {
+                match set
+                    .keys()
+                    .find(|pointer| !pointer.starts_with(&format!("{TOOL_INPUT_POINTER}/")))
+                {
+                    Some(pointer) => Err(ClaudeError::invalid_config(format!(
+                        "Policy rule {name}: rewrite pointer '{pointer}' must start with '{TOOL_INPUT_POINTER}/'"
+                    ))),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn evaluate_hook(&self, event: HookEvent, input: &Value) -> HookOutput {
+        let mut output = HookOutput::default();
+        // Rewrites apply on top of each other, so track the rewritten input
+        let mut rewritten = input.clone();
+
+        for rule in self.rules.iter().filter(|rule| rule.event == event) {
+            if !rule.matcher.matches(&rewritten) {
+                continue;
+            }
+            let result = match &rule.action {
+                PolicyAction::Block { message } if event == HookEvent::PreToolUse => {
+                    PreToolUseHookOutput::deny(message.clone()).into_hook_output(event)
+                }
+                PolicyAction::Block { message } => HookOutput::block(message.clone()),
+                PolicyAction::AddContext { context } => {
+                    ContextHookOutput::with_context(context.clone()).into_hook_output(event)
+                }
+                PolicyAction::Rewrite { set } => {
+                    for (pointer, value) in set {
+                        if !set_pointer(&mut rewritten, pointer, value.clone()) {
+                            tracing::warn!(rule = %rule.name, pointer = %pointer, "Policy rewrite pointer not found");
+                        }
+                    }
+                    PreToolUseHookOutput::default()
+                        .with_updated_input(
+                            rewritten
+                                .pointer(TOOL_INPUT_POINTER)
+                                .cloned()
+                                .unwrap_or_default(),
+                        )
+                        .into_hook_output(event)
+                }
+                PolicyAction::Log { message } => {
+                    tracing::info!(
+                        rule = %rule.name,
+                        event = ?event,
+                        input = %rewritten,
+                        "{}",
+                        message.as_deref().unwrap_or("Policy rule matched")
+                    );
+                    HookOutput::default()
+                }
+            };
+            tracing::debug!(rule = %rule.name, event = ?event, "Policy rule applied");
+
+            output.merge(result);
+            if output.is_blocking() {
+                break;
+            }
+        }
+        output
+    }
+
+    fn check_permission(&self, tool_name: &str, tool_input: &Value) -> PermissionResult {
+        let input = serde_json::json!({"tool_name": tool_name, "tool_input": tool_input});
+        let matched = self
+            .permissions
+            .iter()
+            .find(|permission| permission.matcher.matches(&input));
+
+        let (decision, message, interrupt) = match matched {
+            Some(permission) => {
+                tracing::debug!(rule = %permission.name, tool_name = %tool_name, "Policy permission rule matched");
+                (
+                    permission.rule.decision,
+                    permission.rule.message.clone(),
+                    permission.rule.interrupt,
+                )
+            }
+            None => (self.default_permission, None, false),
+        };
+
+        match decision {
+            PolicyDecision::Allow => PermissionResult::Allow(PermissionResultAllow {
+                updated_input: None,
+                updated_permissions: None,
+            }),
+            PolicyDecision::Deny => PermissionResult::Deny(PermissionResultDeny {
+                message: message.unwrap_or_else(|| format!("Tool {tool_name} is denied by policy")),
+                interrupt,
+            }),
+        }
+    }
+}
+
+/// Set `value` at a JSON pointer, creating missing object members
+///
+/// Returns `false` if the path runs through a value that is neither an object
+/// nor an array with that index.
+fn set_pointer(target: &mut Value, pointer: &str, value: Value) -> bool {
+    let mut current = target;
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        if current.is_null() {
+            *current = Value::Object(serde_json::Map::new());
+        }
+        current = match current {
+            Value::Object(map) => map.entry(token).or_insert(Value::Null),
+            Value::Array(items) => match token.parse::<usize>().ok().and_then(|i| items.get_mut(i))
+            {
+                Some(item) => item,
+                None => return false,
+            },
+            _ => return false,
+        };
+    }
+    *current = value;
+    true
+}
+
+// ============================================================================
+// Policy Engine
+// ============================================================================
+
+/// Compiled policy shared by the hooks and permission callback it creates
+///
+/// Cloning is cheap; all clones see [`update`](Self::update)s and reloads.
+#[derive(Clone)]
+pub struct PolicyEngine {
+    compiled: Arc<RwLock<Arc<CompiledPolicy>>>,
+    path: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for PolicyEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let compiled = self.current();
+        f.debug_struct("PolicyEngine")
+            .field("path", &self.path)
+            .field("rules", &compiled.rules.len())
+            .field("permissions", &compiled.permissions.len())
+            .finish()
+    }
+}
+
+impl PolicyEngine {
+    /// Compile a policy
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::InvalidConfig` for invalid regexes, pointers, or
+    /// actions the rule's event does not support.
+    pub fn new(policy: &Policy) -> Result<Self> {
+        Ok(Self {
+            compiled: Arc::new(RwLock::new(Arc::new(CompiledPolicy::compile(policy)?))),
+            path: None,
+        })
+    }
+
+    /// Load and compile a policy file, remembering its path for [`reload`](Self::reload)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, parsed or compiled.
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let policy = Policy::load(&path).await?;
+        let mut engine = Self::new(&policy)?;
+        engine.path = Some(path);
+        Ok(engine)
+    }
+
+    /// Policy file path, if loaded from a file
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Replace the policy; hooks and callbacks created earlier use the new rules
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::InvalidConfig` if the policy does not compile; the
+    /// current policy is kept.
+    pub fn update(&self, policy: &Policy) -> Result<()> {
+        let compiled = Arc::new(CompiledPolicy::compile(policy)?);
+        *self
+            .compiled
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = compiled;
+        Ok(())
+    }
+
+    /// Re-read the policy file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine has no file, or the file cannot be read,
+    /// parsed or compiled; the current policy is kept.
+    pub async fn reload(&self) -> Result<()> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            ClaudeError::invalid_config("Policy engine was not loaded from a file")
+        })?;
+        self.update(&Policy::load(path).await?)
+    }
+
+    /// Watch the policy file and reload it when it changes
+    ///
+    /// Polls the file's modification time every `interval`. Invalid edits are
+    /// logged and the previous policy stays active. Watching stops when the
+    /// returned [`PolicyWatcher`] is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::InvalidConfig` if the engine has no file.
+    pub fn watch(&self, interval: Duration) -> Result<PolicyWatcher> {
+        let path = self.path.clone().ok_or_else(|| {
+            ClaudeError::invalid_config("Policy engine was not loaded from a file")
+        })?;
+        let engine = self.clone();
+
+        let task = tokio::spawn(async move {
+            let mut last = file_stamp(&path).await;
+            loop {
+                tokio::time::sleep(interval).await;
+                let stamp = file_stamp(&path).await;
+                if stamp == last {
+                    continue;
+                }
+                last = stamp;
+                match engine.reload().await {
+                    Ok(()) => tracing::info!(path = %path.display(), "Reloaded policy"),
+                    Err(e) => {
+                        tracing::warn!(path = %path.display(), error = %e, "Policy reload failed, keeping previous policy");
+                    }
+                }
+            }
+        });
+        Ok(PolicyWatcher { task })
+    }
+
+    /// Hook matchers for every hook event
+    ///
+    /// Pass to [`ClaudeAgentOptions::hooks`](crate::types::ClaudeAgentOptions::hooks).
+    /// Every event is registered, so rules for events the policy had no
+    /// rules for yet take effect after a reload; events without matching
+    /// rules get an empty output.
+    #[must_use]
+    pub fn hooks(&self) -> HashMap<HookEvent, Vec<HookMatcher>> {
+        HookEvent::ALL
+            .into_iter()
+            .map(|event| {
+                let engine = self.clone();
+                let hook = HookManager::callback(move |input, _tool_name, _ctx| {
+                    let output = engine.evaluate_hook(event, &input);
+                    async move { Ok(output) }
+                });
+                (
+                    event,
+                    vec![
+                        HookMatcherBuilder::new(None::<String>)
+                            .add_hook(hook)
+                            .build(),
+                    ],
+                )
+            })
+            .collect()
+    }
+
+    /// Permission callback answering from the policy's permission rules
+    ///
+    /// Pass to [`ClaudeAgentOptions::can_use_tool`](crate::types::ClaudeAgentOptions::can_use_tool).
+    #[must_use]
+    pub fn can_use_tool(&self) -> CanUseToolCallback {
+        let engine = self.clone();
+        PermissionManager::callback(move |tool_name, input, _ctx| {
+            let result = engine.check_permission(&tool_name, &input);
+            async move { Ok(result) }
+        })
+    }
+
+    /// Evaluate the hook rules for an event against a hook input
+    #[must_use]
+    pub fn evaluate_hook(&self, event: HookEvent, input: &Value) -> HookOutput {
+        self.current().evaluate_hook(event, input)
+    }
+
+    /// Evaluate the permission rules for a tool use
+    #[must_use]
+    pub fn check_permission(&self, tool_name: &str, tool_input: &Value) -> PermissionResult {
+        self.current().check_permission(tool_name, tool_input)
+    }
+
+    fn current(&self) -> Arc<CompiledPolicy> {
+        self.compiled
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Modification time and size, used to detect policy file changes
+async fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Handle of a policy file watch started by [`PolicyEngine::watch`]
+///
+/// Dropping it stops the watch.
+#[derive(Debug)]
+pub struct PolicyWatcher {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl PolicyWatcher {
+    /// Stop watching
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for PolicyWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HookContext, HookDecision, PermissionBehavior};
+    use serde_json::json;
+
+    const POLICY: &str = r#"
+        default_permission = "allow"
+
+        [[rules]]
+        name = "no-rm"
+        tool = "Bash"
+        when = [{ pointer = "/tool_input/command", matches = "rm\\s+-rf" }]
+        action = "block"
+        message = "Recursive deletes are not allowed"
+
+        [[rules]]
+        tool = "Write|Edit"
+        action = "rewrite"
+        set = { "/tool_input/file_path" = "/sandbox/out.txt" }
+
+        [[rules]]
+        tool = "Write"
+        when = [{ pointer = "/tool_input/content", exists = true }]
+        action = "add_context"
+        context = "Files are written to the sandbox"
+
+        [[permissions]]
+        tool = "WebFetch"
+        when = [{ pointer = "/tool_input/url", matches = "^http://" }]
+        decision = "deny"
+        message = "Plain HTTP is not allowed"
+        interrupt = true
+    "#;
+
+    fn tool_input(tool_name: &str, input: Value) -> Value {
+        json!({
+            "hook_event_name": "PreToolUse",
+            "session_id": "s1",
+            "transcript_path": "",
+            "cwd": "/tmp",
+            "tool_name": tool_name,
+            "tool_input": input,
+        })
+    }
+
+    #[test]
+    fn test_policy_hook_rules() {
+        let engine = PolicyEngine::new(&Policy::from_toml_str(POLICY).unwrap()).unwrap();
+
+        let blocked = engine.evaluate_hook(
+            HookEvent::PreToolUse,
+            &tool_input("Bash", json!({"command": "rm -rf /"})),
+        );
+        assert_eq!(blocked.decision, Some(HookDecision::Block));
+        assert_eq!(
+            blocked.permission_decision(),
+            Some(PermissionBehavior::Deny)
+        );
+
+        // Tool patterns match the whole name
+        let other = engine.evaluate_hook(
+            HookEvent::PreToolUse,
+            &tool_input("BashOutput", json!({"command": "rm -rf /"})),
+        );
+        assert!(other.is_empty());
+
+        let rewritten = engine.evaluate_hook(
+            HookEvent::PreToolUse,
+            &tool_input("Write", json!({"file_path": "/etc/passwd", "content": "x"})),
+        );
+        assert!(!rewritten.is_blocking());
+        let specific = rewritten.hook_specific_output.as_ref().unwrap();
+        assert_eq!(
+            specific["updatedInput"],
+            json!({"file_path": "/sandbox/out.txt", "content": "x"})
+        );
+        assert_eq!(
+            rewritten.additional_context(),
+            Some("Files are written to the sandbox")
+        );
+
+        assert_eq!(engine.hooks().len(), HookEvent::ALL.len());
+    }
+
+    #[tokio::test]
+    async fn test_policy_hooks_cover_rules_added_by_update() {
+        let engine = PolicyEngine::new(&Policy::default()).unwrap();
+        let hooks = engine.hooks();
+        let stop_hook = hooks[&HookEvent::Stop][0].hooks[0].clone();
+        let input = json!({
+            "hook_event_name": "Stop",
+            "session_id": "s1",
+            "transcript_path": "",
+            "cwd": "/tmp",
+            "stop_hook_active": false,
+        });
+
+        let output = stop_hook
+            .call(input.clone(), None, HookContext::default())
+            .await
+            .unwrap();
+        assert!(output.is_empty());
+
+        // A rule for an event the policy had no rules for
+        let updated = Policy::from_toml_str(
+            r#"
+            [[rules]]
+            event = "Stop"
+            action = "block"
+            message = "Run the tests first"
+            "#,
+        )
+        .unwrap();
+        engine.update(&updated).unwrap();
+
+        let output = stop_hook
+            .call(input, None, HookContext::default())
+            .await
+            .unwrap();
+        assert_eq!(output.decision, Some(HookDecision::Block));
+    }
+
+    #[test]
+    fn test_policy_permissions() {
+        let json = serde_json::to_string(&Policy::from_toml_str(POLICY).unwrap()).unwrap();
+        let mut policy = Policy::from_json_str(&json).unwrap();
+        let engine = PolicyEngine::new(&policy).unwrap();
+
+        match engine.check_permission("WebFetch", &json!({"url": "http://example.com"})) {
+            PermissionResult::Deny(deny) => {
+                assert_eq!(deny.message, "Plain HTTP is not allowed");
+                assert!(deny.interrupt);
+            }
+            PermissionResult::Allow(_) => panic!("expected deny"),
+        }
+        assert!(matches!(
+            engine.check_permission("WebFetch", &json!({"url": "https://example.com"})),
+            PermissionResult::Allow(_)
+        ));
+
+        policy.default_permission = PolicyDecision::Deny;
+        engine.update(&policy).unwrap();
+        assert!(matches!(
+            engine.check_permission("Read", &json!({})),
+            PermissionResult::Deny(_)
+        ));
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        let invalid = [
+            r#"[[rules]]
+               tool = "("
+               action = "log""#,
+            r#"[[rules]]
+               event = "PostToolUse"
+               action = "rewrite"
+               set = { "/tool_input/a" = 1 }"#,
+            r#"[[rules]]
+               action = "rewrite"
+               set = { "/a" = 1 }"#,
+            r#"[[rules]]
+               event = "Stop"
+               action = "add_context"
+               context = "x""#,
+            r#"[[rules]]
+               when = [{ pointer = "tool_input" }]
+               action = "log""#,
+        ];
+        for source in invalid {
+            let policy = Policy::from_toml_str(source).unwrap();
+            assert!(PolicyEngine::new(&policy).is_err(), "{source}");
+        }
+        assert!(Policy::from_toml_str("[[rules]]\naction = \"explode\"").is_err());
+
+        // Misspelled or misplaced keys are rejected instead of dropped
+        let misspelled = [
+            "[[rules]]\ntol = \"Bash\"\naction = \"block\"\nmessage = \"no\"",
+            "[[rules]]\nwehn = []\naction = \"log\"",
+            "[[rules]]\naction = \"block\"\nmesage = \"no\"",
+            "[[rules]]\naction = \"block\"\nmessage = \"no\"\ncontext = \"x\"",
+            "[[rules]]\naction = \"add_context\"",
+        ];
+        for source in misspelled {
+            assert!(Policy::from_toml_str(source).is_err(), "{source}");
+        }
+        assert!(Policy::from_json_str(r#"{"rules": [{"tol": "Bash", "action": "log"}]}"#).is_err());
+        assert!(Policy::from_toml_str("unknown = 1").is_err());
+
+        let example = Policy::from_toml_str(include_str!("../examples/policy.toml")).unwrap();
+        assert!(PolicyEngine::new(&example).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_policy_watch_reloads_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.json");
+        std::fs::write(&path, r#"{"default_permission": "allow"}"#).unwrap();
+
+        let engine = PolicyEngine::load(&path).await.unwrap();
+        let watcher = engine.watch(Duration::from_millis(10)).unwrap();
+        assert!(matches!(
+            engine.check_permission("Bash", &json!({})),
+            PermissionResult::Allow(_)
+        ));
+
+        // Invalid edits keep the previous policy
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        std::fs::write(&path, "{").unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(matches!(
+            engine.check_permission("Bash", &json!({})),
+            PermissionResult::Allow(_)
+        ));
+
+        std::fs::write(&path, r#"{"default_permission": "deny"}"#).unwrap();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+        while matches!(
+            engine.check_permission("Bash", &json!({})),
+            PermissionResult::Allow(_)
+        ) && tokio::time::Instant::now() < deadline
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(matches!(
+            engine.check_permission("Bash", &json!({})),
+            PermissionResult::Deny(_)
+        ));
+        watcher.stop();
+    }
+}
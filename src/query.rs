@@ -3,6 +3,7 @@
 use futures::Stream;
 
 use crate::Transport;
+use crate::client::ClaudeSDKClient;
 use crate::error::Result;
 use crate::message::parse_message;
 use crate::transport::{PromptInput, SubprocessTransport};
@@ -122,6 +123,164 @@ pub async fn query(
     Ok(message_stream)
 }
 
+/// One-shot query over a custom [`Transport`].
+///
+/// Behaves like [`query()`], but sends the prompt through the given transport
+/// instead of spawning the CLI with the prompt as an argument. The query runs
+/// on a [`ClaudeSDKClient`], so hooks, `can_use_tool` and SDK MCP servers in
+/// `options` work as they do for the client. The prompt is written as a
+/// stream-json user message and the input stream stays open until the
+/// `Result` arrives, so the transport must accept stream-json input (for
+/// example a [`SubprocessTransport`] created with [`PromptInput::Stream`]).
+///
+/// # Arguments
+/// * `prompt` - The prompt to send to Claude (string)
+/// * `transport` - Transport to communicate through (not yet connected)
+/// * `options` - Optional configuration (defaults to `ClaudeAgentOptions::default()` if None)
+///
+/// # Returns
+/// A stream of Messages from the conversation
+///
+/// # Errors
+/// Returns error if the transport fails to connect or the prompt cannot be written
+///
+/// # Examples
+///
+/// ```no_run
+/// use anthropic_agent_sdk::query::query_with_transport;
+/// use anthropic_agent_sdk::{ClaudeAgentOptions, PromptInput, SubprocessTransport};
+/// use futures::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let options = ClaudeAgentOptions::builder().max_turns(1).build();
+///     let transport = SubprocessTransport::new(PromptInput::Stream, options.clone(), None)?;
+///
+///     let stream = query_with_transport("What is 2 + 2?", transport, Some(options)).await?;
+///     let mut stream = Box::pin(stream);
+///
+///     while let Some(message) = stream.next().await {
+///         println!("{:?}", message?);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[must_use = "query returns a stream that must be consumed to receive messages"]
+pub async fn query_with_transport(
+    prompt: impl Into<String>,
+    transport: impl Transport + 'static,
+    options: Option<ClaudeAgentOptions>,
+) -> Result<impl Stream<Item = Result<Message>>> {
+    let options = options.unwrap_or_default();
+    let mut client = ClaudeSDKClient::with_transport(transport, options).await?;
+    client.send_message(prompt).await?;
+
+    let message_stream = async_stream::stream! {
+        let mut input_open = true;
+        while let Some(result) = client.next_message().await {
+            // Control requests can arrive until the turn ends, so input
+            // stays open until the result
+            let is_result = matches!(&result, Ok(Message::Result { .. }));
+            yield result;
+            if is_result && input_open {
+                input_open = false;
+                if let Err(e) = client.end_input().await {
+                    yield Err(e);
+                }
+            }
+        }
+        if let Err(e) = client.close().await {
+            tracing::warn!(error = %e, "Failed to close query transport");
+        }
+    };
+
+    Ok(message_stream)
+}
+
+/// One-shot query returning structured output deserialized into `T`
+/// (`schemars` feature).
+///
+/// Sets `output_format` to the JSON schema derived from `T`, runs [`query()`]
+/// to completion and deserializes the result's structured output.
+///
+/// # Arguments
+/// * `prompt` - The prompt to send to Claude (string)
+/// * `options` - Optional configuration; its `output_format` is replaced
+///
+/// # Errors
+/// Returns error if the query fails, and
+/// [`ClaudeError::StructuredOutput`](crate::ClaudeError::StructuredOutput)
+/// with the raw output if it does not match `T`
+///
+/// # Examples
+///
+/// ```no_run
+/// use anthropic_agent_sdk::query_typed;
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, JsonSchema)]
+/// struct Capital {
+///     country: String,
+///     city: String,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let capital: Capital = query_typed("What is the capital of France?", None).await?;
+///     println!("{}: {}", capital.country, capital.city);
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "schemars")]
+pub async fn query_typed<T>(
+    prompt: impl Into<String>,
+    options: Option<ClaudeAgentOptions>,
+) -> Result<T>
+where
+    T: schemars::JsonSchema + serde::de::DeserializeOwned,
+{
+    let mut options = options.unwrap_or_default();
+    options.output_format = Some(crate::types::OutputFormat::for_type::<T>());
+    let stream = query(prompt, Some(options)).await?;
+    typed_result(stream).await
+}
+
+/// Read a message stream to its `Result` and deserialize the structured output
+///
+/// The first error in the stream is returned, but only once the `Result` is
+/// read, so none of the turn is left in the stream.
+#[cfg(feature = "schemars")]
+pub(crate) async fn typed_result<T: serde::de::DeserializeOwned>(
+    stream: impl Stream<Item = Result<Message>>,
+) -> Result<T> {
+    use futures::StreamExt;
+
+    let mut stream = Box::pin(stream);
+    let mut error = None;
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(message @ Message::Result { .. }) => {
+                return match error {
+                    Some(e) => Err(e),
+                    None => message.parse_structured_output(),
+                };
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    Err(error.unwrap_or_else(|| {
+        crate::ClaudeError::structured_output(
+            std::any::type_name::<T>(),
+            "stream ended without a result",
+            serde_json::Value::Null,
+        )
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,452 @@
+//! Session tree operations over the CLI's transcripts
+
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::tree::{Branch, SessionTree, prompt_text};
+use crate::error::{ClaudeError, Result};
+use crate::transcript::{SessionFile, Transcript, TranscriptStore};
+use crate::types::{ClaudeAgentOptions, Message, SessionId};
+
+/// Where a new run continues a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumePoint {
+    /// Session to resume
+    pub session_id: SessionId,
+    /// Message to continue from (`None` continues from the end of the session)
+    pub at_message: Option<String>,
+    /// Whether the run gets a new session ID instead of extending the session
+    pub fork: bool,
+    /// Working directory the session was run in
+    pub cwd: PathBuf,
+}
+
+impl ResumePoint {
+    /// Set the resume fields (`resume`, `resume_session_at`, `fork_session`,
+    /// `cwd`) on existing options
+    #[must_use]
+    pub fn apply(&self, mut options: ClaudeAgentOptions) -> ClaudeAgentOptions {
+        options.resume = Some(self.session_id.clone());
+        options.resume_session_at = self.at_message.clone();
+        options.fork_session = self.fork;
+        options.continue_conversation = false;
+        options.cwd = Some(self.cwd.clone());
+        options
+    }
+
+    /// Default options that resume this point
+    #[must_use]
+    pub fn options(&self) -> ClaudeAgentOptions {
+        self.apply(ClaudeAgentOptions::default())
+    }
+}
+
+/// Manages the sessions of one project as trees of branches
+///
+/// Every session the CLI runs in a working directory has a transcript; a
+/// resume at an earlier message or a rewind adds a branch to it, and a fork
+/// starts a new session that shares the history up to the fork.
+#[derive(Debug, Clone)]
+pub struct SessionManager {
+    store: TranscriptStore,
+    cwd: PathBuf,
+}
+
+impl SessionManager {
+    /// Manage the sessions run in `cwd`, using the CLI's default transcript
+    /// location
+    #[must_use]
+    pub fn new(cwd: impl Into<PathBuf>) -> Self {
+        Self::with_store(TranscriptStore::new(), cwd)
+    }
+
+    /// Manage the sessions run in `cwd` from a custom transcript store
+    #[must_use]
+    pub fn with_store(store: TranscriptStore, cwd: impl Into<PathBuf>) -> Self {
+        Self {
+            store,
+            cwd: cwd.into(),
+        }
+    }
+
+    /// The transcript store
+    #[must_use]
+    pub fn store(&self) -> &TranscriptStore {
+        &self.store
+    }
+
+    /// The project working directory
+    #[must_use]
+    pub fn cwd(&self) -> &Path {
+        &self.cwd
+    }
+
+    /// Sessions of the project, most recently modified first
+    ///
+    /// # Errors
+    /// Returns an I/O error if the project directory cannot be read
+    pub fn sessions(&self) -> Result<Vec<SessionFile>> {
+        self.store.list_sessions(&self.cwd)
+    }
+
+    fn session_path(&self, session_id: &SessionId) -> Result<PathBuf> {
+        self.sessions()?
+            .into_iter()
+            .find(|session| &session.session_id == session_id)
+            .map(|session| session.path)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("No transcript found for session {session_id}"),
+                )
+                .into()
+            })
+    }
+
+    /// The message tree of a session
+    ///
+    /// # Errors
+    /// Returns an I/O error of kind `NotFound` if the session has no
+    /// transcript in this project, or any other I/O error reading it
+    pub fn tree(&self, session_id: &SessionId) -> Result<SessionTree> {
+        Ok(SessionTree::new(Transcript::load(
+            self.session_path(session_id)?,
+        )?))
+    }
+
+    /// Branches of a session, most recently active first
+    ///
+    /// # Errors
+    /// Returns an error if the session transcript cannot be read
+    pub fn branches(&self, session_id: &SessionId) -> Result<Vec<Branch>> {
+        Ok(self.tree(session_id)?.branches())
+    }
+
+    /// Branches of every session of the project, most recently active first
+    ///
+    /// # Errors
+    /// Returns an error if a transcript cannot be read
+    pub fn all_branches(&self) -> Result<Vec<Branch>> {
+        let mut branches = Vec::new();
+        for session in self.sessions()? {
+            branches.extend(SessionTree::new(Transcript::load(&session.path)?).branches());
+        }
+        branches.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+        Ok(branches)
+    }
+
+    /// Resume a session at a message, or at its end
+    ///
+    /// With `fork`, the run gets a new session ID and the original session is
+    /// left untouched; otherwise the run adds a branch to the session.
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidConfig` if `at_message` is not a message
+    /// of the session, or an error if the transcript cannot be read
+    pub fn resume_at(
+        &self,
+        session_id: &SessionId,
+        at_message: Option<&str>,
+        fork: bool,
+    ) -> Result<ResumePoint> {
+        if let Some(uuid) = at_message {
+            if self.tree(session_id)?.entry(uuid).is_none() {
+                return Err(ClaudeError::invalid_config(format!(
+                    "Message {uuid} is not part of session {session_id}"
+                )));
+            }
+        } else {
+            self.session_path(session_id)?;
+        }
+        Ok(ResumePoint {
+            session_id: session_id.clone(),
+            at_message: at_message.map(String::from),
+            fork,
+            cwd: self.cwd.clone(),
+        })
+    }
+
+    /// Fork a session just before a user prompt, to ask something else instead
+    ///
+    /// The new session keeps the history up to the assistant message that
+    /// preceded the prompt; send the alternative prompt to continue it.
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidConfig` if `user_uuid` is not a user
+    /// prompt of the session or is its first message (start a new session
+    /// instead), or an error if the transcript cannot be read
+    pub fn fork_at(&self, session_id: &SessionId, user_uuid: &str) -> Result<ResumePoint> {
+        let tree = self.tree(session_id)?;
+        tree.entry(user_uuid)
+            .filter(|entry| prompt_text(&entry.message).is_some())
+            .ok_or_else(|| {
+                ClaudeError::invalid_config(format!(
+                    "Message {user_uuid} is not a user prompt of session {session_id}"
+                ))
+            })?;
+
+        let thread = tree.transcript().thread(user_uuid);
+        let previous = thread
+            .iter()
+            .rev()
+            .skip(1)
+            .find(|e| matches!(e.message, Message::Assistant { .. }))
+            .and_then(|e| e.uuid.clone())
+            .ok_or_else(|| {
+                ClaudeError::invalid_config(format!(
+                    "Message {user_uuid} starts session {session_id}; start a new session instead"
+                ))
+            })?;
+
+        Ok(ResumePoint {
+            session_id: session_id.clone(),
+            at_message: Some(previous),
+            fork: true,
+            cwd: self.cwd.clone(),
+        })
+    }
+
+    /// Continue a branch from its last message
+    #[must_use]
+    pub fn resume_branch(&self, branch: &Branch) -> ResumePoint {
+        ResumePoint {
+            session_id: branch.session_id.clone(),
+            at_message: Some(branch.leaf_uuid.clone()),
+            fork: false,
+            cwd: self.cwd.clone(),
+        }
+    }
+
+    /// Delete a session's transcript
+    ///
+    /// Returns `false` if the session has no transcript in this project.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the file cannot be deleted
+    pub fn delete_session(&self, session_id: &SessionId) -> Result<bool> {
+        match self.session_path(session_id) {
+            Ok(path) => {
+                std::fs::remove_file(path)?;
+                Ok(true)
+            }
+            Err(ClaudeError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the transcripts of sessions not modified for `max_age`
+    ///
+    /// Returns the IDs of the deleted sessions.
+    ///
+    /// # Errors
+    /// Returns an I/O error if the project directory cannot be read or a
+    /// file cannot be deleted
+    pub fn prune_sessions(&self, max_age: Duration) -> Result<Vec<SessionId>> {
+        let cutoff = SystemTime::now()
+            .checked_sub(max_age)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut deleted = Vec::new();
+        for session in self.sessions()? {
+            if session.modified.is_some_and(|modified| modified < cutoff) {
+                std::fs::remove_file(&session.path)?;
+                deleted.push(session.session_id);
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Remove a branch from a session transcript
+    ///
+    /// Removes the messages that belong only to the branch ending at
+    /// `leaf_uuid`, back to the point where it diverges from another branch.
+    /// Returns the number of transcript lines removed.
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidConfig` if `leaf_uuid` is not in the
+    /// session or the branch is its only one (use
+    /// [`delete_session`](Self::delete_session) instead), or an I/O error if
+    /// the transcript cannot be rewritten
+    pub fn prune_branch(&self, session_id: &SessionId, leaf_uuid: &str) -> Result<usize> {
+        let tree = self.tree(session_id)?;
+        if tree.entry(leaf_uuid).is_none() {
+            return Err(ClaudeError::invalid_config(format!(
+                "Message {leaf_uuid} is not part of session {session_id}"
+            )));
+        }
+        let removed = tree.prune_set(leaf_uuid).ok_or_else(|| {
+            ClaudeError::invalid_config(format!(
+                "Branch ending at {leaf_uuid} is the only branch of session {session_id}"
+            ))
+        })?;
+        rewrite_without(&tree.transcript().path, &removed)
+    }
+
+    /// Keep the `keep` most recently active branches of a session and prune
+    /// the rest
+    ///
+    /// Returns the leaf UUIDs of the pruned branches.
+    ///
+    /// # Errors
+    /// Returns an error if the transcript cannot be read or rewritten
+    pub fn prune_branches(&self, session_id: &SessionId, keep: usize) -> Result<Vec<String>> {
+        let mut pruned = Vec::new();
+        let branches = self.branches(session_id)?;
+        for branch in branches.iter().skip(keep.max(1)) {
+            // Earlier prunes may have removed this branch's leaf already
+            match self.prune_branch(session_id, &branch.leaf_uuid) {
+                Ok(_) => pruned.push(branch.leaf_uuid.clone()),
+                Err(ClaudeError::InvalidConfig(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+/// Rewrite a transcript without the entries in `removed` (and summaries
+/// pointing at them), returning the number of lines dropped
+fn rewrite_without(path: &Path, removed: &std::collections::HashSet<String>) -> Result<usize> {
+    let content = std::fs::read_to_string(path)?;
+    let mut kept = Vec::new();
+    let mut dropped = 0;
+    for line in content.lines() {
+        let value: Option<serde_json::Value> = serde_json::from_str(line).ok();
+        let refers_to_removed = value.as_ref().is_some_and(|v| {
+            ["uuid", "leafUuid"].iter().any(|key| {
+                v.get(*key)
+                    .and_then(|u| u.as_str())
+                    .is_some_and(|u| removed.contains(u))
+            })
+        });
+        if refers_to_removed {
+            dropped += 1;
+        } else {
+            kept.push(line);
+        }
+    }
+
+    // Write to a sibling file and rename so a crash never leaves a partial transcript
+    let tmp = path.with_extension("jsonl.tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        for line in &kept {
+            writeln!(file, "{line}")?;
+        }
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok(dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CWD: &str = "/work/investigation";
+
+    fn user(uuid: &str, parent: Option<&str>, text: &str, ts: &str) -> serde_json::Value {
+        json!({"type": "user", "uuid": uuid, "parentUuid": parent, "sessionId": "s1", "timestamp": ts,
+               "message": {"role": "user", "content": text}})
+    }
+
+    fn assistant(uuid: &str, parent: &str, text: &str, ts: &str) -> serde_json::Value {
+        json!({"type": "assistant", "uuid": uuid, "parentUuid": parent, "sessionId": "s1", "timestamp": ts,
+               "message": {"model": "m", "content": [{"type": "text", "text": text}]}})
+    }
+
+    /// u1 -> a1 -> u2 -> a2 (+ attachment)
+    ///          \-> u3 -> a3
+    fn manager_with_branches() -> (tempfile::TempDir, SessionManager) {
+        let root = tempfile::tempdir().unwrap();
+        let manager = SessionManager::with_store(TranscriptStore::with_root(root.path()), CWD);
+        let dir = manager.store().project_dir(CWD);
+        std::fs::create_dir_all(&dir).unwrap();
+        let lines = [
+            json!({"type": "summary", "summary": "Investigation", "leafUuid": "a2"}),
+            user("u1", None, "Investigate the crash", "2025-12-01T10:00:00Z"),
+            assistant("a1", "u1", "Two candidates.", "2025-12-01T10:00:05Z"),
+            user("u2", Some("a1"), "Try A", "2025-12-01T10:01:00Z"),
+            assistant("a2", "u2", "A fails.", "2025-12-01T10:01:05Z"),
+            json!({"type": "attachment", "uuid": "att", "parentUuid": "a2"}),
+            user("u3", Some("a1"), "Try B", "2025-12-01T10:02:00Z"),
+            assistant("a3", "u3", "B works.", "2025-12-01T10:02:05Z"),
+        ];
+        let content: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        std::fs::write(dir.join("s1.jsonl"), content.join("\n") + "\n").unwrap();
+        (root, manager)
+    }
+
+    #[test]
+    fn test_list_branches() {
+        let (_root, manager) = manager_with_branches();
+        let branches = manager.branches(&SessionId::new("s1")).unwrap();
+        assert_eq!(branches.len(), 2);
+
+        assert_eq!(branches[0].leaf_uuid, "a3");
+        assert!(branches[0].is_main);
+        assert_eq!(branches[0].fork_point.as_deref(), Some("a1"));
+        assert_eq!(branches[0].last_prompt.as_deref(), Some("Try B"));
+        assert_eq!(
+            branches[0].last_activity.as_deref(),
+            Some("2025-12-01T10:02:05Z")
+        );
+
+        assert_eq!(branches[1].leaf_uuid, "a2");
+        assert!(!branches[1].is_main);
+        assert_eq!(branches[1].length, 4);
+        assert_eq!(manager.all_branches().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_fork_and_resume_options() {
+        let (_root, manager) = manager_with_branches();
+        let session = SessionId::new("s1");
+
+        let point = manager.fork_at(&session, "u3").unwrap();
+        assert_eq!(point.at_message.as_deref(), Some("a1"));
+        let options = point.options();
+        assert_eq!(options.resume.as_ref(), Some(&session));
+        assert_eq!(options.resume_session_at.as_deref(), Some("a1"));
+        assert!(options.fork_session);
+        assert_eq!(options.cwd.as_deref(), Some(Path::new(CWD)));
+
+        // Only user prompts after the first message can be forked
+        assert!(manager.fork_at(&session, "u1").is_err());
+        assert!(manager.fork_at(&session, "a1").is_err());
+
+        let branch = &manager.branches(&session).unwrap()[1];
+        let resume = manager.resume_branch(branch);
+        assert_eq!(resume.at_message.as_deref(), Some("a2"));
+        assert!(!resume.fork);
+
+        assert!(manager.resume_at(&session, Some("missing"), false).is_err());
+        assert!(
+            manager
+                .resume_at(&SessionId::new("s9"), None, false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_prune_branches_and_sessions() {
+        let (_root, manager) = manager_with_branches();
+        let session = SessionId::new("s1");
+
+        // u2, a2, the attachment and the summary pointing at a2
+        assert_eq!(manager.prune_branch(&session, "a2").unwrap(), 4);
+        let branches = manager.branches(&session).unwrap();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].leaf_uuid, "a3");
+        assert!(matches!(
+            manager.prune_branch(&session, "a3"),
+            Err(ClaudeError::InvalidConfig(_))
+        ));
+        assert!(manager.prune_branches(&session, 1).unwrap().is_empty());
+
+        assert!(manager.delete_session(&session).unwrap());
+        assert!(!manager.delete_session(&session).unwrap());
+        assert!(manager.prune_sessions(Duration::ZERO).unwrap().is_empty());
+    }
+}
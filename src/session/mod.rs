@@ -0,0 +1,49 @@
+//! Session management
+//!
+//! The CLI's session flags (`resume`, `resume_session_at`, `fork_session`)
+//! are separate options; [`SessionManager`] models the sessions of a project
+//! as trees built from their transcripts instead:
+//!
+//! - [`SessionManager::branches`] lists the branches of a session with their
+//!   last activity
+//! - [`SessionManager::fork_at`] forks before any user prompt, and
+//!   [`SessionManager::resume_branch`] continues a branch; both return a
+//!   [`ResumePoint`] that produces the matching [`ClaudeAgentOptions`](crate::ClaudeAgentOptions)
+//! - [`SessionManager::prune_branch`], [`SessionManager::prune_branches`] and
+//!   [`SessionManager::prune_sessions`] remove old branches and transcripts
+//!
+//! The [`store`] module persists conversations outside the CLI: a
+//! [`SessionStore`] set as `ClaudeAgentOptions::session_store` receives every
+//! message of a client's conversation.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::session::SessionManager;
+//! use anthropic_agent_sdk::{ClaudeSDKClient, SessionId};
+//!
+//! # async fn example() -> anthropic_agent_sdk::Result<()> {
+//! let manager = SessionManager::new("/path/to/project");
+//! let session = SessionId::new("abc123");
+//!
+//! for branch in manager.branches(&session)? {
+//!     println!("{} {:?}: {:?}", branch.leaf_uuid, branch.last_activity, branch.last_prompt);
+//! }
+//!
+//! // Ask something else instead of the prompt `user-uuid`
+//! let options = manager.fork_at(&session, "user-uuid")?.options();
+//! let mut client = ClaudeSDKClient::new(options, None).await?;
+//! client.send_message("What if we tried the other approach?").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod manager;
+pub mod store;
+pub mod tree;
+
+pub use manager::{ResumePoint, SessionManager};
+#[cfg(feature = "sqlite")]
+pub use store::SqliteSessionStore;
+pub use store::{FileSessionStore, SessionQuery, SessionRecord, SessionStore, SharedSessionStore};
+pub use tree::{Branch, SessionTree};
@@ -0,0 +1,194 @@
+//! File-system session store
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+
+use super::{SessionQuery, SessionRecord, SessionStore};
+use crate::error::{ClaudeError, Result};
+use crate::transcript::is_valid_session_id;
+use crate::types::{Message, SessionId};
+
+const RECORD_FILE: &str = "session.json";
+const MESSAGES_FILE: &str = "messages.jsonl";
+
+/// Session store keeping one directory per session
+///
+/// Layout: `<root>/<session_id>/session.json` holds the [`SessionRecord`]
+/// and `<root>/<session_id>/messages.jsonl` one message per line.
+/// [`find_sessions`](SessionStore::find_sessions) reads every record, so use
+/// `SqliteSessionStore` (`sqlite` feature) for large histories.
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+    root: PathBuf,
+}
+
+impl FileSessionStore {
+    /// Store sessions under `root` (created on first write)
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Root directory
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory of a session; rejects IDs that are not safe file names
+    fn session_dir(&self, session_id: &SessionId) -> Result<PathBuf> {
+        if !is_valid_session_id(session_id.as_str()) {
+            return Err(ClaudeError::invalid_config(format!(
+                "Invalid session ID: {session_id}"
+            )));
+        }
+        Ok(self.root.join(session_id.as_str()))
+    }
+
+    async fn read_record(path: &Path) -> Result<Option<SessionRecord>> {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileSessionStore {
+    async fn save_session(&self, record: &SessionRecord) -> Result<()> {
+        let dir = self.session_dir(&record.session_id)?;
+        tokio::fs::create_dir_all(&dir).await?;
+        // Write then rename so readers never see a partial record
+        let tmp = dir.join(format!("{RECORD_FILE}.tmp"));
+        tokio::fs::write(&tmp, serde_json::to_vec_pretty(record)?).await?;
+        tokio::fs::rename(&tmp, dir.join(RECORD_FILE)).await?;
+        Ok(())
+    }
+
+    async fn append_message(&self, session_id: &SessionId, message: &Message) -> Result<()> {
+        let dir = self.session_dir(session_id)?;
+        tokio::fs::create_dir_all(&dir).await?;
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(MESSAGES_FILE))
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &SessionId) -> Result<Option<SessionRecord>> {
+        Self::read_record(&self.session_dir(session_id)?.join(RECORD_FILE)).await
+    }
+
+    async fn messages(&self, session_id: &SessionId) -> Result<Vec<Message>> {
+        let path = self.session_dir(session_id)?.join(MESSAGES_FILE);
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Into::into))
+            .collect()
+    }
+
+    async fn find_sessions(&self, query: &SessionQuery) -> Result<Vec<SessionRecord>> {
+        let mut entries = match tokio::fs::read_dir(&self.root).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut records = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path().join(RECORD_FILE);
+            match Self::read_record(&path).await {
+                Ok(Some(record)) if query.matches(&record) => records.push(record),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping unreadable session record");
+                }
+            }
+        }
+
+        records.sort_by_key(|record| std::cmp::Reverse(record.updated_at));
+        if let Some(limit) = query.limit {
+            records.truncate(limit);
+        }
+        Ok(records)
+    }
+
+    async fn delete_session(&self, session_id: &SessionId) -> Result<bool> {
+        match tokio::fs::remove_dir_all(self.session_dir(session_id)?).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_file_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileSessionStore::new(dir.path());
+        let session_id = SessionId::new("s1");
+
+        let message: Message = serde_json::from_value(json!({
+            "type": "user", "session_id": "s1",
+            "message": {"role": "user", "content": "hello"}
+        }))
+        .unwrap();
+        store.append_message(&session_id, &message).await.unwrap();
+        store.append_message(&session_id, &message).await.unwrap();
+        let mut record = SessionRecord::new(session_id.clone());
+        record.record(&message);
+        record.record(&message);
+        store.save_session(&record).await.unwrap();
+
+        let mut other = SessionRecord::new(SessionId::new("s2"));
+        other.total_cost_usd = 3.0;
+        store.save_session(&other).await.unwrap();
+
+        assert_eq!(store.messages(&session_id).await.unwrap().len(), 2);
+        let stored = store.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(stored.message_count, record.message_count);
+
+        let all = store.find_sessions(&SessionQuery::default()).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let costly = store
+            .find_sessions(&SessionQuery {
+                min_cost_usd: Some(1.0),
+                ..SessionQuery::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(costly.len(), 1);
+        assert_eq!(costly[0].session_id.as_str(), "s2");
+
+        assert!(store.delete_session(&session_id).await.unwrap());
+        assert!(!store.delete_session(&session_id).await.unwrap());
+        assert!(store.get_session(&session_id).await.unwrap().is_none());
+        assert!(store.messages(&session_id).await.unwrap().is_empty());
+
+        assert!(
+            store
+                .get_session(&SessionId::new("../escape"))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,600 @@
+//! Persisting conversations outside the CLI
+//!
+//! The CLI keeps sessions in local transcript files only. A [`SessionStore`]
+//! set as [`ClaudeAgentOptions::session_store`] receives every message of a
+//! [`ClaudeSDKClient`](crate::ClaudeSDKClient) conversation, together
+//! with a [`SessionRecord`] of the session's metadata (user, model, working
+//! directory, cost, tool usage), and answers [`SessionQuery`]s over them.
+//!
+//! Two implementations are provided:
+//!
+//! - [`FileSessionStore`]: one directory per session with a JSON record and a
+//!   JSONL message log
+//! - `SqliteSessionStore` (`sqlite` feature): a single SQLite database
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use std::time::{Duration, SystemTime};
+//!
+//! use anthropic_agent_sdk::session::{FileSessionStore, SessionQuery, SessionStore};
+//! use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient};
+//!
+//! # async fn example() -> anthropic_agent_sdk::Result<()> {
+//! let store = Arc::new(FileSessionStore::new("/var/lib/agent/sessions"));
+//! let options = ClaudeAgentOptions::builder()
+//!     .user("tenant-42")
+//!     .session_store(store.clone())
+//!     .build();
+//! let mut client = ClaudeSDKClient::new(options, None).await?;
+//! // ...
+//!
+//! let expensive = store
+//!     .find_sessions(&SessionQuery {
+//!         user: Some("tenant-42".to_string()),
+//!         updated_after: Some(SystemTime::now() - Duration::from_secs(86_400)),
+//!         min_cost_usd: Some(1.0),
+//!         ..SessionQuery::default()
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::error::Result;
+use crate::types::{ClaudeAgentOptions, ContentBlock, Message, SessionId};
+
+pub use file::FileSessionStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSessionStore;
+
+/// Metadata of a stored session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Session ID assigned by the CLI
+    pub session_id: SessionId,
+    /// User the session ran for (`ClaudeAgentOptions::user`)
+    pub user: Option<String>,
+    /// Model reported by the CLI
+    pub model: Option<String>,
+    /// Working directory of the session
+    pub cwd: Option<PathBuf>,
+    /// When the first message was stored
+    #[serde(with = "unix_millis")]
+    pub created_at: SystemTime,
+    /// When the last message was stored
+    #[serde(with = "unix_millis")]
+    pub updated_at: SystemTime,
+    /// Cost of the session in USD
+    ///
+    /// Summed over every CLI process that ran the session, since each
+    /// reports its own running total with its results.
+    pub total_cost_usd: f64,
+    /// Number of completed turns (result messages)
+    pub turns: u32,
+    /// Number of stored messages
+    pub message_count: u64,
+    /// Number of calls per tool name
+    pub tool_uses: BTreeMap<String, u64>,
+    /// Running total last reported by the current CLI process
+    ///
+    /// Not stored: a loaded record continues in a new process, whose total
+    /// starts again from zero.
+    #[serde(skip)]
+    process_cost_usd: f64,
+}
+
+impl SessionRecord {
+    /// Empty record for a new session
+    #[must_use]
+    pub fn new(session_id: SessionId) -> Self {
+        let now = SystemTime::now();
+        Self {
+            session_id,
+            user: None,
+            model: None,
+            cwd: None,
+            created_at: now,
+            updated_at: now,
+            total_cost_usd: 0.0,
+            turns: 0,
+            message_count: 0,
+            tool_uses: BTreeMap::new(),
+            process_cost_usd: 0.0,
+        }
+    }
+
+    /// Fold a message into the record
+    pub fn record(&mut self, message: &Message) {
+        self.message_count += 1;
+        self.updated_at = SystemTime::now();
+        match message {
+            Message::System { subtype, data } if subtype == "init" => {
+                if let Some(model) = data.get("model").and_then(|v| v.as_str()) {
+                    self.model = Some(model.to_string());
+                }
+                if let Some(cwd) = data.get("cwd").and_then(|v| v.as_str()) {
+                    self.cwd = Some(PathBuf::from(cwd));
+                }
+            }
+            Message::Assistant { message, .. } => {
+                for block in &message.content {
+                    if let ContentBlock::ToolUse { name, .. }
+                    | ContentBlock::ServerToolUse { name, .. } = block
+                    {
+                        *self.tool_uses.entry(name.clone()).or_default() += 1;
+                    }
+                }
+            }
+            Message::Result {
+                total_cost_usd: Some(total),
+                ..
+            } => {
+                self.turns += 1;
+                // A running total of the process; a lower one than before
+                // comes from a new process and is all new cost
+                let previous = if *total >= self.process_cost_usd {
+                    self.process_cost_usd
+                } else {
+                    0.0
+                };
+                self.total_cost_usd += total - previous;
+                self.process_cost_usd = *total;
+            }
+            Message::Result { .. } => self.turns += 1,
+            _ => {}
+        }
+    }
+
+    /// Number of calls to `tool`
+    #[must_use]
+    pub fn tool_use_count(&self, tool: &str) -> u64 {
+        self.tool_uses.get(tool).copied().unwrap_or(0)
+    }
+}
+
+/// Filter for [`SessionStore::find_sessions`]
+///
+/// Unset fields match every session. Results are ordered by `updated_at`,
+/// most recent first.
+#[derive(Debug, Clone, Default)]
+pub struct SessionQuery {
+    /// Sessions of this user
+    pub user: Option<String>,
+    /// Sessions updated at or after this time
+    pub updated_after: Option<SystemTime>,
+    /// Sessions updated before this time
+    pub updated_before: Option<SystemTime>,
+    /// Sessions that cost at least this much (USD)
+    pub min_cost_usd: Option<f64>,
+    /// Sessions that cost at most this much (USD)
+    pub max_cost_usd: Option<f64>,
+    /// Sessions that called this tool
+    pub tool: Option<String>,
+    /// Return at most this many sessions
+    pub limit: Option<usize>,
+}
+
+impl SessionQuery {
+    /// Whether a record passes the filter (`limit` is not considered)
+    #[must_use]
+    pub fn matches(&self, record: &SessionRecord) -> bool {
+        self.user
+            .as_ref()
+            .is_none_or(|user| record.user.as_ref() == Some(user))
+            && self
+                .updated_after
+                .is_none_or(|after| record.updated_at >= after)
+            && self
+                .updated_before
+                .is_none_or(|before| record.updated_at < before)
+            && self
+                .min_cost_usd
+                .is_none_or(|min| record.total_cost_usd >= min)
+            && self
+                .max_cost_usd
+                .is_none_or(|max| record.total_cost_usd <= max)
+            && self
+                .tool
+                .as_ref()
+                .is_none_or(|tool| record.tool_use_count(tool) > 0)
+    }
+}
+
+/// Storage for conversations and their metadata
+///
+/// Implementations must be safe to share between clients; messages of one
+/// session arrive in order from a single client.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Create or replace the record of a session
+    ///
+    /// # Errors
+    /// Returns error if the record cannot be written
+    async fn save_session(&self, record: &SessionRecord) -> Result<()>;
+
+    /// Append a message to a session's history
+    ///
+    /// # Errors
+    /// Returns error if the message cannot be written
+    async fn append_message(&self, session_id: &SessionId, message: &Message) -> Result<()>;
+
+    /// Record of a session, if stored
+    ///
+    /// # Errors
+    /// Returns error if the store cannot be read
+    async fn get_session(&self, session_id: &SessionId) -> Result<Option<SessionRecord>>;
+
+    /// Messages of a session in the order they were appended
+    ///
+    /// # Errors
+    /// Returns error if the store cannot be read
+    async fn messages(&self, session_id: &SessionId) -> Result<Vec<Message>>;
+
+    /// Records matching a query, most recently updated first
+    ///
+    /// # Errors
+    /// Returns error if the store cannot be read
+    async fn find_sessions(&self, query: &SessionQuery) -> Result<Vec<SessionRecord>>;
+
+    /// Delete a session and its messages; returns whether it existed
+    ///
+    /// # Errors
+    /// Returns error if the session cannot be deleted
+    async fn delete_session(&self, session_id: &SessionId) -> Result<bool>;
+}
+
+/// Shared session store
+pub type SharedSessionStore = Arc<dyn SessionStore>;
+
+/// Session a message belongs to, if it says
+fn message_session_id(message: &Message) -> Option<SessionId> {
+    match message {
+        Message::User { session_id, .. } | Message::Assistant { session_id, .. } => {
+            session_id.clone()
+        }
+        Message::Result { session_id, .. } | Message::StreamEvent { session_id, .. } => {
+            Some(session_id.clone())
+        }
+        Message::System { data, .. } => data
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .map(SessionId::new),
+        Message::Unknown { raw } => raw
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .map(SessionId::new),
+    }
+}
+
+/// Writes a client's messages to a [`SessionStore`]
+///
+/// Messages seen before the CLI reports the session ID (the first prompt of
+/// a new session) are held back until it does. The record is saved at each
+/// `Result` and when recording ends. Store errors are logged and never
+/// interrupt the conversation.
+pub(crate) struct SessionRecorder {
+    store: SharedSessionStore,
+    user: Option<String>,
+    cwd: Option<PathBuf>,
+    record: Option<SessionRecord>,
+    pending: Vec<Message>,
+    /// Whether the record changed since it was last saved
+    dirty: bool,
+}
+
+impl SessionRecorder {
+    pub(crate) fn new(store: SharedSessionStore, options: &ClaudeAgentOptions) -> Self {
+        Self {
+            store,
+            user: options.user.clone(),
+            cwd: options.cwd.clone(),
+            record: None,
+            pending: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    /// Record messages on a dedicated task, so a slow store never stalls
+    /// the client's reader
+    ///
+    /// The record is saved one last time when the returned sender is dropped,
+    /// after which the task finishes.
+    pub(crate) fn spawn(mut self) -> (mpsc::UnboundedSender<Message>, JoinHandle<()>) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+        let task = tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                self.record(&message).await;
+            }
+            self.save().await;
+        });
+        (tx, task)
+    }
+
+    /// Store a message (partial stream events are skipped)
+    pub(crate) async fn record(&mut self, message: &Message) {
+        if matches!(message, Message::StreamEvent { .. }) {
+            return;
+        }
+        let session_id = message_session_id(message)
+            .or_else(|| self.record.as_ref().map(|r| r.session_id.clone()));
+        let Some(session_id) = session_id else {
+            self.pending.push(message.clone());
+            return;
+        };
+
+        // A fork continues under a new session ID
+        if self
+            .record
+            .as_ref()
+            .is_none_or(|record| record.session_id != session_id)
+        {
+            self.save().await;
+            self.record = Some(self.open(session_id).await);
+        }
+
+        for message in std::mem::take(&mut self.pending)
+            .iter()
+            .chain(std::iter::once(message))
+        {
+            self.store_message(message).await;
+        }
+        if matches!(message, Message::Result { .. }) {
+            self.save().await;
+        }
+    }
+
+    /// Save the record if it changed since the last save
+    pub(crate) async fn save(&mut self) {
+        let Some(record) = &self.record else {
+            return;
+        };
+        if !std::mem::take(&mut self.dirty) {
+            return;
+        }
+        if let Err(e) = self.store.save_session(record).await {
+            tracing::warn!(error = %e, session_id = %record.session_id, "Failed to save session record");
+        }
+    }
+
+    /// Existing record of a resumed session, or a new one
+    async fn open(&self, session_id: SessionId) -> SessionRecord {
+        let existing = match self.store.get_session(&session_id).await {
+            Ok(existing) => existing,
+            Err(e) => {
+                tracing::warn!(error = %e, session_id = %session_id, "Failed to load session record");
+                None
+            }
+        };
+        let mut record = existing.unwrap_or_else(|| SessionRecord::new(session_id));
+        if self.user.is_some() {
+            record.user.clone_from(&self.user);
+        }
+        if record.cwd.is_none() {
+            record.cwd.clone_from(&self.cwd);
+        }
+        record
+    }
+
+    async fn store_message(&mut self, message: &Message) {
+        let Some(record) = &mut self.record else {
+            return;
+        };
+        if let Err(e) = self.store.append_message(&record.session_id, message).await {
+            tracing::warn!(error = %e, session_id = %record.session_id, "Failed to store message");
+            return;
+        }
+        record.record(message);
+        self.dirty = true;
+    }
+}
+
+/// Serde for `SystemTime` as milliseconds since the Unix epoch
+mod unix_millis {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn to_millis(time: SystemTime) -> i64 {
+        time.duration_since(UNIX_EPOCH)
+            .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+    }
+
+    pub(super) fn from_millis(millis: i64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(u64::try_from(millis).unwrap_or(0))
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        time: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(to_millis(*time))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SystemTime, D::Error> {
+        i64::deserialize(deserializer).map(from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(value: serde_json::Value) -> Message {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn conversation() -> Vec<Message> {
+        vec![
+            message(json!({
+                "type": "system", "subtype": "init", "session_id": "s1",
+                "model": "claude-sonnet-4-5", "cwd": "/work"
+            })),
+            message(json!({
+                "type": "assistant", "session_id": "s1",
+                "message": {"model": "claude-sonnet-4-5", "content": [
+                    {"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "ls"}},
+                    {"type": "tool_use", "id": "t2", "name": "Read", "input": {"file_path": "a"}}
+                ]}
+            })),
+            message(json!({
+                "type": "result", "subtype": "success", "duration_ms": 10,
+                "duration_api_ms": 8, "is_error": false, "num_turns": 2,
+                "session_id": "s1", "total_cost_usd": 0.25
+            })),
+        ]
+    }
+
+    #[test]
+    fn test_record_folds_messages() {
+        let mut record = SessionRecord::new(SessionId::new("s1"));
+        for message in conversation() {
+            record.record(&message);
+        }
+        assert_eq!(record.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(record.cwd, Some(PathBuf::from("/work")));
+        assert_eq!(record.message_count, 3);
+        assert_eq!(record.turns, 1);
+        assert!((record.total_cost_usd - 0.25).abs() < f64::EPSILON);
+        assert_eq!(record.tool_use_count("Bash"), 1);
+        assert_eq!(record.tool_use_count("Write"), 0);
+
+        // The second result reports the session's running total
+        record.record(&message(json!({
+            "type": "result", "subtype": "success", "duration_ms": 10,
+            "duration_api_ms": 8, "is_error": false, "num_turns": 1,
+            "session_id": "s1", "total_cost_usd": 0.4
+        })));
+        assert_eq!(record.turns, 2);
+        assert!((record.total_cost_usd - 0.4).abs() < f64::EPSILON);
+
+        let roundtrip: SessionRecord =
+            serde_json::from_value(serde_json::to_value(&record).unwrap()).unwrap();
+        assert_eq!(roundtrip.tool_uses, record.tool_uses);
+        assert_eq!(
+            unix_millis::to_millis(roundtrip.created_at),
+            unix_millis::to_millis(record.created_at)
+        );
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let mut record = SessionRecord::new(SessionId::new("s1"));
+        record.user = Some("alice".to_string());
+        for message in conversation() {
+            record.record(&message);
+        }
+
+        assert!(SessionQuery::default().matches(&record));
+        let query = SessionQuery {
+            user: Some("alice".to_string()),
+            min_cost_usd: Some(0.1),
+            tool: Some("Bash".to_string()),
+            ..SessionQuery::default()
+        };
+        assert!(query.matches(&record));
+        assert!(
+            !SessionQuery {
+                user: Some("bob".to_string()),
+                ..SessionQuery::default()
+            }
+            .matches(&record)
+        );
+        assert!(
+            !SessionQuery {
+                tool: Some("Write".to_string()),
+                ..SessionQuery::default()
+            }
+            .matches(&record)
+        );
+        assert!(
+            !SessionQuery {
+                updated_before: Some(record.created_at),
+                ..SessionQuery::default()
+            }
+            .matches(&record)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recorder_holds_messages_until_session_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(FileSessionStore::new(dir.path()));
+        let options = ClaudeAgentOptions::builder().user("alice").build();
+        let mut recorder = SessionRecorder::new(store.clone(), &options);
+
+        let prompt = message(json!({
+            "type": "user", "message": {"role": "user", "content": "hi"}
+        }));
+        recorder.record(&prompt).await;
+        assert!(
+            store
+                .find_sessions(&SessionQuery::default())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        for message in conversation() {
+            recorder.record(&message).await;
+        }
+        let session_id = SessionId::new("s1");
+        let record = store.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(record.user.as_deref(), Some("alice"));
+        assert_eq!(record.message_count, 4);
+        let messages = store.messages(&session_id).await.unwrap();
+        assert!(matches!(messages[0], Message::User { .. }));
+        assert!(matches!(messages[3], Message::Result { .. }));
+    }
+
+    fn result(total_cost_usd: f64) -> Message {
+        message(json!({
+            "type": "result", "subtype": "success", "duration_ms": 10,
+            "duration_api_ms": 8, "is_error": false, "num_turns": 1,
+            "session_id": "s1", "total_cost_usd": total_cost_usd
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_recorder_adds_the_cost_of_a_resumed_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(FileSessionStore::new(dir.path()));
+        let options = ClaudeAgentOptions::default();
+        let session_id = SessionId::new("s1");
+
+        let mut recorder = SessionRecorder::new(store.clone(), &options);
+        recorder.record(&result(0.25)).await;
+        recorder.record(&result(0.4)).await;
+        let record = store.get_session(&session_id).await.unwrap().unwrap();
+        assert!((record.total_cost_usd - 0.4).abs() < 1e-9);
+
+        // The resumed session runs in a new CLI process, whose running total
+        // starts again from zero, even once it passes the old total
+        let mut recorder = SessionRecorder::new(store.clone(), &options);
+        recorder.record(&result(0.1)).await;
+        let record = store.get_session(&session_id).await.unwrap().unwrap();
+        assert!((record.total_cost_usd - 0.5).abs() < 1e-9);
+        recorder.record(&result(0.6)).await;
+        let record = store.get_session(&session_id).await.unwrap().unwrap();
+        assert!((record.total_cost_usd - 1.0).abs() < 1e-9);
+        assert_eq!(record.turns, 4);
+    }
+}
@@ -0,0 +1,398 @@
+//! SQLite session store
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+
+use super::unix_millis::{from_millis, to_millis};
+use super::{SessionQuery, SessionRecord, SessionStore};
+use crate::error::{ClaudeError, Result};
+use crate::types::{Message, SessionId};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id     TEXT PRIMARY KEY,
+    user           TEXT,
+    model          TEXT,
+    cwd            TEXT,
+    created_at     INTEGER NOT NULL,
+    updated_at     INTEGER NOT NULL,
+    total_cost_usd REAL NOT NULL,
+    turns          INTEGER NOT NULL,
+    message_count  INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_user_updated ON sessions (user, updated_at);
+CREATE INDEX IF NOT EXISTS sessions_updated ON sessions (updated_at);
+CREATE TABLE IF NOT EXISTS tool_uses (
+    session_id TEXT NOT NULL,
+    tool       TEXT NOT NULL,
+    count      INTEGER NOT NULL,
+    PRIMARY KEY (session_id, tool)
+);
+CREATE INDEX IF NOT EXISTS tool_uses_tool ON tool_uses (tool);
+CREATE TABLE IF NOT EXISTS messages (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    message    TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS messages_session ON messages (session_id, id);
+";
+
+const SESSION_COLUMNS: &str = "session_id, user, model, cwd, created_at, updated_at, \
+                               total_cost_usd, turns, message_count";
+
+fn storage_error(e: rusqlite::Error) -> ClaudeError {
+    ClaudeError::storage(e.to_string())
+}
+
+/// Session store backed by a SQLite database
+///
+/// Sessions are indexed by user and update time, and tool usage is kept in
+/// its own table, so [`find_sessions`](SessionStore::find_sessions) runs as a
+/// single query. Database calls run on tokio's blocking thread pool.
+#[derive(Debug, Clone)]
+pub struct SqliteSessionStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSessionStore {
+    /// Open (or create) the database at `path`
+    ///
+    /// # Errors
+    /// Returns error if the database cannot be opened or migrated
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_connection(Connection::open(path).map_err(storage_error)?)
+    }
+
+    /// In-memory database, discarded when the store is dropped
+    ///
+    /// # Errors
+    /// Returns error if the database cannot be created
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory().map_err(storage_error)?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA).map_err(storage_error)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Run `f` with the connection on the blocking thread pool
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| ClaudeError::storage("SQLite connection lock poisoned"))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| ClaudeError::storage(format!("SQLite task failed: {e}")))?
+    }
+}
+
+/// Read a `sessions` row selected with [`SESSION_COLUMNS`]
+fn session_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionRecord> {
+    Ok(SessionRecord {
+        session_id: SessionId::new(row.get::<_, String>(0)?),
+        user: row.get(1)?,
+        model: row.get(2)?,
+        cwd: row.get::<_, Option<String>>(3)?.map(Into::into),
+        created_at: from_millis(row.get(4)?),
+        updated_at: from_millis(row.get(5)?),
+        total_cost_usd: row.get(6)?,
+        turns: row.get(7)?,
+        message_count: row.get::<_, i64>(8)?.try_into().unwrap_or(0),
+        tool_uses: Default::default(),
+        process_cost_usd: 0.0,
+    })
+}
+
+/// Fill in `tool_uses` of a record
+fn load_tool_uses(conn: &Connection, record: &mut SessionRecord) -> rusqlite::Result<()> {
+    let mut stmt =
+        conn.prepare_cached("SELECT tool, count FROM tool_uses WHERE session_id = ?1")?;
+    let rows = stmt.query_map([record.session_id.as_str()], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    for row in rows {
+        let (tool, count) = row?;
+        record.tool_uses.insert(tool, count.try_into().unwrap_or(0));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SessionStore for SqliteSessionStore {
+    async fn save_session(&self, record: &SessionRecord) -> Result<()> {
+        let record = record.clone();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction().map_err(storage_error)?;
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO sessions ({SESSION_COLUMNS}) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+                ),
+                params![
+                    record.session_id.as_str(),
+                    record.user,
+                    record.model,
+                    record.cwd.as_ref().map(|cwd| cwd.to_string_lossy()),
+                    to_millis(record.created_at),
+                    to_millis(record.updated_at),
+                    record.total_cost_usd,
+                    record.turns,
+                    i64::try_from(record.message_count).unwrap_or(i64::MAX),
+                ],
+            )
+            .map_err(storage_error)?;
+            tx.execute(
+                "DELETE FROM tool_uses WHERE session_id = ?1",
+                [record.session_id.as_str()],
+            )
+            .map_err(storage_error)?;
+            for (tool, count) in &record.tool_uses {
+                tx.execute(
+                    "INSERT INTO tool_uses (session_id, tool, count) VALUES (?1, ?2, ?3)",
+                    params![
+                        record.session_id.as_str(),
+                        tool,
+                        i64::try_from(*count).unwrap_or(i64::MAX)
+                    ],
+                )
+                .map_err(storage_error)?;
+            }
+            tx.commit().map_err(storage_error)
+        })
+        .await
+    }
+
+    async fn append_message(&self, session_id: &SessionId, message: &Message) -> Result<()> {
+        let session_id = session_id.clone();
+        let message = serde_json::to_string(message)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO messages (session_id, message) VALUES (?1, ?2)",
+                params![session_id.as_str(), message],
+            )
+            .map_err(storage_error)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get_session(&self, session_id: &SessionId) -> Result<Option<SessionRecord>> {
+        let session_id = session_id.clone();
+        self.with_conn(move |conn| {
+            let record = conn
+                .query_row(
+                    &format!("SELECT {SESSION_COLUMNS} FROM sessions WHERE session_id = ?1"),
+                    [session_id.as_str()],
+                    session_from_row,
+                )
+                .optional()
+                .map_err(storage_error)?;
+            let Some(mut record) = record else {
+                return Ok(None);
+            };
+            load_tool_uses(conn, &mut record).map_err(storage_error)?;
+            Ok(Some(record))
+        })
+        .await
+    }
+
+    async fn messages(&self, session_id: &SessionId) -> Result<Vec<Message>> {
+        let session_id = session_id.clone();
+        self.with_conn(move |conn| {
+            let mut stmt = conn
+                .prepare("SELECT message FROM messages WHERE session_id = ?1 ORDER BY id")
+                .map_err(storage_error)?;
+            let rows = stmt
+                .query_map([session_id.as_str()], |row| row.get::<_, String>(0))
+                .map_err(storage_error)?;
+            rows.map(|row| Ok(serde_json::from_str(&row.map_err(storage_error)?)?))
+                .collect()
+        })
+        .await
+    }
+
+    async fn find_sessions(&self, query: &SessionQuery) -> Result<Vec<SessionRecord>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(user) = &query.user {
+            conditions.push("user = ?");
+            values.push(user.clone().into());
+        }
+        if let Some(after) = query.updated_after {
+            conditions.push("updated_at >= ?");
+            values.push(to_millis(after).into());
+        }
+        if let Some(before) = query.updated_before {
+            conditions.push("updated_at < ?");
+            values.push(to_millis(before).into());
+        }
+        if let Some(min) = query.min_cost_usd {
+            conditions.push("total_cost_usd >= ?");
+            values.push(min.into());
+        }
+        if let Some(max) = query.max_cost_usd {
+            conditions.push("total_cost_usd <= ?");
+            values.push(max.into());
+        }
+        if let Some(tool) = &query.tool {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM tool_uses t \
+                 WHERE t.session_id = sessions.session_id AND t.tool = ? AND t.count > 0)",
+            );
+            values.push(tool.clone().into());
+        }
+
+        let mut sql = format!("SELECT {SESSION_COLUMNS} FROM sessions");
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY updated_at DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(" LIMIT ?");
+            values.push(i64::try_from(limit).unwrap_or(i64::MAX).into());
+        }
+
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(&sql).map_err(storage_error)?;
+            let mut records = stmt
+                .query_map(params_from_iter(values), session_from_row)
+                .map_err(storage_error)?
+                .collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(storage_error)?;
+            for record in &mut records {
+                load_tool_uses(conn, record).map_err(storage_error)?;
+            }
+            Ok(records)
+        })
+        .await
+    }
+
+    async fn delete_session(&self, session_id: &SessionId) -> Result<bool> {
+        let session_id = session_id.clone();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction().map_err(storage_error)?;
+            let id = session_id.as_str();
+            let deleted = tx
+                .execute("DELETE FROM sessions WHERE session_id = ?1", [id])
+                .map_err(storage_error)?;
+            let messages = tx
+                .execute("DELETE FROM messages WHERE session_id = ?1", [id])
+                .map_err(storage_error)?;
+            tx.execute("DELETE FROM tool_uses WHERE session_id = ?1", [id])
+                .map_err(storage_error)?;
+            tx.commit().map_err(storage_error)?;
+            Ok(deleted + messages > 0)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn record(id: &str, user: &str, cost: f64, tools: &[&str]) -> SessionRecord {
+        let mut record = SessionRecord::new(SessionId::new(id));
+        record.user = Some(user.to_string());
+        record.total_cost_usd = cost;
+        for tool in tools {
+            *record.tool_uses.entry((*tool).to_string()).or_default() += 1;
+        }
+        record
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_store_queries() {
+        let store = SqliteSessionStore::open_in_memory().unwrap();
+        let mut old = record("s1", "alice", 0.5, &["Bash"]);
+        old.updated_at = SystemTime::now() - Duration::from_secs(7 * 86_400);
+        store.save_session(&old).await.unwrap();
+        store
+            .save_session(&record("s2", "alice", 2.0, &["Read", "Edit"]))
+            .await
+            .unwrap();
+        store
+            .save_session(&record("s3", "bob", 1.0, &["Bash"]))
+            .await
+            .unwrap();
+
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "type": "user", "message": {"role": "user", "content": "hello"}
+        }))
+        .unwrap();
+        store
+            .append_message(&SessionId::new("s2"), &message)
+            .await
+            .unwrap();
+
+        let ids = |records: Vec<SessionRecord>| -> Vec<String> {
+            records
+                .into_iter()
+                .map(|r| r.session_id.to_string())
+                .collect()
+        };
+
+        let alice = SessionQuery {
+            user: Some("alice".to_string()),
+            ..SessionQuery::default()
+        };
+        assert_eq!(
+            ids(store.find_sessions(&alice).await.unwrap()),
+            ["s2", "s1"]
+        );
+        let recent = SessionQuery {
+            updated_after: Some(SystemTime::now() - Duration::from_secs(86_400)),
+            ..alice.clone()
+        };
+        assert_eq!(ids(store.find_sessions(&recent).await.unwrap()), ["s2"]);
+        let bash = SessionQuery {
+            tool: Some("Bash".to_string()),
+            max_cost_usd: Some(1.0),
+            ..SessionQuery::default()
+        };
+        let mut found = ids(store.find_sessions(&bash).await.unwrap());
+        found.sort();
+        assert_eq!(found, ["s1", "s3"]);
+        let limited = SessionQuery {
+            limit: Some(1),
+            ..SessionQuery::default()
+        };
+        assert_eq!(store.find_sessions(&limited).await.unwrap().len(), 1);
+
+        let s2 = store
+            .get_session(&SessionId::new("s2"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(s2.tool_use_count("Edit"), 1);
+        assert_eq!(
+            store.messages(&SessionId::new("s2")).await.unwrap().len(),
+            1
+        );
+
+        assert!(store.delete_session(&SessionId::new("s2")).await.unwrap());
+        assert!(!store.delete_session(&SessionId::new("s2")).await.unwrap());
+        assert!(
+            store
+                .messages(&SessionId::new("s2"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
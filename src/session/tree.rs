@@ -0,0 +1,280 @@
+//! Branch structure of a session transcript
+
+use std::collections::{HashMap, HashSet};
+
+use crate::transcript::{Transcript, TranscriptEntry};
+use crate::types::{ContentBlock, Message, SessionId, UserContent};
+
+/// A branch of a session: the conversation from the root to one leaf
+#[derive(Debug, Clone)]
+pub struct Branch {
+    /// Session the branch belongs to
+    pub session_id: SessionId,
+    /// UUID of the last message of the branch
+    pub leaf_uuid: String,
+    /// UUID of the nearest ancestor where another branch diverges
+    /// (`None` if the branch shares no history with another branch)
+    pub fork_point: Option<String>,
+    /// Number of user and assistant messages on the branch
+    pub length: usize,
+    /// Timestamp of the leaf (ISO 8601)
+    pub last_activity: Option<String>,
+    /// Text of the last user prompt on the branch
+    pub last_prompt: Option<String>,
+    /// Whether this is the branch the CLI continues when the session is resumed
+    pub is_main: bool,
+}
+
+/// The text of a user message typed by the user (not a tool result)
+pub(crate) fn prompt_text(message: &Message) -> Option<String> {
+    let Message::User { message, .. } = message else {
+        return None;
+    };
+    match message.content.as_ref()? {
+        UserContent::String(text) => Some(text.clone()),
+        UserContent::Blocks(blocks) => {
+            let texts: Vec<&str> = blocks
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            (!texts.is_empty()).then(|| texts.join("\n"))
+        }
+    }
+}
+
+/// Whether an entry is part of the main conversation (a user or assistant
+/// message outside subagent sidechains)
+fn is_conversation(entry: &TranscriptEntry) -> bool {
+    !entry.is_sidechain
+        && entry.uuid.is_some()
+        && matches!(
+            entry.message,
+            Message::User { .. } | Message::Assistant { .. }
+        )
+}
+
+/// A session transcript viewed as a tree of messages
+#[derive(Debug, Clone)]
+pub struct SessionTree {
+    transcript: Transcript,
+    index: HashMap<String, usize>,
+    children: HashMap<String, Vec<usize>>,
+    /// Entries whose subtree contains a conversation entry
+    leads_to_conversation: HashSet<String>,
+}
+
+impl SessionTree {
+    /// Build the tree of a transcript
+    #[must_use]
+    pub fn new(transcript: Transcript) -> Self {
+        let mut index = HashMap::new();
+        let mut children: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, entry) in transcript.entries.iter().enumerate() {
+            if let Some(uuid) = &entry.uuid {
+                index.insert(uuid.clone(), i);
+            }
+            if let Some(parent) = &entry.parent_uuid {
+                children.entry(parent.clone()).or_default().push(i);
+            }
+        }
+
+        // Children follow their parents in the file, so a reverse pass sees
+        // every child before its parent
+        let mut leads_to_conversation = HashSet::new();
+        for entry in transcript.entries.iter().rev() {
+            let Some(uuid) = &entry.uuid else {
+                continue;
+            };
+            let child_leads = children.get(uuid).into_iter().flatten().any(|&child| {
+                transcript.entries[child]
+                    .uuid
+                    .as_ref()
+                    .is_some_and(|c| leads_to_conversation.contains(c))
+            });
+            if is_conversation(entry) || child_leads {
+                leads_to_conversation.insert(uuid.clone());
+            }
+        }
+
+        Self {
+            transcript,
+            index,
+            children,
+            leads_to_conversation,
+        }
+    }
+
+    /// The underlying transcript
+    #[must_use]
+    pub fn transcript(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    /// Session ID
+    #[must_use]
+    pub fn session_id(&self) -> &SessionId {
+        &self.transcript.session_id
+    }
+
+    /// Entry with the given UUID
+    #[must_use]
+    pub fn entry(&self, uuid: &str) -> Option<&TranscriptEntry> {
+        self.index.get(uuid).map(|&i| &self.transcript.entries[i])
+    }
+
+    fn parent_of(&self, entry: &TranscriptEntry) -> Option<&TranscriptEntry> {
+        entry.parent_uuid.as_deref().and_then(|p| self.entry(p))
+    }
+
+    /// UUIDs of `uuid` and everything that descends from it
+    pub(crate) fn subtree(&self, uuid: &str) -> HashSet<String> {
+        let mut subtree = HashSet::new();
+        let mut stack = vec![uuid.to_string()];
+        while let Some(current) = stack.pop() {
+            if !subtree.insert(current.clone()) {
+                continue;
+            }
+            for &child in self.children.get(&current).into_iter().flatten() {
+                if let Some(child_uuid) = &self.transcript.entries[child].uuid {
+                    stack.push(child_uuid.clone());
+                }
+            }
+        }
+        subtree
+    }
+
+    fn subtree_has_conversation(&self, uuid: &str, exclude: &HashSet<&str>) -> bool {
+        self.subtree(uuid)
+            .iter()
+            .any(|u| !exclude.contains(u.as_str()) && self.entry(u).is_some_and(is_conversation))
+    }
+
+    /// The conversation entry the CLI continues from on resume (the last one
+    /// in the file)
+    fn main_leaf(&self) -> Option<&str> {
+        self.transcript
+            .entries
+            .iter()
+            .rev()
+            .find(|entry| is_conversation(entry))
+            .and_then(|entry| entry.uuid.as_deref())
+    }
+
+    /// UUIDs of conversation entries no other conversation entry descends from
+    fn leaf_uuids(&self) -> Vec<&str> {
+        let mut has_descendant: HashSet<&str> = HashSet::new();
+        for entry in self
+            .transcript
+            .entries
+            .iter()
+            .filter(|e| is_conversation(e))
+        {
+            let mut current = self.parent_of(entry);
+            let mut steps = 0;
+            while let Some(ancestor) = current {
+                let Some(uuid) = ancestor.uuid.as_deref() else {
+                    break;
+                };
+                // Stop at already-marked ancestors; the guard protects against cycles
+                if !has_descendant.insert(uuid) || steps > self.index.len() {
+                    break;
+                }
+                steps += 1;
+                current = self.parent_of(ancestor);
+            }
+        }
+        self.transcript
+            .entries
+            .iter()
+            .filter(|entry| is_conversation(entry))
+            .filter_map(|entry| entry.uuid.as_deref())
+            .filter(|uuid| !has_descendant.contains(uuid))
+            .collect()
+    }
+
+    /// Number of children of `uuid` that lead to further conversation
+    fn branch_count_at(&self, uuid: &str) -> usize {
+        self.children
+            .get(uuid)
+            .into_iter()
+            .flatten()
+            .filter_map(|&child| self.transcript.entries[child].uuid.as_ref())
+            .filter(|child| self.leads_to_conversation.contains(*child))
+            .count()
+    }
+
+    /// Build the branch ending at `leaf`
+    fn branch(&self, leaf: &str, main_leaf: Option<&str>) -> Branch {
+        let thread = self.transcript.thread(leaf);
+        let leaf_entry = self.entry(leaf);
+
+        let fork_point = thread
+            .iter()
+            .rev()
+            .skip(1)
+            .filter_map(|entry| entry.uuid.as_deref())
+            .find(|uuid| self.branch_count_at(uuid) > 1)
+            .map(String::from);
+
+        Branch {
+            session_id: self.transcript.session_id.clone(),
+            leaf_uuid: leaf.to_string(),
+            fork_point,
+            length: thread.iter().filter(|e| is_conversation(e)).count(),
+            last_activity: leaf_entry.and_then(|e| e.timestamp.clone()),
+            last_prompt: thread
+                .iter()
+                .rev()
+                .find_map(|entry| prompt_text(&entry.message)),
+            is_main: main_leaf == Some(leaf),
+        }
+    }
+
+    /// All branches, most recently active first
+    #[must_use]
+    pub fn branches(&self) -> Vec<Branch> {
+        let main_leaf = self.main_leaf();
+        let mut branches: Vec<Branch> = self
+            .leaf_uuids()
+            .into_iter()
+            .map(|leaf| self.branch(leaf, main_leaf))
+            .collect();
+        // ISO 8601 timestamps sort lexicographically
+        branches.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+        branches
+    }
+
+    /// The branch the CLI continues when the session is resumed
+    #[must_use]
+    pub fn main_branch(&self) -> Option<Branch> {
+        let main_leaf = self.main_leaf()?;
+        Some(self.branch(main_leaf, Some(main_leaf)))
+    }
+
+    /// Entries removed when pruning the branch ending at `leaf`: the leaf
+    /// and every ancestor up to (excluding) the point where another branch
+    /// diverges, with everything that hangs off them
+    ///
+    /// Returns `None` if `leaf` is unknown or the branch shares no history
+    /// with another branch (pruning it would empty the session).
+    pub(crate) fn prune_set(&self, leaf: &str) -> Option<HashSet<String>> {
+        let thread = self.transcript.thread(leaf);
+        if thread.is_empty() {
+            return None;
+        }
+        let on_path: HashSet<&str> = thread.iter().filter_map(|e| e.uuid.as_deref()).collect();
+
+        let mut top = leaf;
+        for entry in thread.iter().rev().skip(1) {
+            let uuid = entry.uuid.as_deref()?;
+            if self.subtree_has_conversation(uuid, &on_path) {
+                return Some(self.subtree(top));
+            }
+            top = uuid;
+        }
+        None
+    }
+}
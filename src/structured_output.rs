@@ -0,0 +1,508 @@
+//! Structured output validation and repair
+//!
+//! With `output_format` set, the CLI can still end a turn with
+//! `structured_output` missing or not matching the schema.
+//! [`validate`] checks a value against a JSON schema, and a
+//! [`StructuredOutputPolicy`] set as `ClaudeAgentOptions::structured_output_policy`
+//! makes [`ClaudeSDKClient::ask_structured`](crate::ClaudeSDKClient::ask_structured)
+//! (and `ask_typed`) validate every result and send a corrective follow-up
+//! listing the violations, up to `max_attempts` times.
+//!
+//! The validator covers the keywords JSON schemas for structured output use:
+//! `type`, `enum`, `const`, `properties`, `required`, `additionalProperties`,
+//! `items`, `prefixItems`, `minItems`, `maxItems`, `minLength`, `maxLength`,
+//! `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `allOf`,
+//! `anyOf`, `oneOf`, `not` and local `$ref`s (`#/$defs/...`). Other keywords
+//! (`pattern`, `format`, ...) are not checked.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::structured_output::StructuredOutputPolicy;
+//! use anthropic_agent_sdk::types::OutputFormat;
+//! use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient};
+//!
+//! # async fn example() -> anthropic_agent_sdk::Result<()> {
+//! let options = ClaudeAgentOptions::builder()
+//!     .output_format(OutputFormat::json_schema(serde_json::json!({
+//!         "type": "object",
+//!         "properties": {"score": {"type": "integer", "minimum": 0, "maximum": 10}},
+//!         "required": ["score"]
+//!     })))
+//!     .structured_output_policy(StructuredOutputPolicy::new(3))
+//!     .build();
+//! let mut client = ClaudeSDKClient::new(options, None).await?;
+//! let output = client.ask_structured("Rate this README from 0 to 10").await?;
+//! println!("score: {}", output["score"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::types::Message;
+
+/// A place where a value does not match its schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Location in the value (`$` is the root, e.g. `$.items[2].name`)
+    pub path: String,
+    /// What is wrong
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Validate `value` against a JSON schema, returning every violation
+///
+/// An empty list means the value is valid.
+#[must_use]
+pub fn validate(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut validator = Validator {
+        root: schema,
+        violations: Vec::new(),
+        depth: 0,
+    };
+    validator.check(schema, value, "$");
+    validator.violations
+}
+
+/// Limit on nested `$ref` resolution (guards against recursive schemas
+/// applied to cyclic references)
+const MAX_DEPTH: usize = 64;
+
+struct Validator<'a> {
+    root: &'a Value,
+    violations: Vec<SchemaViolation>,
+    depth: usize,
+}
+
+impl<'a> Validator<'a> {
+    fn fail(&mut self, path: &str, message: impl Into<String>) {
+        self.violations.push(SchemaViolation {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Whether `value` matches `schema`, without recording violations
+    fn matches(&mut self, schema: &'a Value, value: &Value, path: &str) -> bool {
+        let before = self.violations.len();
+        self.check(schema, value, path);
+        let matched = self.violations.len() == before;
+        self.violations.truncate(before);
+        matched
+    }
+
+    fn check(&mut self, schema: &'a Value, value: &Value, path: &str) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                self.fail(path, "no value is allowed here");
+                return;
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer));
+            match target {
+                Some(target) if self.depth < MAX_DEPTH => {
+                    self.depth += 1;
+                    self.check(target, value, path);
+                    self.depth -= 1;
+                }
+                Some(_) => self.fail(path, "schema nests too deeply"),
+                None => self.fail(path, format!("unresolvable $ref {reference}")),
+            }
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+                self.fail(
+                    path,
+                    format!("expected {}, got {}", types.join(" or "), type_name(value)),
+                );
+                // Keyword checks below assume the right type
+                return;
+            }
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                self.fail(
+                    path,
+                    format!("{value} is not one of {}", Value::from(allowed.clone())),
+                );
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if constant != value {
+                self.fail(path, format!("expected {constant}, got {value}"));
+            }
+        }
+
+        match value {
+            Value::Object(object) => self.check_object(schema, object, path),
+            Value::Array(items) => self.check_array(schema, items, path),
+            Value::String(text) => self.check_string(schema, text, path),
+            Value::Number(_) => self.check_number(schema, value, path),
+            _ => {}
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for sub in all {
+                self.check(sub, value, path);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any.iter().any(|sub| self.matches(sub, value, path)) {
+                self.fail(path, "does not match any of the allowed schemas (anyOf)");
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let count = one
+                .iter()
+                .filter(|sub| self.matches(sub, value, path))
+                .count();
+            if count != 1 {
+                self.fail(
+                    path,
+                    format!("must match exactly one schema (oneOf), matches {count}"),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.matches(not, value, path) {
+                self.fail(path, "matches a schema it must not match (not)");
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &'a serde_json::Map<String, Value>,
+        object: &serde_json::Map<String, Value>,
+        path: &str,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    self.fail(path, format!("missing required property `{name}`"));
+                }
+            }
+        }
+        for (name, field) in object {
+            let field_path = format!("{path}.{name}");
+            match properties.and_then(|p| p.get(name)) {
+                Some(field_schema) => self.check(field_schema, field, &field_path),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        self.fail(path, format!("unexpected property `{name}`"));
+                    }
+                    Some(extra) => self.check(extra, field, &field_path),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn check_array(
+        &mut self,
+        schema: &'a serde_json::Map<String, Value>,
+        items: &[Value],
+        path: &str,
+    ) {
+        let prefix = schema
+            .get("prefixItems")
+            .and_then(Value::as_array)
+            .map_or(&[][..], Vec::as_slice);
+        for (i, item) in items.iter().enumerate() {
+            let item_schema = prefix.get(i).or_else(|| schema.get("items"));
+            if let Some(item_schema) = item_schema {
+                self.check(item_schema, item, &format!("{path}[{i}]"));
+            }
+        }
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                self.fail(
+                    path,
+                    format!("expected at least {min} items, got {}", items.len()),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if items.len() as u64 > max {
+                self.fail(
+                    path,
+                    format!("expected at most {max} items, got {}", items.len()),
+                );
+            }
+        }
+    }
+
+    fn check_string(&mut self, schema: &serde_json::Map<String, Value>, text: &str, path: &str) {
+        let length = text.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                self.fail(
+                    path,
+                    format!("expected at least {min} characters, got {length}"),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                self.fail(
+                    path,
+                    format!("expected at most {max} characters, got {length}"),
+                );
+            }
+        }
+    }
+
+    fn check_number(&mut self, schema: &serde_json::Map<String, Value>, value: &Value, path: &str) {
+        let Some(number) = value.as_f64() else {
+            return;
+        };
+        let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+        if let Some(min) = bound("minimum") {
+            if number < min {
+                self.fail(path, format!("{value} is less than the minimum {min}"));
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if number > max {
+                self.fail(path, format!("{value} is greater than the maximum {max}"));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if number <= min {
+                self.fail(path, format!("{value} must be greater than {min}"));
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if number >= max {
+                self.fail(path, format!("{value} must be less than {max}"));
+            }
+        }
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// How the client validates and repairs structured output
+#[derive(Debug, Clone)]
+pub struct StructuredOutputPolicy {
+    /// Total number of turns to try, including the first (at least 1)
+    pub max_attempts: u32,
+    /// Text sent before the list of violations in a corrective follow-up
+    pub repair_instructions: String,
+}
+
+impl StructuredOutputPolicy {
+    /// Policy allowing `max_attempts` turns in total
+    #[must_use]
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Follow-up message asking for output without `violations`
+    #[must_use]
+    pub fn repair_message(&self, violations: &[SchemaViolation]) -> String {
+        let mut message = self.repair_instructions.clone();
+        for violation in violations {
+            message.push_str("\n- ");
+            message.push_str(&violation.to_string());
+        }
+        message
+    }
+}
+
+impl Default for StructuredOutputPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            repair_instructions: "Your structured output does not match the required JSON \
+                                  schema. Produce it again, fixing these problems:"
+                .to_string(),
+        }
+    }
+}
+
+/// One turn of a structured output request that did not produce valid output
+#[derive(Debug, Clone)]
+pub struct StructuredOutputAttempt {
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// Output as returned by the CLI (`null` if there was none)
+    pub raw: Value,
+    /// Why the output was rejected
+    pub violations: Vec<SchemaViolation>,
+}
+
+/// Violations of the last attempt, for error messages
+pub(crate) fn last_violations(attempts: &[StructuredOutputAttempt]) -> String {
+    attempts
+        .last()
+        .map(|attempt| {
+            attempt
+                .violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        })
+        .unwrap_or_default()
+}
+
+/// Output of a `Result` message, and the violations that make it unusable
+/// before any schema check (failed run, no output)
+pub(crate) fn result_output(message: &Message) -> (Value, Vec<SchemaViolation>) {
+    let root_violation = |message: String| {
+        vec![SchemaViolation {
+            path: "$".to_string(),
+            message,
+        }]
+    };
+    match message.parse_structured_output::<Value>() {
+        Ok(raw) => (raw, Vec::new()),
+        Err(crate::ClaudeError::StructuredOutput { message, raw, .. }) => {
+            (raw, root_violation(message))
+        }
+        Err(e) => (Value::Null, root_violation(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(violations: &[SchemaViolation]) -> Vec<&str> {
+        violations.iter().map(|v| v.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_validate_objects_and_arrays() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "score": {"type": "integer", "minimum": 0, "maximum": 10},
+                "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+                "status": {"enum": ["open", "closed"]}
+            },
+            "required": ["name", "score"],
+            "additionalProperties": false
+        });
+
+        let valid = json!({"name": "x", "score": 7, "tags": ["a"], "status": "open"});
+        assert!(validate(&schema, &valid).is_empty());
+
+        let invalid = json!({
+            "name": "", "score": 11.5, "tags": ["a", 2, "c"], "status": "stale", "extra": true
+        });
+        let violations = validate(&schema, &invalid);
+        assert_eq!(
+            paths(&violations),
+            ["$", "$.name", "$.score", "$.status", "$.tags[1]", "$.tags"]
+        );
+        assert!(violations[0].message.contains("extra"));
+
+        let violations = validate(&schema, &json!({"score": "7"}));
+        assert_eq!(paths(&violations), ["$", "$.score"]);
+        assert_eq!(violations[0].message, "missing required property `name`");
+        assert_eq!(
+            violations[1].to_string(),
+            "$.score: expected integer, got string"
+        );
+    }
+
+    #[test]
+    fn test_validate_refs_and_combinators() {
+        // Shape produced by schemars for a struct with an optional enum field
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "level": {"anyOf": [{"$ref": "#/$defs/Level"}, {"type": "null"}]},
+                "items": {"type": "array", "items": {"$ref": "#/$defs/Item"}}
+            },
+            "required": ["items"],
+            "$defs": {
+                "Level": {"type": "string", "enum": ["low", "high"]},
+                "Item": {
+                    "type": "object",
+                    "properties": {"id": {"type": "integer"}},
+                    "required": ["id"]
+                }
+            }
+        });
+
+        assert!(validate(&schema, &json!({"level": null, "items": [{"id": 1}]})).is_empty());
+        assert!(validate(&schema, &json!({"level": "high", "items": []})).is_empty());
+
+        let violations = validate(&schema, &json!({"level": "medium", "items": [{}]}));
+        assert_eq!(paths(&violations), ["$.items[0]", "$.level"]);
+
+        let one_of = json!({"oneOf": [{"type": "integer"}, {"type": "number"}]});
+        assert_eq!(validate(&one_of, &json!(1)).len(), 1);
+        assert!(validate(&one_of, &json!(1.5)).is_empty());
+
+        let broken = json!({"$ref": "#/$defs/Missing"});
+        assert_eq!(validate(&broken, &json!(1)).len(), 1);
+    }
+
+    #[test]
+    fn test_repair_message_lists_violations() {
+        let policy = StructuredOutputPolicy::default();
+        let message = policy.repair_message(&[SchemaViolation {
+            path: "$.score".to_string(),
+            message: "expected integer, got string".to_string(),
+        }]);
+        assert!(message.starts_with(&policy.repair_instructions));
+        assert!(message.ends_with("\n- $.score: expected integer, got string"));
+    }
+}
@@ -0,0 +1,889 @@
+//! Test utilities (requires the `testing` feature)
+//!
+//! This module provides [`MockTransport`], an in-memory [`Transport`] that
+//! replays a scripted sequence of stream-json lines and records everything the
+//! SDK writes. Combined with [`ClaudeSDKClient::with_transport`], it lets you
+//! exercise the client, hooks and permission callbacks end to end without
+//! spawning the Claude Code CLI.
+//!
+//! # Example
+//!
+//! ```
+//! use anthropic_agent_sdk::testing::{self, MockTransport};
+//! use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient, Message};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anthropic_agent_sdk::Result<()> {
+//! let mock = MockTransport::new()
+//!     .emit(testing::system_init("session-1"))
+//!     .wait_for_user_message()
+//!     .emit(testing::assistant_text("session-1", "Hello!"))
+//!     .emit(testing::result("session-1", "Hello!"));
+//! let handle = mock.handle();
+//!
+//! let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default()).await?;
+//! client.send_message("Hi").await?;
+//!
+//! while let Some(message) = client.next_message().await {
+//!     if let Message::Result { .. } = message? {
+//!         break;
+//!     }
+//! }
+//!
+//! assert_eq!(handle.user_messages().len(), 1);
+//! client.close().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ClaudeSDKClient::with_transport`]: crate::ClaudeSDKClient::with_transport
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{ClaudeError, Result};
+use crate::transport::Transport;
+
+/// Default time a wait step blocks before failing the stream
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sender half of the mock message stream
+type MessageSender = mpsc::UnboundedSender<Result<Value>>;
+
+/// Reply the mock sends when the SDK writes a `control_request`
+#[derive(Debug, Clone)]
+pub enum MockControlReply {
+    /// Reply with a success `control_response` carrying the given payload
+    Success(Option<Value>),
+    /// Reply with an error `control_response` carrying the given message
+    Error(String),
+    /// Do not reply at all (useful for timeout tests)
+    NoReply,
+}
+
+/// A single step of a mock script
+#[derive(Debug, Clone)]
+enum ScriptStep {
+    /// Emit a JSON value on stdout
+    Emit(Value),
+    /// Emit a raw stdout line (parsed like the subprocess transport would)
+    EmitRaw(String),
+    /// Block until the SDK writes a line matching the expectation
+    Await(Expectation),
+    /// Sleep before continuing
+    Delay(Duration),
+    /// Simulate the CLI exiting with a non-zero status
+    Fail { exit_code: i32, stderr: String },
+    /// Close the stdout stream (simulates the CLI exiting cleanly)
+    EndStream,
+}
+
+/// A write the script waits for
+#[derive(Debug, Clone)]
+enum Expectation {
+    UserMessage,
+    ControlRequest(String),
+    ControlResponse(String),
+}
+
+impl Expectation {
+    fn matches(&self, value: &Value) -> bool {
+        let msg_type = value.get("type").and_then(Value::as_str);
+        match self {
+            Self::UserMessage => msg_type == Some("user"),
+            Self::ControlRequest(subtype) => {
+                msg_type == Some("control_request")
+                    && value
+                        .get("request")
+                        .and_then(|r| r.get("subtype"))
+                        .and_then(Value::as_str)
+                        == Some(subtype.as_str())
+            }
+            Self::ControlResponse(request_id) => {
+                msg_type == Some("control_response")
+                    && value
+                        .get("response")
+                        .and_then(|r| r.get("request_id"))
+                        .and_then(Value::as_str)
+                        == Some(request_id.as_str())
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::UserMessage => "user message".to_string(),
+            Self::ControlRequest(subtype) => format!("control_request '{subtype}'"),
+            Self::ControlResponse(request_id) => format!("control_response for '{request_id}'"),
+        }
+    }
+}
+
+/// State shared between a [`MockTransport`] and its [`MockTransportHandle`]s
+struct MockState {
+    writes: std::sync::Mutex<Vec<String>>,
+    write_count: watch::Sender<usize>,
+    stream_tx: std::sync::Mutex<Option<MessageSender>>,
+    control_replies: std::sync::Mutex<HashMap<String, MockControlReply>>,
+    expected_control_requests: std::sync::Mutex<Vec<String>>,
+    input_ended: CancellationToken,
+    shutdown: CancellationToken,
+}
+
+impl MockState {
+    fn written_json(&self) -> Vec<Value> {
+        self.writes
+            .lock()
+            .map(|writes| {
+                writes
+                    .iter()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn send(&self, item: Result<Value>) {
+        if let Ok(guard) = self.stream_tx.lock() {
+            if let Some(ref tx) = *guard {
+                let _ = tx.send(item);
+            }
+        }
+    }
+
+    /// Answer a `control_request` written by the SDK
+    fn reply_to_control_request(&self, value: &Value) {
+        let Some(request_id) = value.get("request_id").and_then(Value::as_str) else {
+            return;
+        };
+        let subtype = value
+            .get("request")
+            .and_then(|r| r.get("subtype"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        let reply = self
+            .control_replies
+            .lock()
+            .ok()
+            .and_then(|replies| replies.get(subtype).cloned())
+            .unwrap_or(MockControlReply::Success(None));
+
+        let response = match reply {
+            MockControlReply::Success(payload) => {
+                let mut response = json!({"subtype": "success", "request_id": request_id});
+                if let Some(payload) = payload {
+                    response["response"] = payload;
+                }
+                response
+            }
+            MockControlReply::Error(message) => {
+                json!({"subtype": "error", "request_id": request_id, "error": message})
+            }
+            MockControlReply::NoReply => return,
+        };
+
+        self.send(Ok(
+            json!({"type": "control_response", "response": response}),
+        ));
+    }
+}
+
+/// In-memory scripted [`Transport`] for deterministic tests
+///
+/// A `MockTransport` plays back a script of stdout lines built with
+/// [`emit`](Self::emit) and friends, and records every line the SDK writes to
+/// "stdin". Scripts can block on SDK writes with the `wait_for_*` steps, so a
+/// reply is only emitted after the SDK has sent the message it answers.
+///
+/// Control requests written by the SDK are answered automatically with a
+/// success `control_response`; use [`control_reply`](Self::control_reply) to
+/// change that per subtype.
+///
+/// After the script finishes, the stream stays open (like a running CLI)
+/// until the transport is closed, its input is ended, or an
+/// [`end_stream`](Self::end_stream) step runs.
+pub struct MockTransport {
+    script: Option<Vec<ScriptStep>>,
+    state: Arc<MockState>,
+    connected: bool,
+    connect_error: Option<String>,
+    wait_timeout: Duration,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockTransport {
+    /// Create a mock transport with an empty script
+    #[must_use]
+    pub fn new() -> Self {
+        let (write_count, _) = watch::channel(0);
+        Self {
+            script: Some(Vec::new()),
+            state: Arc::new(MockState {
+                writes: std::sync::Mutex::new(Vec::new()),
+                write_count,
+                stream_tx: std::sync::Mutex::new(None),
+                control_replies: std::sync::Mutex::new(HashMap::new()),
+                expected_control_requests: std::sync::Mutex::new(Vec::new()),
+                input_ended: CancellationToken::new(),
+                shutdown: CancellationToken::new(),
+            }),
+            connected: false,
+            connect_error: None,
+            wait_timeout: DEFAULT_WAIT_TIMEOUT,
+            task: None,
+        }
+    }
+
+    /// Get a handle for inspecting the transport after it has been moved into a client
+    #[must_use]
+    pub fn handle(&self) -> MockTransportHandle {
+        MockTransportHandle {
+            state: self.state.clone(),
+        }
+    }
+
+    fn push(mut self, step: ScriptStep) -> Self {
+        if let Some(ref mut script) = self.script {
+            script.push(step);
+        }
+        self
+    }
+
+    /// Emit a JSON value on stdout
+    #[must_use]
+    pub fn emit(self, value: Value) -> Self {
+        self.push(ScriptStep::Emit(value))
+    }
+
+    /// Emit a raw stdout line
+    ///
+    /// Lines that are not valid JSON surface as `ClaudeError::JsonDecode`,
+    /// like they would from the subprocess transport.
+    #[must_use]
+    pub fn emit_raw(self, line: impl Into<String>) -> Self {
+        self.push(ScriptStep::EmitRaw(line.into()))
+    }
+
+    /// Block the script until the SDK writes a user message
+    #[must_use]
+    pub fn wait_for_user_message(self) -> Self {
+        self.push(ScriptStep::Await(Expectation::UserMessage))
+    }
+
+    /// Block the script until the SDK writes a `control_request` with the given subtype
+    #[must_use]
+    pub fn wait_for_control_request(self, subtype: impl Into<String>) -> Self {
+        self.push(ScriptStep::Await(Expectation::ControlRequest(
+            subtype.into(),
+        )))
+    }
+
+    /// Block the script until the SDK answers the CLI request with the given id
+    #[must_use]
+    pub fn wait_for_control_response(self, request_id: impl Into<String>) -> Self {
+        self.push(ScriptStep::Await(Expectation::ControlResponse(
+            request_id.into(),
+        )))
+    }
+
+    /// Sleep before running the next step
+    #[must_use]
+    pub fn delay(self, duration: Duration) -> Self {
+        self.push(ScriptStep::Delay(duration))
+    }
+
+    /// Simulate the CLI exiting with a non-zero status
+    ///
+    /// Emits `ClaudeError::Process` and closes the stream.
+    #[must_use]
+    pub fn fail(self, exit_code: i32, stderr: impl Into<String>) -> Self {
+        self.push(ScriptStep::Fail {
+            exit_code,
+            stderr: stderr.into(),
+        })
+    }
+
+    /// Close the stdout stream (simulates the CLI exiting cleanly)
+    #[must_use]
+    pub fn end_stream(self) -> Self {
+        self.push(ScriptStep::EndStream)
+    }
+
+    /// Configure how `control_request`s with the given subtype are answered
+    #[must_use]
+    pub fn control_reply(self, subtype: impl Into<String>, reply: MockControlReply) -> Self {
+        if let Ok(mut replies) = self.state.control_replies.lock() {
+            replies.insert(subtype.into(), reply);
+        }
+        self
+    }
+
+    /// Register a control request subtype that must be written before
+    /// [`MockTransportHandle::verify`] passes
+    #[must_use]
+    pub fn expect_control_request(self, subtype: impl Into<String>) -> Self {
+        if let Ok(mut expected) = self.state.expected_control_requests.lock() {
+            expected.push(subtype.into());
+        }
+        self
+    }
+
+    /// Make `connect()` fail with a connection error
+    #[must_use]
+    pub fn fail_on_connect(mut self, message: impl Into<String>) -> Self {
+        self.connect_error = Some(message.into());
+        self
+    }
+
+    /// Set how long `wait_for_*` steps block before failing (default 5 seconds)
+    #[must_use]
+    pub fn wait_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Run the script, sending stdout lines through the shared stream sender
+    async fn run_script(state: Arc<MockState>, script: Vec<ScriptStep>, wait_timeout: Duration) {
+        let mut write_rx = state.write_count.subscribe();
+        let mut cursor = 0;
+
+        for step in script {
+            match step {
+                ScriptStep::Emit(value) => state.send(Ok(value)),
+                ScriptStep::EmitRaw(line) => {
+                    state.send(serde_json::from_str(&line).map_err(ClaudeError::from));
+                }
+                ScriptStep::Await(expectation) => {
+                    let wait = async {
+                        loop {
+                            write_rx.borrow_and_update();
+                            let found =
+                                state.writes.lock().ok().and_then(|writes| {
+                                    writes.iter().enumerate().skip(cursor).find_map(
+                                        |(index, line)| {
+                                            serde_json::from_str::<Value>(line)
+                                                .ok()
+                                                .filter(|v| expectation.matches(v))
+                                                .map(|_| index)
+                                        },
+                                    )
+                                });
+                            if let Some(index) = found {
+                                return Some(index);
+                            }
+                            if write_rx.changed().await.is_err() {
+                                return None;
+                            }
+                        }
+                    };
+                    match tokio::time::timeout(wait_timeout, wait).await {
+                        Ok(Some(index)) => cursor = index + 1,
+                        Ok(None) => return,
+                        Err(_) => {
+                            state.send(Err(ClaudeError::transport(format!(
+                                "MockTransport: timed out waiting for {}",
+                                expectation.describe()
+                            ))));
+                            return;
+                        }
+                    }
+                }
+                ScriptStep::Delay(duration) => tokio::time::sleep(duration).await,
+                ScriptStep::Fail { exit_code, stderr } => {
+                    state.send(Err(ClaudeError::process(
+                        "Command failed",
+                        exit_code,
+                        Some(stderr),
+                    )));
+                    return;
+                }
+                ScriptStep::EndStream => return,
+            }
+        }
+
+        // Keep the stream open until the transport is shut down or its input
+        // is ended, like a CLI that exits once stdin closes
+        tokio::select! {
+            () = state.shutdown.cancelled() => {}
+            () = state.input_ended.cancelled() => {}
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn connect(&mut self) -> Result<()> {
+        if let Some(ref message) = self.connect_error {
+            return Err(ClaudeError::connection(message.clone()));
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &str) -> Result<()> {
+        if !self.connected {
+            return Err(ClaudeError::transport("MockTransport is not connected"));
+        }
+        if self.state.input_ended.is_cancelled() {
+            return Err(ClaudeError::transport("MockTransport input has been ended"));
+        }
+
+        for line in data.lines().filter(|l| !l.trim().is_empty()) {
+            if let Ok(mut writes) = self.state.writes.lock() {
+                writes.push(line.to_string());
+            }
+            if let Ok(value) = serde_json::from_str::<Value>(line) {
+                if value.get("type").and_then(Value::as_str) == Some("control_request") {
+                    self.state.reply_to_control_request(&value);
+                }
+            }
+            self.state.write_count.send_modify(|count| *count += 1);
+        }
+        Ok(())
+    }
+
+    async fn end_input(&mut self) -> Result<()> {
+        self.state.input_ended.cancel();
+        Ok(())
+    }
+
+    fn read_messages(&mut self) -> mpsc::UnboundedReceiver<Result<Value>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let Some(script) = self.script.take() else {
+            let _ = tx.send(Err(ClaudeError::connection(
+                "MockTransport: messages already taken",
+            )));
+            return rx;
+        };
+
+        if let Ok(mut guard) = self.state.stream_tx.lock() {
+            *guard = Some(tx);
+        }
+
+        let state = self.state.clone();
+        let wait_timeout = self.wait_timeout;
+        self.task = Some(tokio::spawn(async move {
+            Self::run_script(state.clone(), script, wait_timeout).await;
+            // Dropping the sender closes the stream
+            if let Ok(mut guard) = state.stream_tx.lock() {
+                *guard = None;
+            }
+        }));
+
+        rx
+    }
+
+    fn is_ready(&self) -> bool {
+        self.connected && !self.state.shutdown.is_cancelled()
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.connected = false;
+        self.state.shutdown.cancel();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+        if let Ok(mut guard) = self.state.stream_tx.lock() {
+            *guard = None;
+        }
+        Ok(())
+    }
+}
+
+/// Handle for inspecting a [`MockTransport`] after it has been moved into a client
+#[derive(Clone)]
+pub struct MockTransportHandle {
+    state: Arc<MockState>,
+}
+
+impl MockTransportHandle {
+    /// All lines written by the SDK, in order (without trailing newlines)
+    #[must_use]
+    pub fn writes(&self) -> Vec<String> {
+        self.state
+            .writes
+            .lock()
+            .map(|writes| writes.clone())
+            .unwrap_or_default()
+    }
+
+    /// All written lines that parse as JSON
+    #[must_use]
+    pub fn written_json(&self) -> Vec<Value> {
+        self.state.written_json()
+    }
+
+    /// User messages written by the SDK
+    #[must_use]
+    pub fn user_messages(&self) -> Vec<Value> {
+        self.written_of_type("user")
+    }
+
+    /// `control_request` envelopes written by the SDK
+    #[must_use]
+    pub fn control_requests(&self) -> Vec<Value> {
+        self.written_of_type("control_request")
+    }
+
+    /// `control_response` envelopes written by the SDK (answers to CLI requests)
+    #[must_use]
+    pub fn control_responses(&self) -> Vec<Value> {
+        self.written_of_type("control_response")
+    }
+
+    fn written_of_type(&self, msg_type: &str) -> Vec<Value> {
+        self.written_json()
+            .into_iter()
+            .filter(|v| v.get("type").and_then(Value::as_str) == Some(msg_type))
+            .collect()
+    }
+
+    /// Subtypes of all `control_request`s written so far
+    #[must_use]
+    pub fn control_request_subtypes(&self) -> Vec<String> {
+        self.control_requests()
+            .iter()
+            .filter_map(|v| {
+                v.get("request")
+                    .and_then(|r| r.get("subtype"))
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            })
+            .collect()
+    }
+
+    /// Whether the SDK has ended its input stream
+    #[must_use]
+    pub fn input_ended(&self) -> bool {
+        self.state.input_ended.is_cancelled()
+    }
+
+    /// Return the inner `request` object of the first `control_request` with the given subtype
+    ///
+    /// # Panics
+    /// Panics if no such control request was written.
+    #[must_use]
+    pub fn assert_control_request(&self, subtype: &str) -> Value {
+        self.control_requests()
+            .into_iter()
+            .filter_map(|v| v.get("request").cloned())
+            .find(|r| r.get("subtype").and_then(Value::as_str) == Some(subtype))
+            .unwrap_or_else(|| {
+                panic!(
+                    "expected control_request '{subtype}', got {:?}",
+                    self.control_request_subtypes()
+                )
+            })
+    }
+
+    /// Wait until a `control_request` with the given subtype is written
+    ///
+    /// Returns the inner `request` object, or `None` on timeout.
+    pub async fn wait_for_control_request(
+        &self,
+        subtype: &str,
+        timeout: Duration,
+    ) -> Option<Value> {
+        let mut write_rx = self.state.write_count.subscribe();
+        let wait = async {
+            loop {
+                write_rx.borrow_and_update();
+                let found = self
+                    .control_requests()
+                    .into_iter()
+                    .filter_map(|v| v.get("request").cloned())
+                    .find(|r| r.get("subtype").and_then(Value::as_str) == Some(subtype));
+                if found.is_some() {
+                    return found;
+                }
+                if write_rx.changed().await.is_err() {
+                    return None;
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.ok().flatten()
+    }
+
+    /// Assert that every control request registered with
+    /// [`MockTransport::expect_control_request`] has been written
+    ///
+    /// # Panics
+    /// Panics listing the missing subtypes.
+    pub fn verify(&self) {
+        let seen = self.control_request_subtypes();
+        let missing: Vec<String> = self
+            .state
+            .expected_control_requests
+            .lock()
+            .map(|expected| {
+                expected
+                    .iter()
+                    .filter(|subtype| !seen.contains(subtype))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        assert!(
+            missing.is_empty(),
+            "missing expected control requests {missing:?}, got {seen:?}"
+        );
+    }
+}
+
+// ============================================================================
+// Stream-json fixtures
+// ============================================================================
+
+/// A `system` `init` message for the given session
+#[must_use]
+pub fn system_init(session_id: &str) -> Value {
+    json!({
+        "type": "system",
+        "subtype": "init",
+        "session_id": session_id,
+        "cwd": "/tmp/mock",
+        "model": "claude-sonnet-4-5",
+        "tools": ["Bash", "Read", "Write", "Edit", "Glob", "Grep"],
+        "mcp_servers": [],
+        "permissionMode": "default",
+        "apiKeySource": "none",
+        "slash_commands": []
+    })
+}
+
+/// An assistant message with a single text block
+#[must_use]
+pub fn assistant_text(session_id: &str, text: &str) -> Value {
+    json!({
+        "type": "assistant",
+        "session_id": session_id,
+        "message": {
+            "model": "claude-sonnet-4-5",
+            "content": [{"type": "text", "text": text}]
+        }
+    })
+}
+
+/// An assistant message with a single `tool_use` block
+#[must_use]
+pub fn tool_use(session_id: &str, tool_use_id: &str, name: &str, input: &Value) -> Value {
+    json!({
+        "type": "assistant",
+        "session_id": session_id,
+        "message": {
+            "model": "claude-sonnet-4-5",
+            "content": [{"type": "tool_use", "id": tool_use_id, "name": name, "input": input}]
+        }
+    })
+}
+
+/// A user message carrying a `tool_result` block
+#[must_use]
+pub fn tool_result(session_id: &str, tool_use_id: &str, content: &str, is_error: bool) -> Value {
+    json!({
+        "type": "user",
+        "session_id": session_id,
+        "message": {
+            "role": "user",
+            "content": [{
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": content,
+                "is_error": is_error
+            }]
+        }
+    })
+}
+
+/// A successful `result` message ending the turn
+#[must_use]
+pub fn result(session_id: &str, text: &str) -> Value {
+    json!({
+        "type": "result",
+        "subtype": "success",
+        "duration_ms": 100,
+        "duration_api_ms": 80,
+        "is_error": false,
+        "num_turns": 1,
+        "session_id": session_id,
+        "total_cost_usd": 0.001,
+        "result": text
+    })
+}
+
+/// A `stream_event` message wrapping a raw API stream event
+///
+/// Emitted by the CLI when `include_partial_messages` is enabled. Each call
+/// gets a distinct `uuid`.
+#[must_use]
+pub fn stream_event(session_id: &str, event: &Value) -> Value {
+    static NEXT_EVENT: AtomicU64 = AtomicU64::new(0);
+    json!({
+        "type": "stream_event",
+        "uuid": format!("event-{}", NEXT_EVENT.fetch_add(1, Ordering::Relaxed)),
+        "session_id": session_id,
+        "event": event
+    })
+}
+
+/// A `control_request` sent by the CLI to the SDK
+#[must_use]
+pub fn control_request(request_id: &str, request: &Value) -> Value {
+    json!({
+        "type": "control_request",
+        "request_id": request_id,
+        "request": request
+    })
+}
+
+/// A `hook_callback` control request invoking a hook registered during `initialize`
+///
+/// Callback IDs are assigned in registration order (`hook_0`, `hook_1`, ...).
+#[must_use]
+pub fn hook_callback(request_id: &str, callback_id: &str, input: &Value) -> Value {
+    control_request(
+        request_id,
+        &json!({
+            "subtype": "hook_callback",
+            "callback_id": callback_id,
+            "input": input,
+            "tool_use_id": input.get("tool_use_id")
+        }),
+    )
+}
+
+/// A `can_use_tool` control request asking the SDK to approve a tool use
+///
+/// Sent by the CLI when it runs with `--permission-prompt-tool stdio`.
+#[must_use]
+pub fn can_use_tool(
+    request_id: &str,
+    tool_name: &str,
+    input: &Value,
+    permission_suggestions: &Value,
+) -> Value {
+    control_request(
+        request_id,
+        &json!({
+            "subtype": "can_use_tool",
+            "tool_name": tool_name,
+            "input": input,
+            "permission_suggestions": permission_suggestions
+        }),
+    )
+}
+
+/// An `mcp_message` control request carrying JSON-RPC for an SDK MCP server
+#[must_use]
+pub fn mcp_message(request_id: &str, server_name: &str, message: &Value) -> Value {
+    control_request(
+        request_id,
+        &json!({
+            "subtype": "mcp_message",
+            "server_name": server_name,
+            "message": message
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_script_waits_for_user_message() {
+        let mut mock = MockTransport::new()
+            .emit(system_init("s1"))
+            .wait_for_user_message()
+            .emit(result("s1", "done"))
+            .end_stream();
+        let handle = mock.handle();
+        mock.connect().await.unwrap();
+        let mut rx = mock.read_messages();
+
+        let init = rx.recv().await.unwrap().unwrap();
+        assert_eq!(init["subtype"], "init");
+
+        // Nothing more until the SDK writes
+        let pending = tokio::time::timeout(Duration::from_millis(50), rx.recv()).await;
+        assert!(pending.is_err());
+
+        mock.write("{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n")
+            .await
+            .unwrap();
+        let res = rx.recv().await.unwrap().unwrap();
+        assert_eq!(res["type"], "result");
+        assert!(rx.recv().await.is_none());
+        assert_eq!(handle.user_messages().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_control_requests_are_acked() {
+        let mut mock = MockTransport::new()
+            .control_reply("set_model", MockControlReply::Error("bad model".into()));
+        let handle = mock.handle();
+        mock.connect().await.unwrap();
+        let mut rx = mock.read_messages();
+
+        mock.write(
+            r#"{"type":"control_request","request_id":"r1","request":{"subtype":"interrupt"}}"#,
+        )
+        .await
+        .unwrap();
+        mock.write(
+            r#"{"type":"control_request","request_id":"r2","request":{"subtype":"set_model"}}"#,
+        )
+        .await
+        .unwrap();
+
+        let ack = rx.recv().await.unwrap().unwrap();
+        assert_eq!(ack["response"]["subtype"], "success");
+        assert_eq!(ack["response"]["request_id"], "r1");
+        let err = rx.recv().await.unwrap().unwrap();
+        assert_eq!(err["response"]["subtype"], "error");
+        assert_eq!(err["response"]["error"], "bad model");
+
+        assert_eq!(
+            handle.control_request_subtypes(),
+            vec!["interrupt", "set_model"]
+        );
+        assert_eq!(
+            handle.assert_control_request("interrupt")["subtype"],
+            "interrupt"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_timeout_emits_error() {
+        let mut mock = MockTransport::new()
+            .wait_timeout(Duration::from_millis(20))
+            .wait_for_control_request("initialize");
+        mock.connect().await.unwrap();
+        let mut rx = mock.read_messages();
+
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("initialize"));
+    }
+
+    #[tokio::test]
+    async fn test_write_requires_connect() {
+        let mut mock = MockTransport::new();
+        assert!(mock.write("{}\n").await.is_err());
+        assert!(!mock.is_ready());
+    }
+}
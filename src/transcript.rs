@@ -0,0 +1,586 @@
+//! Session transcripts written by the Claude Code CLI
+//!
+//! The CLI records every session as a JSONL file at
+//! `~/.claude/projects/<encoded-cwd>/<session-id>.jsonl` (the root follows
+//! `CLAUDE_CONFIG_DIR` when set). Each line is one entry: a user, assistant or
+//! system message plus metadata such as its `uuid`, the `parentUuid` it
+//! continues from, and a timestamp. Entries form a tree; a resumed or
+//! rewound session branches it.
+//!
+//! [`TranscriptStore`] lists the sessions of a project and locates a session
+//! by ID; [`Transcript`] parses one file into typed [`Message`]s.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::transcript::TranscriptStore;
+//!
+//! # fn main() -> anthropic_agent_sdk::Result<()> {
+//! let store = TranscriptStore::new();
+//! for session in store.list_sessions(std::env::current_dir()?)? {
+//!     let transcript = store.load(&session.session_id)?;
+//!     println!(
+//!         "{}: {} entries, {}",
+//!         session.session_id,
+//!         transcript.entries.len(),
+//!         transcript.summary().unwrap_or("(no summary)")
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::error::{ClaudeError, Result};
+use crate::message::parse_message;
+use crate::types::{Message, SessionId};
+
+/// Encode a working directory the way the CLI names its project directories
+///
+/// Every character other than an ASCII letter or digit becomes `-`, so
+/// `/home/me/my.app` is stored under `-home-me-my-app`.
+#[must_use]
+pub fn encode_project_path(cwd: &Path) -> String {
+    cwd.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Whether a session ID is safe to use as a file name
+pub(crate) fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A session transcript file found on disk
+#[derive(Debug, Clone)]
+pub struct SessionFile {
+    /// Session ID (the file stem)
+    pub session_id: SessionId,
+    /// Path of the transcript file
+    pub path: PathBuf,
+    /// Last modification time
+    pub modified: Option<SystemTime>,
+    /// File size in bytes
+    pub size: u64,
+}
+
+/// Access to the CLI's transcript directory
+#[derive(Debug, Clone)]
+pub struct TranscriptStore {
+    root: PathBuf,
+}
+
+impl Default for TranscriptStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TranscriptStore {
+    /// Store at the CLI's default location
+    ///
+    /// `$CLAUDE_CONFIG_DIR/projects` when the variable is set, otherwise
+    /// `~/.claude/projects`.
+    #[must_use]
+    pub fn new() -> Self {
+        let config_dir = std::env::var_os("CLAUDE_CONFIG_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(".claude")
+            });
+        Self::with_root(config_dir.join("projects"))
+    }
+
+    /// Store rooted at a custom projects directory
+    #[must_use]
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The projects directory
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding the transcripts of sessions run in `cwd`
+    #[must_use]
+    pub fn project_dir(&self, cwd: impl AsRef<Path>) -> PathBuf {
+        self.root.join(encode_project_path(cwd.as_ref()))
+    }
+
+    /// All project directories, sorted by name
+    ///
+    /// # Errors
+    /// Returns an I/O error if the projects directory exists but cannot be read
+    pub fn list_projects(&self) -> Result<Vec<PathBuf>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut projects = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                projects.push(path);
+            }
+        }
+        projects.sort();
+        Ok(projects)
+    }
+
+    /// Sessions run in `cwd`, most recently modified first
+    ///
+    /// # Errors
+    /// Returns an I/O error if the project directory exists but cannot be read
+    pub fn list_sessions(&self, cwd: impl AsRef<Path>) -> Result<Vec<SessionFile>> {
+        Self::sessions_in(&self.project_dir(cwd))
+    }
+
+    /// Sessions in a project directory, most recently modified first
+    ///
+    /// # Errors
+    /// Returns an I/O error if the directory exists but cannot be read
+    pub fn sessions_in(project_dir: &Path) -> Result<Vec<SessionFile>> {
+        if !project_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut sessions = Vec::new();
+        for entry in std::fs::read_dir(project_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            sessions.push(SessionFile {
+                session_id: SessionId::new(stem),
+                path: path.clone(),
+                modified: metadata.modified().ok(),
+                size: metadata.len(),
+            });
+        }
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.modified));
+        Ok(sessions)
+    }
+
+    /// Locate the transcript of a session in any project
+    ///
+    /// # Errors
+    /// Returns an I/O error if a project directory cannot be read
+    pub fn find_session(&self, session_id: &SessionId) -> Result<Option<PathBuf>> {
+        if !is_valid_session_id(session_id.as_str()) {
+            return Ok(None);
+        }
+        let file_name = format!("{session_id}.jsonl");
+        for project in self.list_projects()? {
+            let path = project.join(&file_name);
+            if path.is_file() {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Locate and parse the transcript of a session
+    ///
+    /// # Errors
+    /// Returns an I/O error of kind `NotFound` if no transcript exists for
+    /// the session, or any other I/O error reading it
+    pub fn load(&self, session_id: &SessionId) -> Result<Transcript> {
+        let path = self.find_session(session_id)?.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No transcript found for session {session_id}"),
+            )
+        })?;
+        Transcript::load(path)
+    }
+}
+
+/// Metadata the CLI records alongside each transcript message
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntryMetadata {
+    #[serde(default)]
+    uuid: Option<String>,
+    #[serde(default)]
+    parent_uuid: Option<String>,
+    #[serde(default)]
+    session_id: Option<String>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    is_sidechain: bool,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    git_branch: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// One line of a transcript
+#[derive(Debug, Clone)]
+pub struct TranscriptEntry {
+    /// Entry UUID (absent for summary and bookkeeping lines)
+    pub uuid: Option<String>,
+    /// UUID of the entry this one continues from (`None` for a root)
+    pub parent_uuid: Option<String>,
+    /// Session the entry was written by (differs from the file's session
+    /// for entries carried over by a resume)
+    pub session_id: Option<SessionId>,
+    /// ISO 8601 timestamp
+    pub timestamp: Option<String>,
+    /// Whether the entry belongs to a subagent (`Task`) conversation
+    pub is_sidechain: bool,
+    /// Working directory at the time of the entry
+    pub cwd: Option<String>,
+    /// Git branch at the time of the entry
+    pub git_branch: Option<String>,
+    /// CLI version that wrote the entry
+    pub version: Option<String>,
+    /// The entry as a typed message; summaries and other bookkeeping lines
+    /// are `Message::Unknown`
+    pub message: Message,
+}
+
+impl TranscriptEntry {
+    /// Parse one transcript line
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::MessageParse` if the line is not a JSON object
+    pub fn parse(value: serde_json::Value) -> Result<Self> {
+        let metadata: EntryMetadata = serde_json::from_value(value.clone()).map_err(|e| {
+            ClaudeError::message_parse(
+                format!("Invalid transcript entry: {e}"),
+                Some(value.clone()),
+            )
+        })?;
+        Ok(Self {
+            uuid: metadata.uuid,
+            parent_uuid: metadata.parent_uuid,
+            session_id: metadata.session_id.map(SessionId::new),
+            timestamp: metadata.timestamp,
+            is_sidechain: metadata.is_sidechain,
+            cwd: metadata.cwd,
+            git_branch: metadata.git_branch,
+            version: metadata.version,
+            message: parse_message(value)?,
+        })
+    }
+
+    /// Entry type as written by the CLI (`user`, `assistant`, `summary`, ...)
+    #[must_use]
+    pub fn entry_type(&self) -> Option<&str> {
+        match &self.message {
+            Message::User { .. } => Some("user"),
+            Message::Assistant { .. } => Some("assistant"),
+            Message::System { .. } => Some("system"),
+            Message::Result { .. } => Some("result"),
+            Message::StreamEvent { .. } => Some("stream_event"),
+            Message::Unknown { raw } => raw.get("type").and_then(|t| t.as_str()),
+        }
+    }
+}
+
+/// A parsed session transcript
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    /// Session ID (from the file name)
+    pub session_id: SessionId,
+    /// Path the transcript was read from
+    pub path: PathBuf,
+    /// Entries in file order
+    pub entries: Vec<TranscriptEntry>,
+    /// Number of lines that could not be parsed (e.g. a line still being
+    /// written)
+    pub skipped_lines: usize,
+}
+
+impl Transcript {
+    /// Read and parse a transcript file
+    ///
+    /// Blank lines are ignored, and lines that are not valid JSON objects are
+    /// skipped and counted in [`skipped_lines`](Self::skipped_lines).
+    ///
+    /// # Errors
+    /// Returns an I/O error if the file cannot be read
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let session_id = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(SessionId::new)
+            .unwrap_or_default();
+
+        let mut entries = Vec::new();
+        let mut skipped_lines = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line).map_err(ClaudeError::from) {
+                Ok(value) => match TranscriptEntry::parse(value) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => {
+                        tracing::warn!(path = %path.display(), error = %e, "Skipping transcript line");
+                        skipped_lines += 1;
+                    }
+                },
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping transcript line");
+                    skipped_lines += 1;
+                }
+            }
+        }
+
+        Ok(Self {
+            session_id,
+            path: path.to_path_buf(),
+            entries,
+            skipped_lines,
+        })
+    }
+
+    /// All entry messages in file order
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.entries.iter().map(|entry| &entry.message)
+    }
+
+    /// Title of the session, from the CLI's most recent `summary` entry
+    #[must_use]
+    pub fn summary(&self) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find_map(|entry| match &entry.message {
+                Message::Unknown { raw }
+                    if raw.get("type").and_then(|t| t.as_str()) == Some("summary") =>
+                {
+                    raw.get("summary").and_then(|s| s.as_str())
+                }
+                _ => None,
+            })
+    }
+
+    /// Entry with the given UUID
+    #[must_use]
+    pub fn entry(&self, uuid: &str) -> Option<&TranscriptEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.uuid.as_deref() == Some(uuid))
+    }
+
+    /// Entries that continue directly from `uuid`
+    #[must_use]
+    pub fn children(&self, uuid: &str) -> Vec<&TranscriptEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.parent_uuid.as_deref() == Some(uuid))
+            .collect()
+    }
+
+    /// Entries with a UUID but no parent (conversation starts)
+    #[must_use]
+    pub fn roots(&self) -> Vec<&TranscriptEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.uuid.is_some() && entry.parent_uuid.is_none())
+            .collect()
+    }
+
+    /// Entries no other entry continues from (ends of branches), in file order
+    #[must_use]
+    pub fn leaves(&self) -> Vec<&TranscriptEntry> {
+        let parents: std::collections::HashSet<&str> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.parent_uuid.as_deref())
+            .collect();
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry
+                    .uuid
+                    .as_deref()
+                    .is_some_and(|uuid| !parents.contains(uuid))
+            })
+            .collect()
+    }
+
+    /// The conversation ending at `uuid`, from its root to the entry itself
+    ///
+    /// Returns an empty list if `uuid` is not in the transcript.
+    #[must_use]
+    pub fn thread(&self, uuid: &str) -> Vec<&TranscriptEntry> {
+        let by_uuid: HashMap<&str, &TranscriptEntry> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.uuid.as_deref().map(|u| (u, entry)))
+            .collect();
+
+        let mut thread = Vec::new();
+        let mut current = by_uuid.get(uuid).copied();
+        while let Some(entry) = current {
+            // Guard against cycles in a corrupted file
+            if thread.len() > by_uuid.len() {
+                break;
+            }
+            thread.push(entry);
+            current = entry
+                .parent_uuid
+                .as_deref()
+                .and_then(|parent| by_uuid.get(parent).copied());
+        }
+        thread.reverse();
+        thread
+    }
+
+    /// The conversation ending at the last entry of the file (the branch the
+    /// CLI resumes)
+    #[must_use]
+    pub fn main_thread(&self) -> Vec<&TranscriptEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.uuid.is_some() && !entry.is_sidechain)
+            .and_then(|entry| entry.uuid.as_deref())
+            .map(|uuid| self.thread(uuid))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_transcript(dir: &Path, session_id: &str, lines: &[serde_json::Value]) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(format!("{session_id}.jsonl"));
+        let content: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        std::fs::write(&path, content.join("\n") + "\n").unwrap();
+        path
+    }
+
+    fn sample_lines() -> Vec<serde_json::Value> {
+        vec![
+            json!({"type": "summary", "summary": "Fix the build", "leafUuid": "a2"}),
+            json!({
+                "type": "user", "uuid": "u1", "parentUuid": null, "sessionId": "s1",
+                "timestamp": "2025-12-01T10:00:00.000Z", "cwd": "/work", "isSidechain": false,
+                "message": {"role": "user", "content": "Why does the build fail?"}
+            }),
+            json!({
+                "type": "assistant", "uuid": "a1", "parentUuid": "u1", "sessionId": "s1",
+                "timestamp": "2025-12-01T10:00:05.000Z",
+                "message": {"id": "msg_1", "model": "claude-sonnet-4-5", "role": "assistant",
+                            "content": [{"type": "text", "text": "A missing import."}],
+                            "usage": {"input_tokens": 10, "output_tokens": 5}}
+            }),
+            // A rewind branched the conversation at u1
+            json!({
+                "type": "assistant", "uuid": "a2", "parentUuid": "u1", "sessionId": "s1",
+                "timestamp": "2025-12-01T10:01:00.000Z",
+                "message": {"model": "claude-sonnet-4-5", "content": [{"type": "text", "text": "Let me check."}]}
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_encode_project_path() {
+        assert_eq!(
+            encode_project_path(Path::new("/home/me/my.app")),
+            "-home-me-my-app"
+        );
+    }
+
+    #[test]
+    fn test_load_transcript_entries_and_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_transcript(dir.path(), "s1", &sample_lines());
+        std::fs::write(
+            &path,
+            std::fs::read_to_string(&path).unwrap() + "{\"type\": \"user\", \"uu",
+        )
+        .unwrap();
+
+        let transcript = Transcript::load(&path).unwrap();
+        assert_eq!(transcript.session_id.as_str(), "s1");
+        assert_eq!(transcript.entries.len(), 4);
+        assert_eq!(transcript.skipped_lines, 1);
+        assert_eq!(transcript.summary(), Some("Fix the build"));
+
+        let first = transcript.entry("u1").unwrap();
+        assert!(matches!(first.message, Message::User { .. }));
+        assert_eq!(first.timestamp.as_deref(), Some("2025-12-01T10:00:00.000Z"));
+        assert_eq!(first.cwd.as_deref(), Some("/work"));
+        assert_eq!(transcript.entries[0].entry_type(), Some("summary"));
+
+        assert_eq!(transcript.roots().len(), 1);
+        assert_eq!(transcript.children("u1").len(), 2);
+        let leaves: Vec<_> = transcript
+            .leaves()
+            .iter()
+            .filter_map(|e| e.uuid.as_deref())
+            .collect();
+        assert_eq!(leaves, vec!["a1", "a2"]);
+
+        let main: Vec<_> = transcript
+            .main_thread()
+            .iter()
+            .filter_map(|e| e.uuid.as_deref())
+            .collect();
+        assert_eq!(main, vec!["u1", "a2"]);
+        assert!(transcript.thread("missing").is_empty());
+    }
+
+    #[test]
+    fn test_store_lists_and_finds_sessions() {
+        let root = tempfile::tempdir().unwrap();
+        let store = TranscriptStore::with_root(root.path());
+        let project = store.project_dir("/work/app");
+        write_transcript(&project, "s1", &sample_lines());
+        write_transcript(&store.project_dir("/other"), "s2", &sample_lines()[..2]);
+        std::fs::write(project.join("notes.txt"), "not a transcript").unwrap();
+
+        let sessions = store.list_sessions("/work/app").unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id.as_str(), "s1");
+        assert!(store.list_sessions("/nowhere").unwrap().is_empty());
+        assert_eq!(store.list_projects().unwrap().len(), 2);
+
+        let found = store.find_session(&SessionId::new("s2")).unwrap().unwrap();
+        assert!(found.starts_with(store.project_dir("/other")));
+        assert!(
+            store
+                .find_session(&SessionId::new("../s1"))
+                .unwrap()
+                .is_none()
+        );
+
+        let transcript = store.load(&SessionId::new("s1")).unwrap();
+        assert_eq!(transcript.entries.len(), 4);
+        assert!(matches!(
+            store.load(&SessionId::new("missing")),
+            Err(ClaudeError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+}
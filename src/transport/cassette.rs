@@ -0,0 +1,715 @@
+//! Record and replay CLI sessions as JSONL cassettes
+//!
+//! A cassette is a JSONL file with one [`CassetteEntry`] per line: every line
+//! the SDK wrote to the CLI's stdin, every JSON value the CLI printed on
+//! stdout, and any error that ended the stream, each stamped with its offset
+//! from the start of the recording.
+//!
+//! - [`RecordingTransport`] wraps another [`Transport`] (typically a
+//!   [`SubprocessTransport`](super::SubprocessTransport)) and writes the cassette
+//!   while the session runs.
+//! - [`ReplayTransport`] plays a cassette back at recorded or accelerated
+//!   speed, so a real agent run can serve as a regression fixture.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use anthropic_agent_sdk::transport::cassette::{RecordingTransport, ReplaySpeed, ReplayTransport};
+//! use anthropic_agent_sdk::{ClaudeAgentOptions, ClaudeSDKClient, PromptInput, SubprocessTransport};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Record a real run
+//! let options = ClaudeAgentOptions::default();
+//! let subprocess = SubprocessTransport::new(PromptInput::Stream, options.clone(), None)?;
+//! let recorder = RecordingTransport::new(subprocess, "fixtures/run.jsonl")?;
+//! let mut client = ClaudeSDKClient::with_transport(recorder, options.clone()).await?;
+//! client.send_message("List the files in this directory").await?;
+//! // ... consume messages, then close
+//! client.close().await?;
+//!
+//! // Later: replay it without the CLI, 10x faster than recorded
+//! let replay = ReplayTransport::from_file("fixtures/run.jsonl")?.speed(ReplaySpeed::Accelerated(10.0));
+//! let mut client = ClaudeSDKClient::with_transport(replay, options).await?;
+//! client.send_message("List the files in this directory").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+
+use super::Transport;
+use crate::error::{ClaudeError, Result};
+
+/// How long replay waits for the SDK to send the user message a recorded
+/// response depends on
+const REPLAY_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Direction of a cassette entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CassetteDirection {
+    /// Line written by the SDK to the CLI's stdin
+    Stdin,
+    /// JSON value read from the CLI's stdout
+    Stdout,
+    /// Error that ended the stdout stream
+    Error,
+}
+
+/// A single recorded line of a cassette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteEntry {
+    /// Milliseconds since the recording started
+    pub offset_ms: u64,
+    /// Wall-clock time of the entry (milliseconds since the Unix epoch)
+    pub timestamp_ms: u64,
+    /// Direction of the entry
+    pub direction: CassetteDirection,
+    /// The line as JSON (stdin lines that are not JSON are stored as strings)
+    pub data: Value,
+}
+
+impl CassetteEntry {
+    /// Message `type` of the entry's data, if any
+    #[must_use]
+    pub fn message_type(&self) -> Option<&str> {
+        self.data.get("type").and_then(Value::as_str)
+    }
+
+    /// Load all entries from a cassette file
+    ///
+    /// # Errors
+    /// Returns error if the file cannot be read or a line is not a valid entry
+    pub fn load(path: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let file = std::fs::File::open(path.as_ref())?;
+        let mut entries = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(&line)?);
+        }
+        Ok(entries)
+    }
+}
+
+/// Convert a recorded error entry back into a `ClaudeError`
+fn error_from_entry(data: &Value) -> ClaudeError {
+    let message = data
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("Recorded transport error")
+        .to_string();
+    match data.get("exit_code").and_then(Value::as_i64) {
+        Some(code) => ClaudeError::process(
+            message,
+            i32::try_from(code).unwrap_or(-1),
+            data.get("stderr").and_then(Value::as_str).map(String::from),
+        ),
+        None => ClaudeError::transport(message),
+    }
+}
+
+/// Convert an error into a cassette entry payload
+fn error_to_data(error: &ClaudeError) -> Value {
+    match error {
+        ClaudeError::Process {
+            message,
+            exit_code,
+            stderr,
+        } => serde_json::json!({
+            "message": message,
+            "exit_code": exit_code,
+            "stderr": stderr,
+        }),
+        other => serde_json::json!({ "message": other.to_string() }),
+    }
+}
+
+// ============================================================================
+// Recording
+// ============================================================================
+
+/// Cassette file writer shared between the write path and the reader task
+struct CassetteWriter {
+    file: std::sync::Mutex<BufWriter<std::fs::File>>,
+    started: Instant,
+}
+
+impl CassetteWriter {
+    fn record(&self, direction: CassetteDirection, data: Value) {
+        let entry = CassetteEntry {
+            offset_ms: u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+                .unwrap_or_default(),
+            direction,
+            data,
+        };
+
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        if let Ok(mut file) = self.file.lock() {
+            // Flush per line so a crashed run still leaves a usable cassette
+            if writeln!(file, "{line}")
+                .and_then(|()| file.flush())
+                .is_err()
+            {
+                tracing::warn!("Failed to write cassette entry");
+            }
+        }
+    }
+}
+
+/// Transport wrapper that records a session to a cassette file
+///
+/// Every stdin line and stdout value passing through the wrapped transport is
+/// appended to the cassette with its timestamp; the wrapped transport is
+/// otherwise used unchanged.
+pub struct RecordingTransport<T: Transport> {
+    inner: T,
+    writer: Arc<CassetteWriter>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Wrap a transport, recording to the given file (created or truncated)
+    ///
+    /// # Errors
+    /// Returns error if the cassette file cannot be created
+    pub fn new(inner: T, path: impl AsRef<Path>) -> Result<Self> {
+        let file = std::fs::File::create(path.as_ref())?;
+        Ok(Self {
+            inner,
+            writer: Arc::new(CassetteWriter {
+                file: std::sync::Mutex::new(BufWriter::new(file)),
+                started: Instant::now(),
+            }),
+        })
+    }
+
+    /// Get a reference to the wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<T: Transport + 'static> Transport for RecordingTransport<T> {
+    async fn connect(&mut self) -> Result<()> {
+        self.inner.connect().await
+    }
+
+    async fn write(&mut self, data: &str) -> Result<()> {
+        // Record before forwarding: the reply can reach the reader task
+        // before `inner.write` returns
+        for line in data.lines().filter(|l| !l.trim().is_empty()) {
+            let value = serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.into()));
+            self.writer.record(CassetteDirection::Stdin, value);
+        }
+        self.inner.write(data).await
+    }
+
+    async fn end_input(&mut self) -> Result<()> {
+        self.inner.end_input().await
+    }
+
+    fn read_messages(&mut self) -> mpsc::UnboundedReceiver<Result<Value>> {
+        let mut inner_rx = self.inner.read_messages();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let writer = self.writer.clone();
+
+        tokio::spawn(async move {
+            while let Some(item) = inner_rx.recv().await {
+                match &item {
+                    Ok(value) => writer.record(CassetteDirection::Stdout, value.clone()),
+                    Err(e) => writer.record(CassetteDirection::Error, error_to_data(e)),
+                }
+                if tx.send(item).is_err() {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+// ============================================================================
+// Replay
+// ============================================================================
+
+/// Playback speed for [`ReplayTransport`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// Reproduce the recorded gaps between stdout lines
+    #[default]
+    Recorded,
+    /// Divide the recorded gaps by the given factor (e.g. `10.0` = 10x faster)
+    Accelerated(f64),
+    /// Emit everything as fast as possible
+    Instant,
+}
+
+impl ReplaySpeed {
+    fn scale(self, gap_ms: u64) -> Option<Duration> {
+        match self {
+            Self::Recorded => Some(Duration::from_millis(gap_ms)),
+            Self::Accelerated(factor) if factor > 0.0 => {
+                #[allow(clippy::cast_precision_loss)]
+                let scaled = gap_ms as f64 / factor;
+                Some(Duration::from_secs_f64(scaled / 1000.0))
+            }
+            Self::Accelerated(_) | Self::Instant => None,
+        }
+    }
+}
+
+/// A stdout entry prepared for playback
+struct ReplayItem {
+    entry: CassetteEntry,
+    /// Number of SDK inputs (user messages and answers to CLI control requests)
+    /// that must have been written before this entry
+    inputs_before: usize,
+    /// Offset to measure the delay from
+    anchor_ms: u64,
+}
+
+/// Lines written by the SDK during a replay
+#[derive(Clone, Default)]
+pub struct ReplayWrites {
+    lines: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+impl ReplayWrites {
+    /// All lines written by the SDK, in order
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().map(|l| l.clone()).unwrap_or_default()
+    }
+
+    /// All written lines that parse as JSON
+    #[must_use]
+    pub fn json(&self) -> Vec<Value> {
+        self.lines()
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+}
+
+/// Transport that plays back a recorded cassette
+///
+/// Stdout entries are emitted in order with the recorded timing (scaled by
+/// [`ReplaySpeed`]). Entries that were recorded after a user message, or after
+/// the SDK answered a CLI control request such as `hook_callback`, are held back
+/// until the SDK has written the same number of those inputs, so the client sees
+/// responses in the order it would with the real CLI.
+///
+/// Control requests written by the SDK are answered with the recorded
+/// `control_response` for the same subtype (re-keyed to the new request id),
+/// or with an empty success if none was recorded. Recorded control responses
+/// are never replayed on their own.
+pub struct ReplayTransport {
+    items: Option<Vec<ReplayItem>>,
+    control_responses: Arc<std::sync::Mutex<HashMap<String, VecDeque<Value>>>>,
+    speed: ReplaySpeed,
+    writes: ReplayWrites,
+    input_count: watch::Sender<usize>,
+    stream_tx: Arc<std::sync::Mutex<Option<mpsc::UnboundedSender<Result<Value>>>>>,
+    connected: bool,
+    shutdown: CancellationToken,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl ReplayTransport {
+    /// Load a cassette file for replay
+    ///
+    /// # Errors
+    /// Returns error if the cassette cannot be read or parsed
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::from_entries(CassetteEntry::load(path)?))
+    }
+
+    /// Build a replay transport from cassette entries
+    #[must_use]
+    pub fn from_entries(entries: Vec<CassetteEntry>) -> Self {
+        let mut items = Vec::new();
+        let mut control_responses: HashMap<String, VecDeque<Value>> = HashMap::new();
+        let mut request_subtypes: HashMap<String, String> = HashMap::new();
+        let mut inputs = 0;
+        let mut anchor_ms = 0;
+
+        for entry in entries {
+            match entry.direction {
+                CassetteDirection::Stdin => match entry.message_type() {
+                    Some("user" | "control_response") => {
+                        inputs += 1;
+                        anchor_ms = anchor_ms.max(entry.offset_ms);
+                    }
+                    Some("control_request") => {
+                        let id = entry.data.get("request_id").and_then(Value::as_str);
+                        let subtype = entry
+                            .data
+                            .get("request")
+                            .and_then(|r| r.get("subtype"))
+                            .and_then(Value::as_str);
+                        if let (Some(id), Some(subtype)) = (id, subtype) {
+                            request_subtypes.insert(id.to_string(), subtype.to_string());
+                        }
+                    }
+                    _ => {}
+                },
+                CassetteDirection::Stdout if entry.message_type() == Some("control_response") => {
+                    let response = entry.data.get("response");
+                    let subtype = response
+                        .and_then(|r| r.get("request_id"))
+                        .and_then(Value::as_str)
+                        .and_then(|id| request_subtypes.get(id));
+                    if let (Some(subtype), Some(response)) = (subtype, response) {
+                        control_responses
+                            .entry(subtype.clone())
+                            .or_default()
+                            .push_back(response.clone());
+                    }
+                }
+                CassetteDirection::Stdout | CassetteDirection::Error => {
+                    let offset = entry.offset_ms;
+                    items.push(ReplayItem {
+                        entry,
+                        inputs_before: inputs,
+                        anchor_ms,
+                    });
+                    anchor_ms = offset;
+                }
+            }
+        }
+
+        let (input_count, _) = watch::channel(0);
+        Self {
+            items: Some(items),
+            control_responses: Arc::new(std::sync::Mutex::new(control_responses)),
+            speed: ReplaySpeed::default(),
+            writes: ReplayWrites::default(),
+            input_count,
+            stream_tx: Arc::new(std::sync::Mutex::new(None)),
+            connected: false,
+            shutdown: CancellationToken::new(),
+            task: None,
+        }
+    }
+
+    /// Set the playback speed
+    #[must_use]
+    pub fn speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Get a handle to the lines the SDK writes during replay
+    #[must_use]
+    pub fn writes(&self) -> ReplayWrites {
+        self.writes.clone()
+    }
+
+    fn send(&self, item: Result<Value>) {
+        if let Ok(guard) = self.stream_tx.lock() {
+            if let Some(ref tx) = *guard {
+                let _ = tx.send(item);
+            }
+        }
+    }
+
+    /// Answer a control request written by the SDK from the recorded responses
+    fn reply_to_control_request(&self, value: &Value) {
+        let Some(request_id) = value.get("request_id").and_then(Value::as_str) else {
+            return;
+        };
+        let subtype = value
+            .get("request")
+            .and_then(|r| r.get("subtype"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        let mut response = self
+            .control_responses
+            .lock()
+            .ok()
+            .and_then(|mut responses| responses.get_mut(subtype).and_then(VecDeque::pop_front))
+            .unwrap_or_else(|| serde_json::json!({"subtype": "success"}));
+        response["request_id"] = Value::String(request_id.to_string());
+
+        self.send(Ok(serde_json::json!({
+            "type": "control_response",
+            "response": response
+        })));
+    }
+
+    async fn play(
+        items: Vec<ReplayItem>,
+        speed: ReplaySpeed,
+        mut input_rx: watch::Receiver<usize>,
+        stream_tx: Arc<std::sync::Mutex<Option<mpsc::UnboundedSender<Result<Value>>>>>,
+    ) {
+        let send = |item: Result<Value>| {
+            if let Ok(guard) = stream_tx.lock() {
+                if let Some(ref tx) = *guard {
+                    let _ = tx.send(item);
+                }
+            }
+        };
+
+        for item in items {
+            // Hold back responses until the SDK has sent the input they follow
+            let wait = input_rx.wait_for(|count| *count >= item.inputs_before);
+            match tokio::time::timeout(REPLAY_WAIT_TIMEOUT, wait).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(_)) => return,
+                Err(_) => {
+                    send(Err(ClaudeError::transport(format!(
+                        "Replay timed out waiting for SDK input #{}",
+                        item.inputs_before
+                    ))));
+                    return;
+                }
+            }
+
+            let gap = item.entry.offset_ms.saturating_sub(item.anchor_ms);
+            if let Some(delay) = speed.scale(gap) {
+                tokio::time::sleep(delay).await;
+            }
+
+            match item.entry.direction {
+                CassetteDirection::Error => {
+                    send(Err(error_from_entry(&item.entry.data)));
+                    return;
+                }
+                _ => send(Ok(item.entry.data)),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    async fn connect(&mut self) -> Result<()> {
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &str) -> Result<()> {
+        if !self.connected {
+            return Err(ClaudeError::transport("Replay transport is not connected"));
+        }
+
+        for line in data.lines().filter(|l| !l.trim().is_empty()) {
+            if let Ok(mut lines) = self.writes.lines.lock() {
+                lines.push(line.to_string());
+            }
+            let Ok(value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+            match value.get("type").and_then(Value::as_str) {
+                Some("user" | "control_response") => {
+                    self.input_count.send_modify(|count| *count += 1);
+                }
+                Some("control_request") => self.reply_to_control_request(&value),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn end_input(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_messages(&mut self) -> mpsc::UnboundedReceiver<Result<Value>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let Some(items) = self.items.take() else {
+            let _ = tx.send(Err(ClaudeError::connection(
+                "Replay transport messages already taken",
+            )));
+            return rx;
+        };
+
+        if let Ok(mut guard) = self.stream_tx.lock() {
+            *guard = Some(tx);
+        }
+
+        let speed = self.speed;
+        let input_rx = self.input_count.subscribe();
+        let stream_tx = self.stream_tx.clone();
+        let shutdown = self.shutdown.clone();
+        self.task = Some(tokio::spawn(async move {
+            tokio::select! {
+                () = Self::play(items, speed, input_rx, stream_tx.clone()) => {}
+                () = shutdown.cancelled() => {}
+            }
+            // The recorded CLI has exited: close the stream
+            if let Ok(mut guard) = stream_tx.lock() {
+                *guard = None;
+            }
+        }));
+
+        rx
+    }
+
+    fn is_ready(&self) -> bool {
+        self.connected && !self.shutdown.is_cancelled()
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.connected = false;
+        self.shutdown.cancel();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(offset_ms: u64, direction: CassetteDirection, data: Value) -> CassetteEntry {
+        CassetteEntry {
+            offset_ms,
+            timestamp_ms: 1_700_000_000_000 + offset_ms,
+            direction,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_entry_serialization() {
+        let e = entry(
+            12,
+            CassetteDirection::Stdout,
+            json!({"type": "system", "subtype": "init"}),
+        );
+        let line = serde_json::to_string(&e).unwrap();
+        assert!(line.contains("\"direction\":\"stdout\""));
+        assert!(line.contains("\"offset_ms\":12"));
+        let back: CassetteEntry = serde_json::from_str(&line).unwrap();
+        assert_eq!(back, e);
+        assert_eq!(back.message_type(), Some("system"));
+    }
+
+    #[test]
+    fn test_replay_speed_scaling() {
+        assert_eq!(
+            ReplaySpeed::Recorded.scale(100),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            ReplaySpeed::Accelerated(10.0).scale(100),
+            Some(Duration::from_millis(10))
+        );
+        assert_eq!(ReplaySpeed::Instant.scale(100), None);
+    }
+
+    #[tokio::test]
+    async fn test_replay_waits_for_user_message_and_rekeys_acks() {
+        let entries = vec![
+            entry(
+                0,
+                CassetteDirection::Stdout,
+                json!({"type": "system", "subtype": "init"}),
+            ),
+            entry(
+                5,
+                CassetteDirection::Stdin,
+                json!({"type": "user", "message": {"role": "user", "content": "hi"}}),
+            ),
+            entry(
+                6,
+                CassetteDirection::Stdin,
+                json!({"type": "control_request", "request_id": "old_1", "request": {"subtype": "interrupt"}}),
+            ),
+            entry(
+                7,
+                CassetteDirection::Stdout,
+                json!({"type": "control_response", "response": {"subtype": "success", "request_id": "old_1"}}),
+            ),
+            entry(
+                20,
+                CassetteDirection::Stdout,
+                json!({"type": "result", "subtype": "success"}),
+            ),
+        ];
+
+        let mut replay = ReplayTransport::from_entries(entries).speed(ReplaySpeed::Instant);
+        let writes = replay.writes();
+        replay.connect().await.unwrap();
+        let mut rx = replay.read_messages();
+
+        assert_eq!(rx.recv().await.unwrap().unwrap()["subtype"], "init");
+        // The result was recorded after a user message, so it is held back
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), rx.recv())
+                .await
+                .is_err()
+        );
+
+        replay
+            .write("{\"type\":\"control_request\",\"request_id\":\"new_1\",\"request\":{\"subtype\":\"interrupt\"}}\n")
+            .await
+            .unwrap();
+        let ack = rx.recv().await.unwrap().unwrap();
+        assert_eq!(ack["response"]["request_id"], "new_1");
+
+        replay
+            .write("{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n")
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap().unwrap()["type"], "result");
+        assert!(rx.recv().await.is_none());
+        assert_eq!(writes.lines().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_error() {
+        let entries = vec![entry(
+            1,
+            CassetteDirection::Error,
+            json!({"message": "Command failed", "exit_code": 2, "stderr": "bad flag"}),
+        )];
+        let mut replay = ReplayTransport::from_entries(entries).speed(ReplaySpeed::Instant);
+        replay.connect().await.unwrap();
+        let mut rx = replay.read_messages();
+
+        match rx.recv().await.unwrap() {
+            Err(ClaudeError::Process {
+                exit_code, stderr, ..
+            }) => {
+                assert_eq!(exit_code, 2);
+                assert_eq!(stderr.as_deref(), Some("bad flag"));
+            }
+            other => panic!("expected process error, got {other:?}"),
+        }
+    }
+}
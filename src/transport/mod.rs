@@ -3,6 +3,7 @@
 //! This module provides the transport abstraction and implementations for
 //! communicating with the Claude Code CLI process.
 
+pub mod cassette;
 pub mod subprocess;
 
 use async_trait::async_trait;
@@ -114,6 +115,7 @@ fn version_lt(v1: &str, v2: &str) -> bool {
     false
 }
 
+pub use cassette::{RecordingTransport, ReplaySpeed, ReplayTransport};
 pub use subprocess::{PromptInput, SubprocessTransport};
 
 #[cfg(test)]
@@ -1,24 +1,28 @@
 //! Subprocess transport implementation using Claude Code CLI
 
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::{Child, ChildStdin, ChildStdout, Command};
-use tokio::sync::{Mutex, mpsc};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::{Mutex, mpsc, watch};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 
 use crate::error::{ClaudeError, Result};
-use crate::types::{ClaudeAgentOptions, SystemPrompt};
+use crate::types::{ClaudeAgentOptions, SystemPrompt, ToolName, ToolsConfig};
 use crate::utils::truncate_for_display;
 use crate::{Transport, VERSION};
 
 const DEFAULT_MAX_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+const DEFAULT_STDERR_CAPTURE_LINES: usize = 20;
+
+/// How long to wait for stderr to drain after the CLI exits with an error
+const STDERR_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
 
 // Dangerous environment variables that should not be passed to subprocess
 const DANGEROUS_ENV_VARS: &[&str] = &[
@@ -70,6 +74,10 @@ pub struct SubprocessTransport {
     max_buffer_size: usize,
     reader_task: Option<JoinHandle<()>>,
     stderr_task: Option<JoinHandle<()>>,
+    /// Last stderr lines, reported in `ClaudeError::Process` on failure
+    stderr_tail: Arc<std::sync::Mutex<VecDeque<String>>>,
+    /// Becomes true once stderr reaches EOF
+    stderr_done: watch::Receiver<bool>,
     /// Cancellation token for aborting operations (like `AbortController` in JS)
     cancellation_token: CancellationToken,
 }
@@ -137,6 +145,8 @@ impl SubprocessTransport {
             max_buffer_size,
             reader_task: None,
             stderr_task: None,
+            stderr_tail: Arc::default(),
+            stderr_done: watch::channel(true).1,
             cancellation_token: token,
         })
     }
@@ -212,6 +222,11 @@ impl SubprocessTransport {
             cmd.arg("--allowedTools").arg(tools.join(","));
         }
 
+        // Base tool set
+        if let Some(tools) = self.tools_arg()? {
+            cmd.arg("--tools").arg(tools);
+        }
+
         // Max turns
         if let Some(max_turns) = self.options.max_turns {
             cmd.arg("--max-turns").arg(max_turns.to_string());
@@ -233,9 +248,29 @@ impl SubprocessTransport {
             cmd.arg("--model").arg(model);
         }
 
-        // Permission prompt tool
-        if let Some(ref tool) = self.options.permission_prompt_tool_name {
-            cmd.arg("--permission-prompt-tool").arg(tool);
+        // Permission prompt tool. With can_use_tool the CLI asks the SDK over
+        // the control protocol, which requires streaming mode
+        match (
+            &self.options.permission_prompt_tool_name,
+            self.options.can_use_tool.is_some(),
+        ) {
+            (Some(_), true) => {
+                return Err(ClaudeError::invalid_config(
+                    "can_use_tool cannot be combined with permission_prompt_tool_name",
+                ));
+            }
+            (Some(tool), false) => {
+                cmd.arg("--permission-prompt-tool").arg(tool);
+            }
+            (None, true) if matches!(self.prompt, PromptInput::Stream) => {
+                cmd.arg("--permission-prompt-tool").arg("stdio");
+            }
+            (None, true) => {
+                tracing::warn!(
+                    "can_use_tool requires streaming mode and is ignored for a string prompt"
+                );
+            }
+            (None, false) => {}
         }
 
         // Permission mode
@@ -274,18 +309,13 @@ impl SubprocessTransport {
             cmd.arg("--add-dir").arg(dir);
         }
 
-        // MCP servers
+        // MCP servers. In-process SDK servers are declared as `{"type": "sdk"}`
+        // entries; the client answers their mcp_message control requests
+        let mut config_map = HashMap::new();
         match &self.options.mcp_servers {
             crate::types::McpServers::Dict(servers) => {
-                if !servers.is_empty() {
-                    let mut config_map = HashMap::new();
-                    for (name, config) in servers {
-                        config_map.insert(name.clone(), Self::serialize_mcp_config(config));
-                    }
-                    let config_json = serde_json::json!({
-                        "mcpServers": config_map
-                    });
-                    cmd.arg("--mcp-config").arg(config_json.to_string());
+                for (name, config) in servers {
+                    config_map.insert(name.clone(), Self::serialize_mcp_config(config));
                 }
             }
             crate::types::McpServers::Path(path) => {
@@ -293,6 +323,18 @@ impl SubprocessTransport {
             }
             crate::types::McpServers::None => {}
         }
+        for name in self.options.sdk_mcp_servers.keys() {
+            config_map.insert(
+                name.clone(),
+                serde_json::json!({"type": "sdk", "name": name}),
+            );
+        }
+        if !config_map.is_empty() {
+            let config_json = serde_json::json!({
+                "mcpServers": config_map
+            });
+            cmd.arg("--mcp-config").arg(config_json.to_string());
+        }
 
         // Include partial messages
         if self.options.include_partial_messages {
@@ -506,6 +548,132 @@ impl SubprocessTransport {
         Ok(cmd)
     }
 
+    /// Build the `--tools` value from `ClaudeAgentOptions::tools`
+    ///
+    /// A list restricts the built-in tool set (an empty list disables all
+    /// built-in tools); the `claude_code` preset keeps the default set. Names
+    /// must be valid, and the list must not contradict `allowed_tools` or
+    /// `disallowed_tools`. MCP tools are not part of the built-in set and are
+    /// never checked against it.
+    fn tools_arg(&self) -> Result<Option<String>> {
+        let tools = match &self.options.tools {
+            None => return Ok(None),
+            Some(ToolsConfig::Preset(preset)) => {
+                return if preset.preset == "claude_code" {
+                    Ok(Some("default".to_string()))
+                } else {
+                    Err(ClaudeError::invalid_config(format!(
+                        "Unknown tools preset '{}'",
+                        preset.preset
+                    )))
+                };
+            }
+            Some(ToolsConfig::List(tools)) => tools,
+        };
+
+        if let Some(invalid) = tools.iter().find(|t| !t.is_valid()) {
+            return Err(ClaudeError::invalid_config(format!(
+                "Invalid tool name '{invalid}' in tools"
+            )));
+        }
+
+        let unavailable: Vec<&str> = self
+            .options
+            .allowed_tools
+            .iter()
+            .filter(|t| !t.is_mcp() && !tools.iter().any(|tool| tool.as_str() == t.base_name()))
+            .map(ToolName::as_str)
+            .collect();
+        if !unavailable.is_empty() {
+            return Err(ClaudeError::invalid_config(format!(
+                "allowed_tools [{}] are not in the tools list",
+                unavailable.join(", ")
+            )));
+        }
+
+        let contradicted: Vec<&str> = tools
+            .iter()
+            .filter(|tool| {
+                self.options
+                    .disallowed_tools
+                    .iter()
+                    .any(|t| t.as_str() == tool.as_str())
+            })
+            .map(ToolName::as_str)
+            .collect();
+        if !contradicted.is_empty() {
+            return Err(ClaudeError::invalid_config(format!(
+                "Tools [{}] are both listed in tools and disallowed_tools",
+                contradicted.join(", ")
+            )));
+        }
+
+        let names: Vec<&str> = tools.iter().map(ToolName::as_str).collect();
+        Ok(Some(names.join(",")))
+    }
+
+    /// Spawn the task that consumes stderr line by line to prevent blocking
+    ///
+    /// Lines go to the stderr callback (or the parent's stderr), and the last
+    /// `stderr_capture_lines` are kept for error reporting.
+    fn spawn_stderr_task(&mut self, stderr: ChildStderr) -> JoinHandle<()> {
+        let callback = self.options.stderr.clone();
+        let capture_lines = self
+            .options
+            .stderr_capture_lines
+            .unwrap_or(DEFAULT_STDERR_CAPTURE_LINES);
+        let stderr_tail = self.stderr_tail.clone();
+        let (stderr_done_tx, stderr_done_rx) = watch::channel(false);
+        self.stderr_done = stderr_done_rx;
+        tokio::spawn(async move {
+            let mut stderr = BufReader::new(stderr);
+            let mut buffer = Vec::new();
+
+            loop {
+                buffer.clear();
+                match stderr.read_until(b'\n', &mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        let line = String::from_utf8_lossy(&buffer);
+                        let line = line.trim_end_matches(['\r', '\n']);
+                        if let Some(ref callback) = callback {
+                            callback(line.to_string());
+                        } else {
+                            // Forward stderr to parent's stderr
+                            let _ = std::io::Write::write_all(&mut std::io::stderr(), &buffer);
+                        }
+                        if capture_lines > 0 {
+                            if let Ok(mut tail) = stderr_tail.lock() {
+                                if tail.len() == capture_lines {
+                                    tail.pop_front();
+                                }
+                                tail.push_back(line.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            let _ = stderr_done_tx.send(true);
+        })
+    }
+
+    /// Captured stderr tail for a failed run, once stderr has drained
+    async fn stderr_tail_for_error(
+        stderr_tail: &std::sync::Mutex<VecDeque<String>>,
+        mut stderr_done: watch::Receiver<bool>,
+    ) -> String {
+        let _ =
+            tokio::time::timeout(STDERR_DRAIN_TIMEOUT, stderr_done.wait_for(|done| *done)).await;
+        stderr_tail
+            .lock()
+            .ok()
+            .filter(|tail| !tail.is_empty())
+            .map_or_else(
+                || "Check stderr output for details".to_string(),
+                |tail| Vec::from(tail.clone()).join("\n"),
+            )
+    }
+
     /// Get a child cancellation token for this transport
     /// Callers can use this to cancel ongoing operations
     #[must_use]
@@ -662,23 +830,7 @@ impl Transport for SubprocessTransport {
             .take()
             .ok_or_else(|| ClaudeError::connection("Failed to get stderr handle"))?;
 
-        // Spawn task to consume stderr to prevent blocking
-        // We forward it to parent stderr for visibility
-        let stderr_task = tokio::spawn(async move {
-            use tokio::io::AsyncReadExt;
-            let mut stderr = stderr;
-            let mut buffer = vec![0u8; 4096];
-
-            loop {
-                match stderr.read(&mut buffer).await {
-                    Ok(0) | Err(_) => break,
-                    Ok(n) => {
-                        // Forward stderr to parent's stderr
-                        let _ = std::io::Write::write_all(&mut std::io::stderr(), &buffer[..n]);
-                    }
-                }
-            }
-        });
+        let stderr_task = self.spawn_stderr_task(stderr);
 
         // Store handles
         self.stdin = Some(stdin);
@@ -738,6 +890,8 @@ impl Transport for SubprocessTransport {
         let process = Arc::new(Mutex::new(self.process.take()));
         let max_buffer_size = self.max_buffer_size;
         let cancel_token = self.cancellation_token.clone();
+        let stderr_tail = self.stderr_tail.clone();
+        let stderr_done = self.stderr_done.clone();
 
         // Spawn background task to read messages
         let task = tokio::spawn(async move {
@@ -827,10 +981,14 @@ impl Transport for SubprocessTransport {
                         Ok(status) => {
                             if !status.success() {
                                 if let Some(code) = status.code() {
+                                    // Let stderr drain so the tail includes the final lines
+                                    let stderr =
+                                        Self::stderr_tail_for_error(&stderr_tail, stderr_done)
+                                            .await;
                                     let _ = tx.send(Err(ClaudeError::process(
                                         "Command failed",
                                         code,
-                                        Some("Check stderr output for details".to_string()),
+                                        Some(stderr),
                                     )));
                                 }
                             }
@@ -1106,4 +1264,230 @@ mod tests {
         let value = &args[plugin_dir_idx.unwrap() + 1];
         assert_eq!(value, "/path/to/plugin");
     }
+
+    #[test]
+    fn test_can_use_tool_uses_stdio_permission_prompt() {
+        let Ok(cli_path) = SubprocessTransport::find_cli() else {
+            return; // Skip if CLI not installed
+        };
+
+        let callback = crate::permissions::PermissionManager::callback(|_, _, _| async {
+            Ok(crate::types::PermissionResult::Allow(
+                crate::types::PermissionResultAllow {
+                    updated_input: None,
+                    updated_permissions: None,
+                },
+            ))
+        });
+        let options = ClaudeAgentOptions::builder()
+            .can_use_tool(callback.clone())
+            .build();
+
+        let transport =
+            SubprocessTransport::new(PromptInput::Stream, options, Some(cli_path.clone()))
+                .expect("Transport creation should succeed");
+        let cmd = transport
+            .build_command()
+            .expect("build_command should succeed");
+        let args: Vec<String> = cmd
+            .as_std()
+            .get_args()
+            .filter_map(|a| a.to_str().map(String::from))
+            .collect();
+        let idx = args
+            .iter()
+            .position(|a| a == "--permission-prompt-tool")
+            .expect("Expected --permission-prompt-tool flag in args");
+        assert_eq!(args[idx + 1], "stdio");
+
+        // An explicit prompt tool conflicts with the callback
+        let options = ClaudeAgentOptions::builder()
+            .can_use_tool(callback)
+            .permission_prompt_tool_name("mcp__approver__check")
+            .build();
+        let transport = SubprocessTransport::new(PromptInput::Stream, options, Some(cli_path))
+            .expect("Transport creation should succeed");
+        let err = transport.build_command().unwrap_err();
+        assert!(err.to_string().contains("permission_prompt_tool_name"));
+    }
+
+    #[test]
+    fn test_sdk_mcp_servers_added_to_mcp_config() {
+        struct NoopServer;
+
+        #[async_trait]
+        impl crate::mcp::SdkMcpServer for NoopServer {
+            async fn handle_message(
+                &self,
+                _message: serde_json::Value,
+            ) -> Result<Option<serde_json::Value>> {
+                Ok(None)
+            }
+        }
+
+        let Ok(cli_path) = SubprocessTransport::find_cli() else {
+            return; // Skip if CLI not installed
+        };
+
+        let mut servers: HashMap<String, Arc<dyn crate::mcp::SdkMcpServer>> = HashMap::new();
+        servers.insert("app".to_string(), Arc::new(NoopServer));
+        let options = ClaudeAgentOptions::builder()
+            .sdk_mcp_servers(servers)
+            .build();
+
+        let transport = SubprocessTransport::new(PromptInput::Stream, options, Some(cli_path))
+            .expect("Transport creation should succeed");
+        let cmd = transport
+            .build_command()
+            .expect("build_command should succeed");
+        let args: Vec<String> = cmd
+            .as_std()
+            .get_args()
+            .filter_map(|a| a.to_str().map(String::from))
+            .collect();
+        let idx = args
+            .iter()
+            .position(|a| a == "--mcp-config")
+            .expect("Expected --mcp-config flag in args");
+        let config: serde_json::Value = serde_json::from_str(&args[idx + 1]).unwrap();
+        assert_eq!(
+            config["mcpServers"]["app"],
+            serde_json::json!({"type": "sdk", "name": "app"})
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stderr_lines_reach_callback_and_process_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Stand-in CLI that fails after writing to stderr
+        let dir = tempfile::tempdir().unwrap();
+        let cli_path = dir.path().join("claude");
+        std::fs::write(
+            &cli_path,
+            "#!/bin/sh\necho 'first' >&2\necho 'second' >&2\nprintf 'third' >&2\nexit 3\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&cli_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let lines = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let lines_clone = lines.clone();
+        let options = ClaudeAgentOptions::builder()
+            .stderr(Arc::new(move |line: String| {
+                lines_clone.lock().unwrap().push(line);
+            }) as crate::types::StderrCallback)
+            .stderr_capture_lines(2)
+            .build();
+
+        let mut transport = SubprocessTransport::new(PromptInput::Stream, options, Some(cli_path))
+            .expect("Transport creation should succeed");
+        transport.connect().await.unwrap();
+        let mut rx = transport.read_messages();
+
+        let mut process_error = None;
+        while let Some(item) = rx.recv().await {
+            if let Err(e) = item {
+                process_error = Some(e);
+            }
+        }
+        match process_error {
+            Some(ClaudeError::Process {
+                exit_code, stderr, ..
+            }) => {
+                assert_eq!(exit_code, 3);
+                assert_eq!(stderr.as_deref(), Some("second\nthird"));
+            }
+            other => panic!("Expected process error, got {other:?}"),
+        }
+        assert_eq!(*lines.lock().unwrap(), vec!["first", "second", "third"]);
+    }
+
+    #[test]
+    fn test_tools_config_maps_to_tools_flag() {
+        let Ok(cli_path) = SubprocessTransport::find_cli() else {
+            return; // Skip if CLI not installed
+        };
+        let tools_arg = |options: ClaudeAgentOptions| {
+            SubprocessTransport::new(PromptInput::Stream, options, Some(cli_path.clone()))
+                .expect("Transport creation should succeed")
+                .tools_arg()
+        };
+
+        // Not configured: no flag
+        assert_eq!(tools_arg(ClaudeAgentOptions::default()).unwrap(), None);
+
+        let options = ClaudeAgentOptions::builder()
+            .tools(ToolsConfig::from_list(vec!["Read".into(), "Grep".into()]))
+            .allowed_tools(vec!["Read".into(), "mcp__app__lookup".into()])
+            .build();
+        assert_eq!(tools_arg(options).unwrap().as_deref(), Some("Read,Grep"));
+
+        // Empty list disables all built-in tools
+        let options = ClaudeAgentOptions::builder()
+            .tools(ToolsConfig::from_list(vec![]))
+            .build();
+        assert_eq!(tools_arg(options).unwrap().as_deref(), Some(""));
+
+        let options = ClaudeAgentOptions::builder()
+            .tools(ToolsConfig::claude_code_preset())
+            .build();
+        assert_eq!(tools_arg(options).unwrap().as_deref(), Some("default"));
+
+        // Permission rules are checked by their base tool name
+        let options = ClaudeAgentOptions::builder()
+            .tools(ToolsConfig::from_list(vec!["Bash".into()]))
+            .allowed_tools(vec!["Bash(git log:*)".into()])
+            .build();
+        assert!(tools_arg(options).is_ok());
+    }
+
+    #[test]
+    fn test_tools_config_conflicts_are_invalid() {
+        let Ok(cli_path) = SubprocessTransport::find_cli() else {
+            return; // Skip if CLI not installed
+        };
+        let build_error = |options: ClaudeAgentOptions| {
+            let err =
+                SubprocessTransport::new(PromptInput::Stream, options, Some(cli_path.clone()))
+                    .expect("Transport creation should succeed")
+                    .build_command()
+                    .unwrap_err();
+            assert!(matches!(err, ClaudeError::InvalidConfig(_)), "{err}");
+            err.to_string()
+        };
+
+        let err = build_error(
+            ClaudeAgentOptions::builder()
+                .tools(ToolsConfig::from_list(vec!["Read,Write".into()]))
+                .build(),
+        );
+        assert!(err.contains("Invalid tool name"));
+
+        let err = build_error(
+            ClaudeAgentOptions::builder()
+                .tools(ToolsConfig::from_list(vec!["Read".into()]))
+                .allowed_tools(vec!["Read".into(), "Bash".into()])
+                .build(),
+        );
+        assert!(err.contains("Bash"));
+
+        let err = build_error(
+            ClaudeAgentOptions::builder()
+                .tools(ToolsConfig::from_list(vec!["Read".into(), "Write".into()]))
+                .disallowed_tools(vec!["Write".into()])
+                .build(),
+        );
+        assert!(err.contains("Write"));
+
+        let err = build_error(
+            ClaudeAgentOptions::builder()
+                .tools(ToolsConfig::Preset(crate::types::ToolsPreset {
+                    config_type: "preset".to_string(),
+                    preset: "minimal".to_string(),
+                }))
+                .build(),
+        );
+        assert!(err.contains("minimal"));
+    }
 }
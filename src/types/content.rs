@@ -0,0 +1,378 @@
+//! Content blocks for user messages sent to Claude
+//!
+//! [`UserContentBlock`] builds multimodal prompts (text, images and PDF
+//! documents) for [`ClaudeSDKClient::send_content`](crate::ClaudeSDKClient::send_content).
+//! Blocks are validated before anything is written to the CLI: media types
+//! must be supported and match the data, and sizes must stay within the API
+//! limits.
+
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{ClaudeError, Result};
+
+/// Maximum decoded size of a single image (5 MB)
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Maximum decoded size of a single PDF document (32 MB)
+pub const MAX_DOCUMENT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Image media types accepted by the API
+pub const IMAGE_MEDIA_TYPES: &[&str] = &["image/jpeg", "image/png", "image/gif", "image/webp"];
+
+/// Media type of PDF documents
+pub const PDF_MEDIA_TYPE: &str = "application/pdf";
+
+/// Source of image or document data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MediaSource {
+    /// Inline base64-encoded data
+    Base64 {
+        /// Media type (e.g., "image/png", "application/pdf")
+        media_type: String,
+        /// Base64-encoded bytes
+        data: String,
+    },
+}
+
+/// Content block of a user message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserContentBlock {
+    /// Text content
+    Text {
+        /// Text content
+        text: String,
+    },
+    /// Image (JPEG, PNG, GIF or WebP)
+    Image {
+        /// Image data
+        source: MediaSource,
+    },
+    /// Document (PDF)
+    Document {
+        /// Document data
+        source: MediaSource,
+        /// Optional document title
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+    },
+}
+
+impl UserContentBlock {
+    /// Create a text block
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+
+    /// Create an image block from base64-encoded data
+    pub fn image_base64(media_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self::Image {
+            source: MediaSource::Base64 {
+                media_type: media_type.into(),
+                data: data.into(),
+            },
+        }
+    }
+
+    /// Create an image block from raw bytes, detecting the media type
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidContent` if the bytes are not a supported
+    /// image format or exceed [`MAX_IMAGE_BYTES`]
+    pub fn image_bytes(bytes: &[u8]) -> Result<Self> {
+        let media_type = sniff_media_type(bytes)
+            .filter(|media_type| IMAGE_MEDIA_TYPES.contains(media_type))
+            .ok_or_else(|| {
+                ClaudeError::invalid_content("Image data is not JPEG, PNG, GIF or WebP")
+            })?;
+        check_size("Image", bytes.len(), MAX_IMAGE_BYTES)?;
+        Ok(Self::image_base64(media_type, STANDARD.encode(bytes)))
+    }
+
+    /// Create an image block by reading a file, detecting the media type
+    ///
+    /// The file is read synchronously.
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::Io` if the file cannot be read, or
+    /// `ClaudeError::InvalidContent` if it is not a supported image
+    pub fn image_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::image_bytes(&std::fs::read(path)?)
+    }
+
+    /// Create a PDF document block from base64-encoded data
+    pub fn pdf_base64(data: impl Into<String>) -> Self {
+        Self::Document {
+            source: MediaSource::Base64 {
+                media_type: PDF_MEDIA_TYPE.to_string(),
+                data: data.into(),
+            },
+            title: None,
+        }
+    }
+
+    /// Create a PDF document block from raw bytes
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidContent` if the bytes are not a PDF or
+    /// exceed [`MAX_DOCUMENT_BYTES`]
+    pub fn pdf_bytes(bytes: &[u8]) -> Result<Self> {
+        if sniff_media_type(bytes) != Some(PDF_MEDIA_TYPE) {
+            return Err(ClaudeError::invalid_content("Document data is not a PDF"));
+        }
+        check_size("PDF document", bytes.len(), MAX_DOCUMENT_BYTES)?;
+        Ok(Self::pdf_base64(STANDARD.encode(bytes)))
+    }
+
+    /// Create a PDF document block by reading a file
+    ///
+    /// The file is read synchronously, and its file name becomes the title.
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::Io` if the file cannot be read, or
+    /// `ClaudeError::InvalidContent` if it is not a PDF
+    pub fn pdf_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let title = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        Ok(Self::pdf_bytes(&std::fs::read(path)?)?.with_title(title))
+    }
+
+    /// Set the title of a document block (no effect on other blocks)
+    #[must_use]
+    pub fn with_title(mut self, new_title: Option<String>) -> Self {
+        if let Self::Document { ref mut title, .. } = self {
+            *title = new_title;
+        }
+        self
+    }
+
+    /// Check the block against the API's media type and size limits
+    ///
+    /// Base64 data must decode, its declared media type must be supported
+    /// and match the decoded bytes, and the decoded size must not exceed
+    /// [`MAX_IMAGE_BYTES`] or [`MAX_DOCUMENT_BYTES`].
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidContent` describing the first problem found
+    pub fn validate(&self) -> Result<()> {
+        let (kind, source, allowed, max_bytes) = match self {
+            Self::Text { .. } => return Ok(()),
+            Self::Image { source } => ("Image", source, IMAGE_MEDIA_TYPES, MAX_IMAGE_BYTES),
+            Self::Document { source, .. } => (
+                "PDF document",
+                source,
+                &[PDF_MEDIA_TYPE][..],
+                MAX_DOCUMENT_BYTES,
+            ),
+        };
+        let MediaSource::Base64 { media_type, data } = source;
+
+        if !allowed.contains(&media_type.as_str()) {
+            return Err(ClaudeError::invalid_content(format!(
+                "{kind} media type '{media_type}' is not supported (expected one of: {})",
+                allowed.join(", ")
+            )));
+        }
+        // Cheap bound before decoding (base64 is 4/3 the size, minus up to 2 padding bytes)
+        check_size(kind, (data.len() / 4 * 3).saturating_sub(2), max_bytes)?;
+        let bytes = STANDARD.decode(data).map_err(|e| {
+            ClaudeError::invalid_content(format!("{kind} data is not valid base64: {e}"))
+        })?;
+        check_size(kind, bytes.len(), max_bytes)?;
+        match sniff_media_type(&bytes) {
+            Some(detected) if detected != media_type => Err(ClaudeError::invalid_content(format!(
+                "{kind} is declared as '{media_type}' but the data is '{detected}'"
+            ))),
+            None => Err(ClaudeError::invalid_content(format!(
+                "{kind} data does not look like '{media_type}'"
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+/// Content of a user message: plain text or content blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UserInput {
+    /// Plain text
+    Text(String),
+    /// Content blocks (text, images, documents)
+    Blocks(Vec<UserContentBlock>),
+}
+
+impl UserInput {
+    /// Text of the message, with the text blocks joined by newlines
+    #[must_use]
+    pub fn text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Blocks(blocks) => blocks
+                .iter()
+                .filter_map(|block| match block {
+                    UserContentBlock::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    /// Validate every content block
+    ///
+    /// # Errors
+    /// Returns `ClaudeError::InvalidContent` if the block list is empty or any
+    /// block fails [`UserContentBlock::validate`]
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Text(_) => Ok(()),
+            Self::Blocks(blocks) if blocks.is_empty() => Err(ClaudeError::invalid_content(
+                "Message has no content blocks",
+            )),
+            Self::Blocks(blocks) => blocks.iter().try_for_each(UserContentBlock::validate),
+        }
+    }
+}
+
+impl From<String> for UserInput {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for UserInput {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Vec<UserContentBlock>> for UserInput {
+    fn from(blocks: Vec<UserContentBlock>) -> Self {
+        Self::Blocks(blocks)
+    }
+}
+
+/// Detect a supported media type from the leading bytes
+fn sniff_media_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.starts_with(b"%PDF-") {
+        Some(PDF_MEDIA_TYPE)
+    } else {
+        None
+    }
+}
+
+fn check_size(kind: &str, len: usize, max_bytes: usize) -> Result<()> {
+    if len > max_bytes {
+        return Err(ClaudeError::invalid_content(format!(
+            "{kind} is {len} bytes, above the {max_bytes} byte limit"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn test_blocks_serialize_to_api_shape() {
+        let input = UserInput::from(vec![
+            UserContentBlock::text("What is in this screenshot?"),
+            UserContentBlock::image_bytes(PNG).unwrap(),
+        ]);
+        let json = serde_json::to_value(&input).unwrap();
+        assert_eq!(json[0]["type"], "text");
+        assert_eq!(json[1]["type"], "image");
+        assert_eq!(json[1]["source"]["type"], "base64");
+        assert_eq!(json[1]["source"]["media_type"], "image/png");
+        assert_eq!(json[1]["source"]["data"], STANDARD.encode(PNG));
+
+        // Plain text stays a string
+        assert_eq!(serde_json::to_value(UserInput::from("hi")).unwrap(), "hi");
+        assert_eq!(input.text(), "What is in this screenshot?");
+    }
+
+    #[test]
+    fn test_validate_media_types() {
+        assert!(
+            UserContentBlock::image_base64("image/png", STANDARD.encode(PNG))
+                .validate()
+                .is_ok()
+        );
+
+        let err = UserContentBlock::image_base64("image/bmp", STANDARD.encode(PNG))
+            .validate()
+            .unwrap_err();
+        assert!(matches!(err, ClaudeError::InvalidContent(_)));
+        assert!(err.to_string().contains("image/bmp"));
+
+        // Declared type must match the data
+        let err = UserContentBlock::image_base64("image/jpeg", STANDARD.encode(PNG))
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("image/png"));
+
+        let err = UserContentBlock::image_base64("image/png", "not base64!")
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("base64"));
+
+        assert!(UserContentBlock::image_bytes(b"plain text").is_err());
+        assert!(UserContentBlock::pdf_bytes(PNG).is_err());
+        assert!(
+            UserContentBlock::pdf_bytes(b"%PDF-1.7\n")
+                .unwrap()
+                .validate()
+                .is_ok()
+        );
+        assert!(UserInput::Blocks(vec![]).validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_size_limits() {
+        let mut big = PNG.to_vec();
+        big.resize(MAX_IMAGE_BYTES + 1, 0);
+        let err = UserContentBlock::image_bytes(&big).unwrap_err();
+        assert!(err.to_string().contains("limit"));
+
+        let err = UserContentBlock::image_base64("image/png", STANDARD.encode(&big))
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("limit"));
+    }
+
+    #[test]
+    fn test_pdf_file_uses_file_name_as_title() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.pdf");
+        std::fs::write(&path, b"%PDF-1.4\n%%EOF").unwrap();
+
+        let block = UserContentBlock::pdf_file(&path).unwrap();
+        let json = serde_json::to_value(&block).unwrap();
+        assert_eq!(json["type"], "document");
+        assert_eq!(json["title"], "report.pdf");
+        assert_eq!(json["source"]["media_type"], "application/pdf");
+
+        assert!(matches!(
+            UserContentBlock::image_file(dir.path().join("missing.png")),
+            Err(ClaudeError::Io(_))
+        ));
+    }
+}
@@ -1,7 +1,10 @@
 //! Hook types for event handling
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+
+use super::permissions::PermissionBehavior;
 use tokio_util::sync::CancellationToken;
 
 // ============================================================================
@@ -37,6 +40,24 @@ pub enum HookEvent {
     PermissionRequest,
 }
 
+impl HookEvent {
+    /// Every hook event
+    pub const ALL: [Self; 12] = [
+        Self::PreToolUse,
+        Self::PostToolUse,
+        Self::PostToolUseFailure,
+        Self::Notification,
+        Self::UserPromptSubmit,
+        Self::SessionStart,
+        Self::SessionEnd,
+        Self::Stop,
+        Self::SubagentStart,
+        Self::SubagentStop,
+        Self::PreCompact,
+        Self::PermissionRequest,
+    ];
+}
+
 // ============================================================================
 // Hook Input Types
 // ============================================================================
@@ -310,25 +331,499 @@ pub enum HookInput {
 // ============================================================================
 
 /// Hook decision
+///
+/// When several hooks answer the same event, `Block` wins over `Approve`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum HookDecision {
-    /// Block the action
+    /// Approve the action, skipping the permission prompt
+    Approve,
+    /// Block the action (`reason` is fed back to the model)
     Block,
 }
 
 /// Hook output
+///
+/// Mirrors the CLI's hook JSON output. `PreToolUse` permission decisions
+/// (`permissionDecision` allow/deny/ask) live in `hookSpecificOutput`; see
+/// [`permission_decision`](Self::permission_decision).
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HookOutput {
-    /// Decision to block or allow
+    /// Decision to approve or block
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decision: Option<HookDecision>,
+    /// Explanation for the decision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
     /// System message to add
     #[serde(skip_serializing_if = "Option::is_none", rename = "systemMessage")]
     pub system_message: Option<String>,
     /// Hook-specific output data
     #[serde(skip_serializing_if = "Option::is_none", rename = "hookSpecificOutput")]
     pub hook_specific_output: Option<serde_json::Value>,
+    /// Whether the agent should continue (`false` stops it after this hook)
+    #[serde(skip_serializing_if = "Option::is_none", rename = "continue")]
+    pub continue_: Option<bool>,
+    /// Reason shown to the user when `continue_` is `false`
+    #[serde(skip_serializing_if = "Option::is_none", rename = "stopReason")]
+    pub stop_reason: Option<String>,
+    /// Hide the hook's output from the transcript
+    #[serde(skip_serializing_if = "Option::is_none", rename = "suppressOutput")]
+    pub suppress_output: Option<bool>,
+}
+
+impl HookOutput {
+    /// Block the action with a reason for the model
+    #[must_use]
+    pub fn block(reason: impl Into<String>) -> Self {
+        Self {
+            decision: Some(HookDecision::Block),
+            reason: Some(reason.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Approve the action
+    #[must_use]
+    pub fn approve() -> Self {
+        Self {
+            decision: Some(HookDecision::Approve),
+            ..Self::default()
+        }
+    }
+
+    /// Stop the agent (`continue: false`) with a reason shown to the user
+    #[must_use]
+    pub fn stop(reason: impl Into<String>) -> Self {
+        Self {
+            continue_: Some(false),
+            stop_reason: Some(reason.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Add a system message
+    #[must_use]
+    pub fn with_system_message(mut self, message: impl Into<String>) -> Self {
+        self.system_message = Some(message.into());
+        self
+    }
+
+    /// Hide the hook's output from the transcript
+    #[must_use]
+    pub fn with_suppressed_output(mut self) -> Self {
+        self.suppress_output = Some(true);
+        self
+    }
+
+    /// Whether the output carries no decision, message or data
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.decision.is_none()
+            && self.reason.is_none()
+            && self.system_message.is_none()
+            && self.hook_specific_output.is_none()
+            && self.continue_.is_none()
+            && self.stop_reason.is_none()
+            && self.suppress_output.is_none()
+    }
+
+    /// `permissionDecision` from `hookSpecificOutput`, if any
+    #[must_use]
+    pub fn permission_decision(&self) -> Option<PermissionBehavior> {
+        self.specific_field("permissionDecision")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+
+    /// `additionalContext` from `hookSpecificOutput`, if any
+    #[must_use]
+    pub fn additional_context(&self) -> Option<&str> {
+        self.specific_field("additionalContext")
+            .and_then(serde_json::Value::as_str)
+    }
+
+    /// Whether the action is blocked (`decision: block` or `permissionDecision: deny`)
+    #[must_use]
+    pub fn is_blocking(&self) -> bool {
+        self.decision == Some(HookDecision::Block)
+            || self.permission_decision() == Some(PermissionBehavior::Deny)
+    }
+
+    /// Whether the agent should keep running (`continue` is not `false`)
+    #[must_use]
+    pub fn should_continue(&self) -> bool {
+        self.continue_ != Some(false)
+    }
+
+    fn specific_field(&self, key: &str) -> Option<&serde_json::Value> {
+        self.hook_specific_output.as_ref()?.get(key)
+    }
+
+    /// Merge the output of a later hook for the same event into this one
+    ///
+    /// Precedence:
+    /// - `decision`: `Block` wins over `Approve`
+    /// - `permissionDecision`: deny wins over ask, ask over allow; its
+    ///   reason follows the winning decision
+    /// - `continue: false` and `suppressOutput: true` win
+    /// - `reason`, `systemMessage`, `stopReason` and `additionalContext`
+    ///   are concatenated, one line per hook
+    /// - other `hookSpecificOutput` keys (e.g. `updatedInput`) are taken
+    ///   from the later hook
+    pub fn merge(&mut self, other: HookOutput) {
+        self.decision = match (self.decision, other.decision) {
+            (Some(HookDecision::Block), _) | (_, Some(HookDecision::Block)) => {
+                Some(HookDecision::Block)
+            }
+            (current, next) => current.or(next),
+        };
+        concat_message(&mut self.reason, other.reason);
+        concat_message(&mut self.system_message, other.system_message);
+        concat_message(&mut self.stop_reason, other.stop_reason);
+        self.continue_ = match (self.continue_, other.continue_) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (current, next) => current.or(next),
+        };
+        self.suppress_output = match (self.suppress_output, other.suppress_output) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (current, next) => current.or(next),
+        };
+        self.hook_specific_output =
+            match (self.hook_specific_output.take(), other.hook_specific_output) {
+                (
+                    Some(serde_json::Value::Object(current)),
+                    Some(serde_json::Value::Object(next)),
+                ) => Some(serde_json::Value::Object(merge_specific_output(
+                    current, next,
+                ))),
+                (current, next) => next.or(current),
+            };
+    }
+}
+
+/// Append `next` to `current` on a new line
+fn concat_message(current: &mut Option<String>, next: Option<String>) {
+    match (current.as_mut(), next) {
+        (Some(current), Some(next)) => {
+            current.push('\n');
+            current.push_str(&next);
+        }
+        (None, next) => *current = next,
+        (Some(_), None) => {}
+    }
+}
+
+/// Rank of a `permissionDecision` for merging (higher wins)
+fn permission_rank(value: Option<&serde_json::Value>) -> u8 {
+    match value.and_then(serde_json::Value::as_str) {
+        Some("deny") => 3,
+        Some("ask") => 2,
+        Some("allow") => 1,
+        _ => 0,
+    }
+}
+
+/// Merge two `hookSpecificOutput` objects (see [`HookOutput::merge`])
+fn merge_specific_output(
+    mut current: serde_json::Map<String, serde_json::Value>,
+    mut next: serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+    let next_decision = next.remove("permissionDecision");
+    let next_reason = next.remove("permissionDecisionReason");
+    let current_rank = permission_rank(current.get("permissionDecision"));
+    let next_rank = permission_rank(next_decision.as_ref());
+    if next_rank > current_rank {
+        current.remove("permissionDecisionReason");
+        current.extend(next_decision.map(|v| ("permissionDecision".to_string(), v)));
+        current.extend(next_reason.map(|v| ("permissionDecisionReason".to_string(), v)));
+    } else if next_rank == current_rank && next_rank > 0 {
+        let mut reason = current
+            .remove("permissionDecisionReason")
+            .and_then(|v| v.as_str().map(String::from));
+        concat_message(
+            &mut reason,
+            next_reason.and_then(|v| v.as_str().map(String::from)),
+        );
+        current.extend(reason.map(|r| ("permissionDecisionReason".to_string(), r.into())));
+    } else if next_rank == 0 {
+        // A reason without a decision is kept like any other key
+        next.extend(next_reason.map(|v| ("permissionDecisionReason".to_string(), v)));
+    }
+
+    if let Some(serde_json::Value::String(next_context)) = next.remove("additionalContext") {
+        let mut context = current
+            .remove("additionalContext")
+            .and_then(|v| v.as_str().map(String::from));
+        concat_message(&mut context, Some(next_context));
+        current.extend(context.map(|c| ("additionalContext".to_string(), c.into())));
+    }
+
+    current.extend(next);
+    current
+}
+
+// ============================================================================
+// Typed Hook Outputs
+// ============================================================================
+
+/// Conversion of a typed hook output into the wire-level [`HookOutput`]
+pub trait IntoHookOutput {
+    /// Convert into a `HookOutput`, tagging `hookSpecificOutput` with `event`
+    fn into_hook_output(self, event: HookEvent) -> HookOutput;
+}
+
+impl IntoHookOutput for HookOutput {
+    fn into_hook_output(self, _event: HookEvent) -> HookOutput {
+        self
+    }
+}
+
+/// Build `hookSpecificOutput` from event-specific fields (`None` if there are none)
+fn hook_specific_output(
+    event: HookEvent,
+    fields: serde_json::Map<String, serde_json::Value>,
+) -> Option<serde_json::Value> {
+    if fields.is_empty() {
+        return None;
+    }
+    let mut output = serde_json::Map::new();
+    output.insert(
+        "hookEventName".to_string(),
+        serde_json::to_value(event).unwrap_or_default(),
+    );
+    output.extend(fields);
+    Some(serde_json::Value::Object(output))
+}
+
+/// Typed output of a `PreToolUse` hook
+///
+/// Serialized as `permissionDecision`, `permissionDecisionReason`,
+/// `updatedInput` and `additionalContext` inside `hookSpecificOutput`.
+/// A `Deny` decision also sets [`HookDecision::Block`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreToolUseHookOutput {
+    /// Allow, deny or ask about the tool use (`None` leaves it to the permission flow)
+    pub permission_decision: Option<PermissionBehavior>,
+    /// Reason for the permission decision
+    pub permission_decision_reason: Option<String>,
+    /// Replacement tool input
+    pub updated_input: Option<serde_json::Value>,
+    /// Extra context for the model
+    pub additional_context: Option<String>,
+    /// System message to add
+    pub system_message: Option<String>,
+    /// Whether the agent should continue
+    pub continue_: Option<bool>,
+    /// Reason shown to the user when stopping
+    pub stop_reason: Option<String>,
+    /// Hide the hook's output from the transcript
+    pub suppress_output: Option<bool>,
+}
+
+impl PreToolUseHookOutput {
+    /// Allow the tool use without asking
+    #[must_use]
+    pub fn allow() -> Self {
+        Self {
+            permission_decision: Some(PermissionBehavior::Allow),
+            ..Self::default()
+        }
+    }
+
+    /// Deny the tool use
+    #[must_use]
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self {
+            permission_decision: Some(PermissionBehavior::Deny),
+            permission_decision_reason: Some(reason.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Ask the user about the tool use
+    #[must_use]
+    pub fn ask(reason: impl Into<String>) -> Self {
+        Self {
+            permission_decision: Some(PermissionBehavior::Ask),
+            permission_decision_reason: Some(reason.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Replace the tool input
+    #[must_use]
+    pub fn with_updated_input(mut self, input: serde_json::Value) -> Self {
+        self.updated_input = Some(input);
+        self
+    }
+
+    /// Add context for the model
+    #[must_use]
+    pub fn with_additional_context(mut self, context: impl Into<String>) -> Self {
+        self.additional_context = Some(context.into());
+        self
+    }
+}
+
+impl IntoHookOutput for PreToolUseHookOutput {
+    fn into_hook_output(self, event: HookEvent) -> HookOutput {
+        let mut fields = serde_json::Map::new();
+        if let Some(decision) = self.permission_decision {
+            fields.insert(
+                "permissionDecision".to_string(),
+                serde_json::to_value(decision).unwrap_or_default(),
+            );
+        }
+        if let Some(reason) = self.permission_decision_reason {
+            fields.insert("permissionDecisionReason".to_string(), reason.into());
+        }
+        if let Some(input) = self.updated_input {
+            fields.insert("updatedInput".to_string(), input);
+        }
+        if let Some(context) = self.additional_context {
+            fields.insert("additionalContext".to_string(), context.into());
+        }
+
+        HookOutput {
+            decision: (self.permission_decision == Some(PermissionBehavior::Deny))
+                .then_some(HookDecision::Block),
+            system_message: self.system_message,
+            hook_specific_output: hook_specific_output(event, fields),
+            continue_: self.continue_,
+            stop_reason: self.stop_reason,
+            suppress_output: self.suppress_output,
+            ..HookOutput::default()
+        }
+    }
+}
+
+/// Typed output of hooks that can add context for the model
+///
+/// Used by `PostToolUse`, `PostToolUseFailure`, `UserPromptSubmit`,
+/// `SessionStart` and `SubagentStart`; `additional_context` is serialized
+/// as `additionalContext` inside `hookSpecificOutput`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextHookOutput {
+    /// Extra context for the model
+    pub additional_context: Option<String>,
+    /// System message to add
+    pub system_message: Option<String>,
+    /// Whether the agent should continue
+    pub continue_: Option<bool>,
+    /// Reason shown to the user when stopping
+    pub stop_reason: Option<String>,
+    /// Hide the hook's output from the transcript
+    pub suppress_output: Option<bool>,
+}
+
+impl ContextHookOutput {
+    /// Add context for the model
+    #[must_use]
+    pub fn with_context(context: impl Into<String>) -> Self {
+        Self {
+            additional_context: Some(context.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Stop the agent with a reason shown to the user
+    #[must_use]
+    pub fn stop(reason: impl Into<String>) -> Self {
+        Self {
+            continue_: Some(false),
+            stop_reason: Some(reason.into()),
+            ..Self::default()
+        }
+    }
+}
+
+impl IntoHookOutput for ContextHookOutput {
+    fn into_hook_output(self, event: HookEvent) -> HookOutput {
+        let mut fields = serde_json::Map::new();
+        if let Some(context) = self.additional_context {
+            fields.insert("additionalContext".to_string(), context.into());
+        }
+        HookOutput {
+            system_message: self.system_message,
+            hook_specific_output: hook_specific_output(event, fields),
+            continue_: self.continue_,
+            stop_reason: self.stop_reason,
+            suppress_output: self.suppress_output,
+            ..HookOutput::default()
+        }
+    }
+}
+
+/// A hook input type tied to its event and typed output
+///
+/// Implemented by every `*HookInput` struct; used by
+/// [`HookManager::typed_callback`](crate::hooks::HookManager::typed_callback)
+/// to deserialize the input and serialize the output.
+pub trait HookEventInput: DeserializeOwned + Send + 'static {
+    /// Event this input belongs to
+    const EVENT: HookEvent;
+    /// Output returned by hooks for this event
+    type Output: IntoHookOutput + Send + 'static;
+}
+
+impl HookEventInput for PreToolUseHookInput {
+    const EVENT: HookEvent = HookEvent::PreToolUse;
+    type Output = PreToolUseHookOutput;
+}
+
+impl HookEventInput for PostToolUseHookInput {
+    const EVENT: HookEvent = HookEvent::PostToolUse;
+    type Output = ContextHookOutput;
+}
+
+impl HookEventInput for PostToolUseFailureHookInput {
+    const EVENT: HookEvent = HookEvent::PostToolUseFailure;
+    type Output = ContextHookOutput;
+}
+
+impl HookEventInput for UserPromptSubmitHookInput {
+    const EVENT: HookEvent = HookEvent::UserPromptSubmit;
+    type Output = ContextHookOutput;
+}
+
+impl HookEventInput for SessionStartHookInput {
+    const EVENT: HookEvent = HookEvent::SessionStart;
+    type Output = ContextHookOutput;
+}
+
+impl HookEventInput for SubagentStartHookInput {
+    const EVENT: HookEvent = HookEvent::SubagentStart;
+    type Output = ContextHookOutput;
+}
+
+impl HookEventInput for SessionEndHookInput {
+    const EVENT: HookEvent = HookEvent::SessionEnd;
+    type Output = HookOutput;
+}
+
+impl HookEventInput for StopHookInput {
+    const EVENT: HookEvent = HookEvent::Stop;
+    type Output = HookOutput;
+}
+
+impl HookEventInput for SubagentStopHookInput {
+    const EVENT: HookEvent = HookEvent::SubagentStop;
+    type Output = HookOutput;
+}
+
+impl HookEventInput for NotificationHookInput {
+    const EVENT: HookEvent = HookEvent::Notification;
+    type Output = HookOutput;
+}
+
+impl HookEventInput for PreCompactHookInput {
+    const EVENT: HookEvent = HookEvent::PreCompact;
+    type Output = HookOutput;
+}
+
+impl HookEventInput for PermissionRequestHookInput {
+    const EVENT: HookEvent = HookEvent::PermissionRequest;
+    type Output = HookOutput;
 }
 
 /// Context for hook callbacks
@@ -537,6 +1032,7 @@ mod tests {
             decision: Some(HookDecision::Block),
             system_message: Some("Blocked for safety".to_string()),
             hook_specific_output: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&output).unwrap();
@@ -583,4 +1079,137 @@ mod tests {
         let parsed: CompactTrigger = serde_json::from_str("\"manual\"").unwrap();
         assert_eq!(parsed, CompactTrigger::Manual);
     }
+
+    #[test]
+    fn test_pre_tool_use_output_shape() {
+        let output = PreToolUseHookOutput::deny("no network")
+            .with_updated_input(serde_json::json!({"command": "ls"}))
+            .with_additional_context("sandboxed")
+            .into_hook_output(HookEvent::PreToolUse);
+
+        assert_eq!(output.decision, Some(HookDecision::Block));
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "decision": "block",
+                "hookSpecificOutput": {
+                    "hookEventName": "PreToolUse",
+                    "permissionDecision": "deny",
+                    "permissionDecisionReason": "no network",
+                    "updatedInput": {"command": "ls"},
+                    "additionalContext": "sandboxed"
+                }
+            })
+        );
+
+        let allow = PreToolUseHookOutput::allow().into_hook_output(HookEvent::PreToolUse);
+        assert!(allow.decision.is_none());
+        assert!(
+            PreToolUseHookOutput::default()
+                .into_hook_output(HookEvent::PreToolUse)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_context_output_shape() {
+        let output = ContextHookOutput::with_context("repo uses pnpm")
+            .into_hook_output(HookEvent::SessionStart);
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            serde_json::json!({
+                "hookSpecificOutput": {
+                    "hookEventName": "SessionStart",
+                    "additionalContext": "repo uses pnpm"
+                }
+            })
+        );
+
+        let stop =
+            ContextHookOutput::stop("budget exceeded").into_hook_output(HookEvent::PostToolUse);
+        assert_eq!(
+            serde_json::to_value(&stop).unwrap(),
+            serde_json::json!({"continue": false, "stopReason": "budget exceeded"})
+        );
+    }
+
+    #[test]
+    fn test_hook_output_rich_fields_serde() {
+        let output = HookOutput::block("tests must pass first")
+            .with_system_message("blocked by CI policy")
+            .with_suppressed_output();
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            serde_json::json!({
+                "decision": "block",
+                "reason": "tests must pass first",
+                "systemMessage": "blocked by CI policy",
+                "suppressOutput": true
+            })
+        );
+
+        let parsed: HookOutput = serde_json::from_value(serde_json::json!({
+            "decision": "approve",
+            "continue": false,
+            "stopReason": "done",
+            "hookSpecificOutput": {"hookEventName": "PreToolUse", "permissionDecision": "ask"}
+        }))
+        .unwrap();
+        assert_eq!(parsed.decision, Some(HookDecision::Approve));
+        assert!(!parsed.should_continue());
+        assert_eq!(parsed.permission_decision(), Some(PermissionBehavior::Ask));
+        assert!(!parsed.is_blocking());
+    }
+
+    #[test]
+    fn test_hook_output_merge_precedence() {
+        let mut output = PreToolUseHookOutput::allow()
+            .with_additional_context("first")
+            .into_hook_output(HookEvent::PreToolUse);
+        output.system_message = Some("one".to_string());
+        output.decision = Some(HookDecision::Approve);
+
+        let mut ask = PreToolUseHookOutput::ask("needs review")
+            .with_updated_input(serde_json::json!({"command": "ls"}))
+            .with_additional_context("second")
+            .into_hook_output(HookEvent::PreToolUse);
+        ask.system_message = Some("two".to_string());
+        ask.continue_ = Some(true);
+        output.merge(ask);
+
+        output.merge(HookOutput::stop("quota").with_suppressed_output());
+        output.merge(HookOutput {
+            continue_: Some(true),
+            suppress_output: Some(false),
+            ..HookOutput::block("denied")
+        });
+
+        assert_eq!(output.decision, Some(HookDecision::Block));
+        assert_eq!(output.reason.as_deref(), Some("denied"));
+        assert_eq!(output.system_message.as_deref(), Some("one\ntwo"));
+        assert_eq!(output.stop_reason.as_deref(), Some("quota"));
+        assert_eq!(output.continue_, Some(false));
+        assert_eq!(output.suppress_output, Some(true));
+        assert_eq!(output.permission_decision(), Some(PermissionBehavior::Ask));
+        assert_eq!(output.additional_context(), Some("first\nsecond"));
+        assert_eq!(
+            output.hook_specific_output.as_ref().unwrap()["permissionDecisionReason"],
+            "needs review"
+        );
+        assert_eq!(
+            output.hook_specific_output.as_ref().unwrap()["updatedInput"]["command"],
+            "ls"
+        );
+
+        // Deny outranks ask regardless of order, and takes its reason along
+        output.merge(PreToolUseHookOutput::deny("no").into_hook_output(HookEvent::PreToolUse));
+        output.merge(PreToolUseHookOutput::allow().into_hook_output(HookEvent::PreToolUse));
+        assert_eq!(output.permission_decision(), Some(PermissionBehavior::Deny));
+        assert_eq!(
+            output.hook_specific_output.as_ref().unwrap()["permissionDecisionReason"],
+            "no"
+        );
+        assert!(output.is_blocking());
+    }
 }
@@ -89,6 +89,35 @@ impl ToolName {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Check that this is a plain tool name
+    ///
+    /// Valid names are non-empty and contain only ASCII letters, digits, `_`
+    /// and `-` (MCP tools use `mcp__<server>__<tool>`). Permission rules such
+    /// as `Bash(git log:*)` are not plain tool names.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_empty()
+            && self
+                .0
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    }
+
+    /// Tool name without any permission rule, e.g. `Bash` for `Bash(git log:*)`
+    #[must_use]
+    pub fn base_name(&self) -> &str {
+        self.0
+            .split_once('(')
+            .map_or(self.0.as_str(), |(base, _)| base)
+            .trim()
+    }
+
+    /// Whether this names an MCP tool (`mcp__<server>__<tool>`)
+    #[must_use]
+    pub fn is_mcp(&self) -> bool {
+        self.0.starts_with("mcp__")
+    }
 }
 
 impl From<String> for ToolName {
@@ -1,10 +1,15 @@
 //! Message types for conversations
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::content::MediaSource;
 use super::identifiers::SessionId;
 use super::introspection::{ModelUsage, SDKPermissionDenial};
+use super::stream::StreamEvent;
+use super::usage::Usage;
+use crate::error::{ClaudeError, Result};
 
 // ============================================================================
 // AskUserQuestion Tool Types
@@ -77,7 +82,8 @@ pub enum ContentBlock {
     Thinking {
         /// Thinking content
         thinking: String,
-        /// Signature for verification
+        /// Signature for verification (empty until streamed in full)
+        #[serde(default)]
         signature: String,
     },
     /// Tool use request
@@ -100,6 +106,71 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    /// Thinking block redacted by safety systems (encrypted)
+    RedactedThinking {
+        /// Encrypted thinking data, passed back to the API unchanged
+        data: String,
+    },
+    /// Tool use executed by the API server (e.g. web search)
+    ServerToolUse {
+        /// Tool use ID
+        id: String,
+        /// Server tool name (e.g. `web_search`)
+        name: String,
+        /// Tool input parameters
+        input: serde_json::Value,
+    },
+    /// Result of a server-side web search
+    WebSearchToolResult {
+        /// ID of the server tool use this is a result for
+        tool_use_id: String,
+        /// Search results, or an error object
+        content: serde_json::Value,
+    },
+    /// Image content
+    Image {
+        /// Image data
+        source: MediaSource,
+    },
+    /// Block type not known to this SDK version; `raw` holds the block as
+    /// received
+    #[serde(untagged, deserialize_with = "deserialize_unknown_block")]
+    Unknown {
+        /// Raw block JSON (including `type`)
+        #[serde(flatten)]
+        raw: serde_json::Value,
+    },
+}
+
+/// Block `type` tags with a typed `ContentBlock` variant
+const KNOWN_BLOCK_TYPES: [&str; 8] = [
+    "text",
+    "thinking",
+    "tool_use",
+    "tool_result",
+    "redacted_thinking",
+    "server_tool_use",
+    "web_search_tool_result",
+    "image",
+];
+
+/// Accept a block as `ContentBlock::Unknown` only if its `type` is not one
+/// of the typed variants; a known type that failed to match is an error
+fn deserialize_unknown_block<'de, D>(
+    deserializer: D,
+) -> std::result::Result<serde_json::Value, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    match raw.get("type").and_then(|t| t.as_str()) {
+        Some(block_type) if KNOWN_BLOCK_TYPES.contains(&block_type) => {
+            Err(serde::de::Error::custom(format!(
+                "unexpected payload for content block type '{block_type}'"
+            )))
+        }
+        _ => Ok(raw),
+    }
 }
 
 /// User message content
@@ -125,10 +196,16 @@ pub enum UserContent {
 /// Assistant message content
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssistantMessageContent {
+    /// API message ID (shared by all blocks of one response)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     /// Model that generated the message
     pub model: String,
     /// Message content blocks
     pub content: Vec<ContentBlock>,
+    /// Token usage of the API response so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 /// Message types
@@ -148,6 +225,9 @@ pub enum Message {
         /// Checkpoint UUID for file rewind (requires `--replay-user-messages`)
         #[serde(skip_serializing_if = "Option::is_none")]
         uuid: Option<String>,
+        /// Structured result of the tool call, for messages carrying a tool result
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_use_result: Option<serde_json::Value>,
     },
     /// Assistant message
     Assistant {
@@ -187,7 +267,7 @@ pub enum Message {
         total_cost_usd: Option<f64>,
         /// Token usage statistics (aggregate)
         #[serde(skip_serializing_if = "Option::is_none")]
-        usage: Option<serde_json::Value>,
+        usage: Option<Usage>,
         /// Result message (for success subtype)
         #[serde(skip_serializing_if = "Option::is_none")]
         result: Option<String>,
@@ -220,6 +300,109 @@ pub enum Message {
         #[serde(skip_serializing_if = "Option::is_none")]
         parent_tool_use_id: Option<String>,
     },
+    /// Message type not known to this SDK version; `raw` holds the message
+    /// as received
+    ///
+    /// [`parse_message`](crate::message::parse_message) only produces this for
+    /// unknown `type` tags and reports known types with a bad payload as
+    /// errors.
+    #[serde(untagged)]
+    Unknown {
+        /// Raw message JSON (including `type`)
+        #[serde(flatten)]
+        raw: serde_json::Value,
+    },
+}
+
+impl Message {
+    /// Structured tool result attached to a `User` message carrying a tool result
+    ///
+    /// Parse it with [`BuiltinToolOutput::parse`](super::tools::BuiltinToolOutput::parse)
+    /// using the name of the matching tool call.
+    #[must_use]
+    pub fn tool_use_result(&self) -> Option<&serde_json::Value> {
+        match self {
+            Self::User {
+                tool_use_result, ..
+            } => tool_use_result.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Token usage carried by this message, if any
+    ///
+    /// Returns the aggregate usage of a `Result`, the per-response usage of an
+    /// assistant message, and the usage in `message_start`/`message_delta`
+    /// stream events. See [`UsageTracker`](super::usage::UsageTracker) for a
+    /// running total.
+    #[must_use]
+    pub fn usage(&self) -> Option<Usage> {
+        match self {
+            Self::Result { usage, .. } => usage.clone(),
+            Self::Assistant { message, .. } => message.usage.clone(),
+            Self::StreamEvent { .. } => match self.stream_event()? {
+                StreamEvent::MessageStart { message } => message.usage,
+                StreamEvent::MessageDelta { usage, .. } => usage,
+                _ => None,
+            },
+            Self::User { .. } | Self::System { .. } | Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Deserialize the structured output of a `Result` message into `T`
+    ///
+    /// Uses `structured_output`, or the `result` text parsed as JSON when the
+    /// CLI returned the output there instead.
+    ///
+    /// # Errors
+    /// Returns [`ClaudeError::StructuredOutput`] with the raw output if this
+    /// is not a successful `Result`, it carries no output, or the output does
+    /// not deserialize into `T`
+    pub fn parse_structured_output<T: DeserializeOwned>(&self) -> Result<T> {
+        let type_name = std::any::type_name::<T>();
+        let Self::Result {
+            is_error,
+            subtype,
+            result,
+            structured_output,
+            errors,
+            ..
+        } = self
+        else {
+            return Err(ClaudeError::structured_output(
+                type_name,
+                "not a result message",
+                serde_json::Value::Null,
+            ));
+        };
+
+        let raw = structured_output.clone().or_else(|| {
+            result.as_ref().map(|text| {
+                serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::from(text.clone()))
+            })
+        });
+        if *is_error {
+            let reason = if errors.is_empty() {
+                subtype.clone()
+            } else {
+                errors.join("; ")
+            };
+            return Err(ClaudeError::structured_output(
+                type_name,
+                format!("run failed: {reason}"),
+                raw.unwrap_or_default(),
+            ));
+        }
+        let Some(raw) = raw else {
+            return Err(ClaudeError::structured_output(
+                type_name,
+                "result carries no structured output",
+                serde_json::Value::Null,
+            ));
+        };
+        T::deserialize(&raw)
+            .map_err(|e| ClaudeError::structured_output(type_name, e.to_string(), raw.clone()))
+    }
 }
 
 #[cfg(test)]
@@ -391,4 +574,56 @@ mod tests {
         assert_eq!(input.questions[0].header, "Database");
         assert_eq!(input.questions[0].options.len(), 2);
     }
+
+    fn result_message(value: serde_json::Value) -> Message {
+        let mut result = serde_json::json!({
+            "type": "result", "subtype": "success", "duration_ms": 1,
+            "duration_api_ms": 1, "is_error": false, "num_turns": 1, "session_id": "s1"
+        });
+        for (key, field) in value.as_object().unwrap() {
+            result[key] = field.clone();
+        }
+        serde_json::from_value(result).unwrap()
+    }
+
+    #[test]
+    fn test_parse_structured_output() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Answer {
+            city: String,
+            population: u64,
+        }
+
+        let msg = result_message(serde_json::json!({
+            "structured_output": {"city": "Paris", "population": 2_100_000}
+        }));
+        let answer: Answer = msg.parse_structured_output().unwrap();
+        assert_eq!(answer.city, "Paris");
+
+        // Falls back to JSON in the result text
+        let msg = result_message(serde_json::json!({
+            "result": "{\"city\": \"Rome\", \"population\": 2800000}"
+        }));
+        let answer: Answer = msg.parse_structured_output().unwrap();
+        assert_eq!(answer.city, "Rome");
+
+        let msg = result_message(serde_json::json!({
+            "structured_output": {"city": "Paris"}
+        }));
+        match msg.parse_structured_output::<Answer>() {
+            Err(ClaudeError::StructuredOutput { raw, message, .. }) => {
+                assert_eq!(raw, serde_json::json!({"city": "Paris"}));
+                assert!(message.contains("population"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+
+        let msg = result_message(serde_json::json!({
+            "subtype": "error_max_structured_output_retries", "is_error": true
+        }));
+        assert!(matches!(
+            msg.parse_structured_output::<Answer>(),
+            Err(ClaudeError::StructuredOutput { .. })
+        ));
+    }
 }
@@ -4,6 +4,7 @@
 //! including identifiers for type safety, message types, option types, and more.
 
 // Module declarations
+pub mod content;
 pub mod hooks;
 pub mod identifiers;
 pub mod introspection;
@@ -11,27 +12,36 @@ pub mod mcp;
 pub mod messages;
 pub mod options;
 pub mod permissions;
+pub mod stream;
+pub mod system;
+pub mod tools;
 pub mod usage;
 
 // Re-export all public types for backward compatibility
+pub use content::{MediaSource, UserContentBlock, UserInput};
 pub use hooks::{
     // Base input
     BaseHookInput,
     // Event-specific inputs
     CompactTrigger,
+    // Typed outputs
+    ContextHookOutput,
     // Core hook types
     HookContext,
     HookDecision,
     HookEvent,
+    HookEventInput,
     HookInput,
     HookMatcher,
     HookOutput,
+    IntoHookOutput,
     NotificationHookInput,
     PermissionRequestHookInput,
     PostToolUseFailureHookInput,
     PostToolUseHookInput,
     PreCompactHookInput,
     PreToolUseHookInput,
+    PreToolUseHookOutput,
     SessionEndHookInput,
     SessionEndReason,
     SessionStartHookInput,
@@ -64,4 +74,14 @@ pub use permissions::{
     PermissionResultAllow, PermissionResultDeny, PermissionRuleValue, PermissionUpdate,
     PermissionUpdateDestination, SettingSource, ToolPermissionContext,
 };
-pub use usage::{UsageData, UsageLimit};
+pub use stream::{ContentDelta, MessageDeltaInfo, StreamEvent, StreamMessageStart};
+pub use system::{CompactBoundary, HookResponse, SystemEvent, SystemStatus};
+pub use tools::{
+    BashInput, BashOutput, BuiltinToolInput, BuiltinToolOutput, EditInput, EditOperation,
+    EditOutput, GlobInput, GlobOutput, GrepInput, GrepOutput, GrepOutputMode, MultiEditInput,
+    MultiEditOutput, NotebookCellType, NotebookEditInput, NotebookEditMode, NotebookEditOutput,
+    ReadFile, ReadInput, ReadOutput, TaskInput, TaskOutput, TodoItem, TodoStatus, TodoWriteInput,
+    TodoWriteOutput, WebFetchInput, WebFetchOutput, WebSearchInput, WebSearchOutput, WriteInput,
+    WriteOutput,
+};
+pub use usage::{ServerToolUse, Usage, UsageData, UsageLimit, UsageTracker};
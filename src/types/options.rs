@@ -9,6 +9,9 @@ use super::hooks::{HookEvent, HookMatcher};
 use super::identifiers::ToolName;
 use super::mcp::McpServers;
 use super::permissions::{CanUseToolCallback, PermissionMode, SettingSource};
+use crate::mcp::SdkMcpServer;
+use crate::session::SharedSessionStore;
+use crate::structured_output::StructuredOutputPolicy;
 
 // ============================================================================
 // System Prompt Types
@@ -139,7 +142,7 @@ use std::sync::Arc;
 
 /// Callback for stderr output
 ///
-/// This callback is invoked when the Claude CLI writes to stderr.
+/// This callback is invoked for each line the Claude CLI writes to stderr.
 /// Useful for debugging and logging purposes.
 pub type StderrCallback = Arc<dyn Fn(String) + Send + Sync>;
 
@@ -166,6 +169,15 @@ impl OutputFormat {
             schema,
         }
     }
+
+    /// JSON schema output format derived from `T` (`schemars` feature)
+    ///
+    /// Deserialize the result with [`Message::parse_structured_output`](super::Message::parse_structured_output).
+    #[cfg(feature = "schemars")]
+    #[must_use]
+    pub fn for_type<T: schemars::JsonSchema>() -> Self {
+        Self::json_schema(schemars::schema_for!(T).to_value())
+    }
 }
 
 // ============================================================================
@@ -305,6 +317,13 @@ pub struct ClaudeAgentOptions {
     #[builder(default)]
     pub mcp_servers: McpServers,
 
+    /// In-process MCP servers, keyed by the name the CLI exposes them under
+    ///
+    /// Each is added to `--mcp-config` as an SDK server and its JSON-RPC
+    /// traffic is routed to the instance over the control channel.
+    #[builder(default)]
+    pub sdk_mcp_servers: HashMap<String, Arc<dyn SdkMcpServer>>,
+
     /// Permission mode for tool execution
     #[builder(default, setter(strip_option))]
     pub permission_mode: Option<PermissionMode>,
@@ -330,6 +349,9 @@ pub struct ClaudeAgentOptions {
     pub model: Option<String>,
 
     /// Tool name to use for permission prompts
+    ///
+    /// Cannot be combined with `can_use_tool`, which uses the SDK itself as
+    /// the permission prompt.
     #[builder(default, setter(strip_option, into))]
     pub permission_prompt_tool_name: Option<String>,
 
@@ -363,7 +385,15 @@ pub struct ClaudeAgentOptions {
     #[builder(default, setter(strip_option))]
     pub read_timeout_secs: Option<u64>,
 
+    /// Timeout in seconds for the CLI to acknowledge a control request such as
+    /// `interrupt()` or `set_model()` (default: 60s)
+    #[builder(default, setter(strip_option))]
+    pub control_request_timeout_secs: Option<u64>,
+
     /// Callback for tool permission checks
+    ///
+    /// The CLI asks this callback before running any tool that is not already
+    /// allowed. Requires streaming mode (`ClaudeSDKClient`).
     #[builder(default, setter(strip_option))]
     pub can_use_tool: Option<CanUseToolCallback>,
 
@@ -375,6 +405,15 @@ pub struct ClaudeAgentOptions {
     #[builder(default, setter(strip_option, into))]
     pub user: Option<String>,
 
+    /// Store that receives every message of the conversation
+    ///
+    /// `ClaudeSDKClient` writes each message it receives (except partial
+    /// stream events; the CLI replays sent prompts) to the store, with a
+    /// session record keyed by `user`. Store errors are logged and do not
+    /// interrupt the conversation.
+    #[builder(default, setter(strip_option))]
+    pub session_store: Option<SharedSessionStore>,
+
     /// Whether to include partial messages in stream
     #[builder(default)]
     pub include_partial_messages: bool,
@@ -415,6 +454,14 @@ pub struct ClaudeAgentOptions {
     #[builder(default, setter(strip_option))]
     pub output_format: Option<OutputFormat>,
 
+    /// Validation and repair of structured output
+    ///
+    /// When set, `ClaudeSDKClient::ask_structured` and `ask_typed` validate
+    /// each result against `output_format` and send corrective follow-ups
+    /// until the output is valid or the attempts run out.
+    #[builder(default, setter(strip_option))]
+    pub structured_output_policy: Option<StructuredOutputPolicy>,
+
     /// Sandbox configuration for command execution
     #[builder(default, setter(strip_option))]
     pub sandbox: Option<SandboxSettings>,
@@ -461,13 +508,23 @@ pub struct ClaudeAgentOptions {
 
     /// Callback for stderr output
     ///
-    /// Invoked when the Claude CLI writes to stderr. Useful for debugging.
+    /// Invoked once per line the Claude CLI writes to stderr, without the
+    /// trailing newline. When unset, stderr is forwarded to the parent's stderr.
     #[builder(default, setter(strip_option))]
     pub stderr: Option<StderrCallback>,
 
+    /// Number of trailing stderr lines to keep for error reporting (default: 20)
+    ///
+    /// When the CLI exits non-zero, these lines become the `stderr` of the
+    /// returned `ClaudeError::Process`. Set to 0 to disable capture.
+    #[builder(default, setter(strip_option))]
+    pub stderr_capture_lines: Option<usize>,
+
     /// Tools configuration
     ///
     /// Either a list of tool names or a preset (e.g., `ToolsConfig::claude_code_preset()`).
+    /// A list restricts the built-in tools the CLI offers (empty disables them
+    /// all); `allowed_tools` must then only name tools from that list.
     #[builder(default, setter(strip_option))]
     pub tools: Option<ToolsConfig>,
 }
@@ -484,6 +541,10 @@ impl std::fmt::Debug for ClaudeAgentOptions {
             .field("allowed_tools", &self.allowed_tools)
             .field("system_prompt", &self.system_prompt)
             .field("mcp_servers", &self.mcp_servers)
+            .field(
+                "sdk_mcp_servers",
+                &self.sdk_mcp_servers.keys().collect::<Vec<_>>(),
+            )
             .field("permission_mode", &self.permission_mode)
             .field("continue_conversation", &self.continue_conversation)
             .field("resume", &self.resume)
@@ -500,6 +561,10 @@ impl std::fmt::Debug for ClaudeAgentOptions {
             .field("env", &self.env)
             .field("extra_args", &self.extra_args)
             .field("max_buffer_size", &self.max_buffer_size)
+            .field(
+                "control_request_timeout_secs",
+                &self.control_request_timeout_secs,
+            )
             .field(
                 "can_use_tool",
                 &self.can_use_tool.as_ref().map(|_| "<callback>"),
@@ -512,6 +577,10 @@ impl std::fmt::Debug for ClaudeAgentOptions {
                     .map(|h| format!("[{} hook types]", h.len())),
             )
             .field("user", &self.user)
+            .field(
+                "session_store",
+                &self.session_store.as_ref().map(|_| "<store>"),
+            )
             .field("include_partial_messages", &self.include_partial_messages)
             .field("fork_session", &self.fork_session)
             .field("session_id", &self.session_id)
@@ -522,6 +591,7 @@ impl std::fmt::Debug for ClaudeAgentOptions {
             .field("max_thinking_tokens", &self.max_thinking_tokens)
             .field("fallback_model", &self.fallback_model)
             .field("output_format", &self.output_format)
+            .field("structured_output_policy", &self.structured_output_policy)
             .field("sandbox", &self.sandbox)
             .field(
                 "plugins",
@@ -543,6 +613,7 @@ impl std::fmt::Debug for ClaudeAgentOptions {
                 &self.path_to_claude_code_executable,
             )
             .field("stderr", &self.stderr.as_ref().map(|_| "<callback>"))
+            .field("stderr_capture_lines", &self.stderr_capture_lines)
             .field(
                 "tools",
                 &self.tools.as_ref().map(|t| match t {
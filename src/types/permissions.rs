@@ -63,12 +63,17 @@ pub enum PermissionBehavior {
 }
 
 /// Permission rule value
+///
+/// Serialized in the CLI's camelCase form (`toolName`, `ruleContent`);
+/// `snake_case` keys are still accepted when deserializing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PermissionRuleValue {
     /// Name of the tool
+    #[serde(alias = "tool_name")]
     pub tool_name: String,
     /// Optional rule content
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "rule_content", skip_serializing_if = "Option::is_none")]
     pub rule_content: Option<String>,
 }
 
@@ -81,6 +86,9 @@ pub enum PermissionUpdate {
         /// Rules to add
         #[serde(skip_serializing_if = "Option::is_none")]
         rules: Option<Vec<PermissionRuleValue>>,
+        /// Behavior the rules grant (`allow`, `deny` or `ask`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        behavior: Option<PermissionBehavior>,
         /// Where to save the rules
         #[serde(skip_serializing_if = "Option::is_none")]
         destination: Option<PermissionUpdateDestination>,
@@ -90,6 +98,9 @@ pub enum PermissionUpdate {
         /// New rules
         #[serde(skip_serializing_if = "Option::is_none")]
         rules: Option<Vec<PermissionRuleValue>>,
+        /// Behavior the rules grant (`allow`, `deny` or `ask`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        behavior: Option<PermissionBehavior>,
         /// Where to save the rules
         #[serde(skip_serializing_if = "Option::is_none")]
         destination: Option<PermissionUpdateDestination>,
@@ -99,6 +110,9 @@ pub enum PermissionUpdate {
         /// Rules to remove
         #[serde(skip_serializing_if = "Option::is_none")]
         rules: Option<Vec<PermissionRuleValue>>,
+        /// Behavior the rules grant (`allow`, `deny` or `ask`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        behavior: Option<PermissionBehavior>,
         /// Where to remove from
         #[serde(skip_serializing_if = "Option::is_none")]
         destination: Option<PermissionUpdateDestination>,
@@ -139,6 +153,12 @@ pub enum PermissionUpdate {
 pub struct ToolPermissionContext {
     /// Permission suggestions from CLI
     pub suggestions: Vec<PermissionUpdate>,
+    /// Path that triggered the permission check, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_path: Option<String>,
+    /// ID of the tool use being checked, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
     /// Cancellation token for aborting operations (like `AbortSignal` in JS)
     #[serde(skip)]
     pub cancellation_token: Option<CancellationToken>,
@@ -148,6 +168,8 @@ impl std::fmt::Debug for ToolPermissionContext {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ToolPermissionContext")
             .field("suggestions", &self.suggestions)
+            .field("blocked_path", &self.blocked_path)
+            .field("tool_use_id", &self.tool_use_id)
             .field(
                 "cancellation_token",
                 &self.cancellation_token.as_ref().map(|_| "<token>"),
@@ -162,6 +184,8 @@ impl ToolPermissionContext {
     pub fn new(suggestions: Vec<PermissionUpdate>) -> Self {
         Self {
             suggestions,
+            blocked_path: None,
+            tool_use_id: None,
             cancellation_token: None,
         }
     }
@@ -174,6 +198,8 @@ impl ToolPermissionContext {
     ) -> Self {
         Self {
             suggestions,
+            blocked_path: None,
+            tool_use_id: None,
             cancellation_token: Some(cancellation_token),
         }
     }
@@ -229,5 +255,32 @@ pub enum PermissionResult {
     Deny(PermissionResultDeny),
 }
 
+impl PermissionResult {
+    /// Build the `can_use_tool` response payload the CLI expects
+    ///
+    /// An allow result always carries `updatedInput`, falling back to the
+    /// original tool input when the callback did not modify it.
+    #[must_use]
+    pub fn to_cli_response(&self, original_input: &serde_json::Value) -> serde_json::Value {
+        match self {
+            Self::Allow(allow) => {
+                let mut response = serde_json::json!({
+                    "behavior": "allow",
+                    "updatedInput": allow.updated_input.as_ref().unwrap_or(original_input),
+                });
+                if let Some(ref updates) = allow.updated_permissions {
+                    response["updatedPermissions"] = serde_json::json!(updates);
+                }
+                response
+            }
+            Self::Deny(deny) => serde_json::json!({
+                "behavior": "deny",
+                "message": deny.message,
+                "interrupt": deny.interrupt,
+            }),
+        }
+    }
+}
+
 /// Type alias for a shared permission callback using the trait-based approach.
 pub type CanUseToolCallback = Arc<dyn crate::callbacks::PermissionCallback>;
@@ -0,0 +1,215 @@
+//! Stream event types for partial messages
+//!
+//! With `include_partial_messages`, the CLI forwards the raw API stream events
+//! inside `Message::StreamEvent`. [`StreamEvent`] is the typed form of those
+//! events; use [`Message::stream_event`] to parse one, and
+//! [`PartialMessageAccumulator`](crate::message::PartialMessageAccumulator) to
+//! fold them into a growing assistant message.
+
+use serde::{Deserialize, Serialize};
+
+use super::messages::{ContentBlock, Message};
+use super::usage::Usage;
+
+/// Message metadata from a `message_start` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamMessageStart {
+    /// API message ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Model generating the message
+    #[serde(default)]
+    pub model: String,
+    /// Content blocks present at the start (usually empty)
+    #[serde(default)]
+    pub content: Vec<ContentBlock>,
+    /// Initial token usage (input tokens, cache usage)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Incremental update to a content block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentDelta {
+    /// Text appended to a text block
+    TextDelta {
+        /// Text fragment
+        text: String,
+    },
+    /// Thinking appended to a thinking block
+    ThinkingDelta {
+        /// Thinking fragment
+        thinking: String,
+    },
+    /// Signature of a thinking block
+    SignatureDelta {
+        /// Signature (sent once, at the end of the block)
+        signature: String,
+    },
+    /// Fragment of a tool call's input JSON
+    InputJsonDelta {
+        /// Partial JSON; only the concatenation of all fragments is valid JSON
+        partial_json: String,
+    },
+    /// Delta type not known to this SDK version
+    #[serde(other)]
+    Unknown,
+}
+
+/// Top-level changes from a `message_delta` event
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageDeltaInfo {
+    /// Why the model stopped (`end_turn`, `tool_use`, `max_tokens`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    /// Stop sequence that ended generation, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_sequence: Option<String>,
+}
+
+/// Typed API stream event carried by `Message::StreamEvent`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// A new assistant message begins
+    MessageStart {
+        /// Message metadata
+        message: StreamMessageStart,
+    },
+    /// A content block begins at `index`
+    ContentBlockStart {
+        /// Block index within the message
+        index: usize,
+        /// Initial block (empty text, thinking, or `tool_use` with empty input)
+        content_block: ContentBlock,
+    },
+    /// Incremental content for the block at `index`
+    ContentBlockDelta {
+        /// Block index within the message
+        index: usize,
+        /// The update
+        delta: ContentDelta,
+    },
+    /// The block at `index` is complete
+    ContentBlockStop {
+        /// Block index within the message
+        index: usize,
+    },
+    /// Top-level message changes and cumulative usage
+    MessageDelta {
+        /// Stop reason and stop sequence
+        #[serde(default)]
+        delta: MessageDeltaInfo,
+        /// Cumulative usage of the message
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        usage: Option<Usage>,
+    },
+    /// The message is complete
+    MessageStop,
+    /// Keep-alive
+    Ping,
+    /// Error reported mid-stream
+    Error {
+        /// Error details
+        error: serde_json::Value,
+    },
+    /// Event type not known to this SDK version
+    #[serde(other)]
+    Unknown,
+}
+
+impl Message {
+    /// Parse the event of a `StreamEvent` message into a typed [`StreamEvent`]
+    ///
+    /// Returns `None` for other message types, and for events whose payload
+    /// does not match the expected shape.
+    #[must_use]
+    pub fn stream_event(&self) -> Option<StreamEvent> {
+        match self {
+            Self::StreamEvent { event, .. } => serde_json::from_value(event.clone()).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_content_block_events() {
+        let start: StreamEvent = serde_json::from_value(json!({
+            "type": "content_block_start",
+            "index": 1,
+            "content_block": {"type": "tool_use", "id": "toolu_1", "name": "Bash", "input": {}}
+        }))
+        .unwrap();
+        assert!(matches!(
+            start,
+            StreamEvent::ContentBlockStart {
+                index: 1,
+                content_block: ContentBlock::ToolUse { .. }
+            }
+        ));
+
+        let delta: StreamEvent = serde_json::from_value(json!({
+            "type": "content_block_delta",
+            "index": 1,
+            "delta": {"type": "input_json_delta", "partial_json": "{\"comm"}
+        }))
+        .unwrap();
+        match delta {
+            StreamEvent::ContentBlockDelta {
+                delta: ContentDelta::InputJsonDelta { partial_json },
+                ..
+            } => assert_eq!(partial_json, "{\"comm"),
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        // Thinking blocks start without a signature
+        let thinking: StreamEvent = serde_json::from_value(json!({
+            "type": "content_block_start",
+            "index": 0,
+            "content_block": {"type": "thinking", "thinking": ""}
+        }))
+        .unwrap();
+        assert!(matches!(thinking, StreamEvent::ContentBlockStart { .. }));
+    }
+
+    #[test]
+    fn test_parse_message_delta_and_unknown_types() {
+        let delta: StreamEvent = serde_json::from_value(json!({
+            "type": "message_delta",
+            "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+            "usage": {"output_tokens": 15}
+        }))
+        .unwrap();
+        match delta {
+            StreamEvent::MessageDelta { delta, usage } => {
+                assert_eq!(delta.stop_reason.as_deref(), Some("end_turn"));
+                assert_eq!(usage.unwrap().output_tokens, 15);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let unknown: StreamEvent =
+            serde_json::from_value(json!({"type": "brand_new_event", "x": 1})).unwrap();
+        assert!(matches!(unknown, StreamEvent::Unknown));
+
+        let unknown_delta: StreamEvent = serde_json::from_value(json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "citations_delta", "citation": {}}
+        }))
+        .unwrap();
+        assert!(matches!(
+            unknown_delta,
+            StreamEvent::ContentBlockDelta {
+                delta: ContentDelta::Unknown,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+//! Typed system message events
+//!
+//! `Message::System` carries a `subtype` and untyped data. [`SystemEvent`]
+//! gives the known subtypes typed payloads; use [`Message::as_system_event`]
+//! to convert. Subtypes this SDK version does not know, or payloads that do
+//! not match the expected shape, become [`SystemEvent::Unknown`].
+
+use serde::{Deserialize, Serialize};
+
+use super::hooks::CompactTrigger;
+use super::introspection::SessionInfo;
+use super::messages::Message;
+
+/// Metadata of a `compact_boundary` system message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactBoundary {
+    /// Whether compaction was triggered manually (`/compact`) or automatically
+    #[serde(default = "default_compact_trigger")]
+    pub trigger: CompactTrigger,
+    /// Context size in tokens before compaction
+    #[serde(default)]
+    pub pre_tokens: u64,
+}
+
+fn default_compact_trigger() -> CompactTrigger {
+    CompactTrigger::Auto
+}
+
+/// Payload of a `status` system message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemStatus {
+    /// Current status (e.g. "compacting"), `None` when the CLI is back to normal
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Permission mode, when the status change reports one
+    #[serde(
+        default,
+        rename = "permissionMode",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub permission_mode: Option<String>,
+}
+
+impl SystemStatus {
+    /// Whether the CLI is compacting the conversation
+    #[must_use]
+    pub fn is_compacting(&self) -> bool {
+        self.status.as_deref() == Some("compacting")
+    }
+}
+
+/// Payload of a `hook_response` system message (output of a settings hook)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookResponse {
+    /// Name of the hook that ran
+    #[serde(default)]
+    pub hook_name: String,
+    /// Hook event that triggered it (e.g. "`SessionStart`")
+    #[serde(default)]
+    pub hook_event: String,
+    /// Standard output of the hook command
+    #[serde(default)]
+    pub stdout: String,
+    /// Standard error of the hook command
+    #[serde(default)]
+    pub stderr: String,
+    /// Exit code of the hook command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+/// Typed form of a `Message::System`
+#[derive(Debug, Clone)]
+pub enum SystemEvent {
+    /// Session initialized (`init`): model, tools, MCP servers, cwd
+    Init(SessionInfo),
+    /// The conversation was compacted (`compact_boundary`)
+    CompactBoundary(CompactBoundary),
+    /// CLI status changed (`status`)
+    Status(SystemStatus),
+    /// A settings hook produced output (`hook_response`)
+    HookResponse(HookResponse),
+    /// Subtype not known to this SDK version, or an unexpected payload
+    Unknown {
+        /// System message subtype
+        subtype: String,
+        /// Raw system message data
+        data: serde_json::Value,
+    },
+}
+
+impl SystemEvent {
+    /// Build the typed event for a system message subtype and its data
+    #[must_use]
+    pub fn from_parts(subtype: &str, data: &serde_json::Value) -> Self {
+        let typed = match subtype {
+            "init" => Some(Self::Init(SessionInfo::from_init_data(data))),
+            "compact_boundary" => {
+                let metadata = data
+                    .get("compact_metadata")
+                    .cloned()
+                    .unwrap_or_else(|| serde_json::json!({}));
+                serde_json::from_value(metadata)
+                    .ok()
+                    .map(Self::CompactBoundary)
+            }
+            "status" => serde_json::from_value(data.clone()).ok().map(Self::Status),
+            "hook_response" => serde_json::from_value(data.clone())
+                .ok()
+                .map(Self::HookResponse),
+            _ => None,
+        };
+        typed.unwrap_or_else(|| Self::Unknown {
+            subtype: subtype.to_string(),
+            data: data.clone(),
+        })
+    }
+}
+
+impl Message {
+    /// Typed view of a `System` message, `None` for other message types
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use anthropic_agent_sdk::Message;
+    /// use anthropic_agent_sdk::types::SystemEvent;
+    ///
+    /// let message: Message = serde_json::from_value(serde_json::json!({
+    ///     "type": "system",
+    ///     "subtype": "compact_boundary",
+    ///     "compact_metadata": {"trigger": "auto", "pre_tokens": 150000}
+    /// }))
+    /// .unwrap();
+    ///
+    /// if let Some(SystemEvent::CompactBoundary(boundary)) = message.as_system_event() {
+    ///     assert_eq!(boundary.pre_tokens, 150000);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn as_system_event(&self) -> Option<SystemEvent> {
+        match self {
+            Self::System { subtype, data } => Some(SystemEvent::from_parts(subtype, data)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn system(value: serde_json::Value) -> Message {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_init_and_compact_boundary() {
+        let init = system(json!({
+            "type": "system",
+            "subtype": "init",
+            "session_id": "s1",
+            "model": "claude-sonnet-4-5",
+            "cwd": "/work",
+            "tools": ["Bash", "mcp__app__lookup"],
+            "mcp_servers": [{"name": "app", "status": "connected"}]
+        }));
+        match init.as_system_event() {
+            Some(SystemEvent::Init(info)) => {
+                assert_eq!(info.model.as_deref(), Some("claude-sonnet-4-5"));
+                assert_eq!(info.mcp_server("app").unwrap().tools, vec!["lookup"]);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let compact = system(json!({
+            "type": "system",
+            "subtype": "compact_boundary",
+            "compact_metadata": {"trigger": "manual", "pre_tokens": 90000}
+        }));
+        match compact.as_system_event() {
+            Some(SystemEvent::CompactBoundary(boundary)) => {
+                assert_eq!(boundary.trigger, CompactTrigger::Manual);
+                assert_eq!(boundary.pre_tokens, 90000);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        // Missing metadata defaults to an automatic compaction
+        let bare = system(json!({"type": "system", "subtype": "compact_boundary"}));
+        match bare.as_system_event() {
+            Some(SystemEvent::CompactBoundary(boundary)) => {
+                assert_eq!(boundary.trigger, CompactTrigger::Auto);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_status_and_hook_response() {
+        let status = system(json!({"type": "system", "subtype": "status", "status": "compacting"}));
+        match status.as_system_event() {
+            Some(SystemEvent::Status(status)) => assert!(status.is_compacting()),
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let cleared = system(json!({"type": "system", "subtype": "status", "status": null}));
+        match cleared.as_system_event() {
+            Some(SystemEvent::Status(status)) => assert!(!status.is_compacting()),
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let hook = system(json!({
+            "type": "system",
+            "subtype": "hook_response",
+            "hook_name": "SessionStart:startup",
+            "hook_event": "SessionStart",
+            "stdout": "loaded",
+            "stderr": "",
+            "exit_code": 0
+        }));
+        match hook.as_system_event() {
+            Some(SystemEvent::HookResponse(response)) => {
+                assert_eq!(response.hook_event, "SessionStart");
+                assert_eq!(response.stdout, "loaded");
+                assert_eq!(response.exit_code, Some(0));
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_subtypes_and_non_system_messages() {
+        let future = system(json!({"type": "system", "subtype": "brand_new", "x": 1}));
+        match future.as_system_event() {
+            Some(SystemEvent::Unknown { subtype, data }) => {
+                assert_eq!(subtype, "brand_new");
+                assert_eq!(data["x"], 1);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        // Known subtype with an unexpected payload falls back to Unknown
+        let malformed = system(json!({
+            "type": "system",
+            "subtype": "compact_boundary",
+            "compact_metadata": {"trigger": 5}
+        }));
+        assert!(matches!(
+            malformed.as_system_event(),
+            Some(SystemEvent::Unknown { .. })
+        ));
+
+        let result = system(json!({
+            "type": "result", "subtype": "success", "duration_ms": 1, "duration_api_ms": 1,
+            "is_error": false, "num_turns": 1, "session_id": "s1"
+        }));
+        assert!(result.as_system_event().is_none());
+    }
+}
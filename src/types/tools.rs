@@ -0,0 +1,888 @@
+//! Typed inputs and outputs of built-in Claude Code tools
+//!
+//! `ContentBlock::ToolUse::input`, permission callbacks and hooks carry tool
+//! input as `serde_json::Value`. [`BuiltinToolInput::parse`] turns it into a
+//! typed struct for the built-in tools, and [`BuiltinToolOutput::parse`] does
+//! the same for the structured result the CLI attaches to tool result
+//! messages (see [`Message::tool_use_result`](super::messages::Message::tool_use_result)).
+//! Tools without a typed model (MCP tools, newer tools) become `Other`.
+
+use serde::{Deserialize, Serialize};
+
+use super::messages::{AskUserQuestionInput, AskUserQuestionOutput};
+use crate::error::{ClaudeError, Result};
+
+// ============================================================================
+// Tool Inputs
+// ============================================================================
+
+/// Input for the `Bash` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BashInput {
+    /// Command to execute
+    pub command: String,
+    /// Timeout in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Short description of what the command does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Run the command in the background
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_in_background: Option<bool>,
+}
+
+/// Input for the `Read` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadInput {
+    /// Absolute path of the file to read
+    pub file_path: String,
+    /// Line number to start reading from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Number of lines to read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+/// Input for the `Write` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WriteInput {
+    /// Absolute path of the file to write
+    pub file_path: String,
+    /// Content to write
+    pub content: String,
+}
+
+/// Input for the `Edit` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditInput {
+    /// Absolute path of the file to edit
+    pub file_path: String,
+    /// Text to replace
+    pub old_string: String,
+    /// Replacement text
+    pub new_string: String,
+    /// Replace every occurrence instead of exactly one
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// One edit of a `MultiEdit` call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditOperation {
+    /// Text to replace
+    pub old_string: String,
+    /// Replacement text
+    pub new_string: String,
+    /// Replace every occurrence instead of exactly one
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// Input for the `MultiEdit` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiEditInput {
+    /// Absolute path of the file to edit
+    pub file_path: String,
+    /// Edits applied in order
+    pub edits: Vec<EditOperation>,
+}
+
+/// Input for the `Glob` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GlobInput {
+    /// Glob pattern (e.g. `**/*.rs`)
+    pub pattern: String,
+    /// Directory to search in (defaults to the working directory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Output mode of the `Grep` tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrepOutputMode {
+    /// Matching lines
+    Content,
+    /// Paths of matching files
+    FilesWithMatches,
+    /// Match counts per file
+    Count,
+}
+
+/// Input for the `Grep` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrepInput {
+    /// Regular expression to search for
+    pub pattern: String,
+    /// File or directory to search in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Glob filter for file names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    /// File type filter (e.g. `rust`)
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+    /// Output mode (defaults to `files_with_matches`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_mode: Option<GrepOutputMode>,
+    /// Case insensitive search
+    #[serde(default, rename = "-i", skip_serializing_if = "Option::is_none")]
+    pub case_insensitive: Option<bool>,
+    /// Show line numbers
+    #[serde(default, rename = "-n", skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<bool>,
+    /// Lines of context after each match
+    #[serde(default, rename = "-A", skip_serializing_if = "Option::is_none")]
+    pub after_context: Option<u32>,
+    /// Lines of context before each match
+    #[serde(default, rename = "-B", skip_serializing_if = "Option::is_none")]
+    pub before_context: Option<u32>,
+    /// Lines of context around each match
+    #[serde(default, rename = "-C", skip_serializing_if = "Option::is_none")]
+    pub context: Option<u32>,
+    /// Limit the output to the first N entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_limit: Option<u32>,
+    /// Let patterns span lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiline: Option<bool>,
+}
+
+/// Input for the `WebFetch` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebFetchInput {
+    /// URL to fetch
+    pub url: String,
+    /// Prompt to run on the fetched content
+    pub prompt: String,
+}
+
+/// Input for the `WebSearch` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebSearchInput {
+    /// Search query
+    pub query: String,
+    /// Only include results from these domains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    /// Never include results from these domains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_domains: Option<Vec<String>>,
+}
+
+/// Input for the `Task` tool (subagent invocation)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskInput {
+    /// Short description of the task
+    pub description: String,
+    /// Task prompt for the subagent
+    pub prompt: String,
+    /// Subagent type (e.g. `general-purpose`)
+    pub subagent_type: String,
+}
+
+/// Status of a todo item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    /// Not started
+    Pending,
+    /// Being worked on
+    InProgress,
+    /// Done
+    Completed,
+}
+
+/// Todo list entry of the `TodoWrite` tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoItem {
+    /// Task description (imperative form)
+    pub content: String,
+    /// Current status
+    pub status: TodoStatus,
+    /// Task description shown while in progress
+    #[serde(rename = "activeForm")]
+    pub active_form: String,
+}
+
+/// Input for the `TodoWrite` tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoWriteInput {
+    /// The complete, updated todo list
+    pub todos: Vec<TodoItem>,
+}
+
+/// Notebook cell type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotebookCellType {
+    /// Code cell
+    Code,
+    /// Markdown cell
+    Markdown,
+}
+
+/// Kind of notebook edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotebookEditMode {
+    /// Replace the cell source
+    Replace,
+    /// Insert a new cell
+    Insert,
+    /// Delete the cell
+    Delete,
+}
+
+/// Input for the `NotebookEdit` tool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotebookEditInput {
+    /// Absolute path of the notebook
+    pub notebook_path: String,
+    /// New cell source
+    pub new_source: String,
+    /// ID of the cell to edit (or to insert after)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_id: Option<String>,
+    /// Cell type (required when inserting)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_type: Option<NotebookCellType>,
+    /// Edit mode (defaults to `replace`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edit_mode: Option<NotebookEditMode>,
+}
+
+/// Typed input of a built-in tool call
+#[derive(Debug, Clone)]
+pub enum BuiltinToolInput {
+    /// `Bash`
+    Bash(BashInput),
+    /// `Read`
+    Read(ReadInput),
+    /// `Write`
+    Write(WriteInput),
+    /// `Edit`
+    Edit(EditInput),
+    /// `MultiEdit`
+    MultiEdit(MultiEditInput),
+    /// `Glob`
+    Glob(GlobInput),
+    /// `Grep`
+    Grep(GrepInput),
+    /// `WebFetch`
+    WebFetch(WebFetchInput),
+    /// `WebSearch`
+    WebSearch(WebSearchInput),
+    /// `Task`
+    Task(TaskInput),
+    /// `TodoWrite`
+    TodoWrite(TodoWriteInput),
+    /// `NotebookEdit`
+    NotebookEdit(NotebookEditInput),
+    /// `AskUserQuestion`
+    AskUserQuestion(AskUserQuestionInput),
+    /// Any other tool (MCP tools, tools without a typed model)
+    Other {
+        /// Tool name
+        name: String,
+        /// Raw tool input
+        input: serde_json::Value,
+    },
+}
+
+/// Tools with typed inputs and outputs
+const TYPED_TOOLS: &[&str] = &[
+    "Bash",
+    "Read",
+    "Write",
+    "Edit",
+    "MultiEdit",
+    "Glob",
+    "Grep",
+    "WebFetch",
+    "WebSearch",
+    "Task",
+    "TodoWrite",
+    "NotebookEdit",
+    "AskUserQuestion",
+];
+
+/// Deserialize a built-in tool payload, reporting the tool name on mismatch
+fn parse_payload<T: serde::de::DeserializeOwned>(
+    name: &str,
+    what: &str,
+    value: &serde_json::Value,
+) -> Result<T> {
+    serde_json::from_value(value.clone()).map_err(|e| {
+        ClaudeError::message_parse(
+            format!("Invalid {name} tool {what}: {e}"),
+            Some(value.clone()),
+        )
+    })
+}
+
+impl BuiltinToolInput {
+    /// Parse the input of a call to tool `name`
+    ///
+    /// Unknown tool names yield [`BuiltinToolInput::Other`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::MessageParse` if `name` is a built-in tool but
+    /// `input` does not match its schema.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use anthropic_agent_sdk::types::BuiltinToolInput;
+    ///
+    /// let input = serde_json::json!({"command": "cargo test", "timeout": 60000});
+    /// if let BuiltinToolInput::Bash(bash) = BuiltinToolInput::parse("Bash", &input).unwrap() {
+    ///     assert_eq!(bash.command, "cargo test");
+    /// }
+    /// ```
+    pub fn parse(name: &str, input: &serde_json::Value) -> Result<Self> {
+        let parsed = match name {
+            "Bash" => Self::Bash(parse_payload(name, "input", input)?),
+            "Read" => Self::Read(parse_payload(name, "input", input)?),
+            "Write" => Self::Write(parse_payload(name, "input", input)?),
+            "Edit" => Self::Edit(parse_payload(name, "input", input)?),
+            "MultiEdit" => Self::MultiEdit(parse_payload(name, "input", input)?),
+            "Glob" => Self::Glob(parse_payload(name, "input", input)?),
+            "Grep" => Self::Grep(parse_payload(name, "input", input)?),
+            "WebFetch" => Self::WebFetch(parse_payload(name, "input", input)?),
+            "WebSearch" => Self::WebSearch(parse_payload(name, "input", input)?),
+            "Task" => Self::Task(parse_payload(name, "input", input)?),
+            "TodoWrite" => Self::TodoWrite(parse_payload(name, "input", input)?),
+            "NotebookEdit" => Self::NotebookEdit(parse_payload(name, "input", input)?),
+            "AskUserQuestion" => Self::AskUserQuestion(parse_payload(name, "input", input)?),
+            _ => Self::Other {
+                name: name.to_string(),
+                input: input.clone(),
+            },
+        };
+        Ok(parsed)
+    }
+
+    /// Name of the tool
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Bash(_) => "Bash",
+            Self::Read(_) => "Read",
+            Self::Write(_) => "Write",
+            Self::Edit(_) => "Edit",
+            Self::MultiEdit(_) => "MultiEdit",
+            Self::Glob(_) => "Glob",
+            Self::Grep(_) => "Grep",
+            Self::WebFetch(_) => "WebFetch",
+            Self::WebSearch(_) => "WebSearch",
+            Self::Task(_) => "Task",
+            Self::TodoWrite(_) => "TodoWrite",
+            Self::NotebookEdit(_) => "NotebookEdit",
+            Self::AskUserQuestion(_) => "AskUserQuestion",
+            Self::Other { name, .. } => name,
+        }
+    }
+
+    /// File the tool reads or modifies (`Read`, `Write`, `Edit`, `MultiEdit`, `NotebookEdit`)
+    #[must_use]
+    pub fn file_path(&self) -> Option<&str> {
+        match self {
+            Self::Read(input) => Some(&input.file_path),
+            Self::Write(input) => Some(&input.file_path),
+            Self::Edit(input) => Some(&input.file_path),
+            Self::MultiEdit(input) => Some(&input.file_path),
+            Self::NotebookEdit(input) => Some(&input.notebook_path),
+            _ => None,
+        }
+    }
+
+    /// Whether the tool modifies files (`Write`, `Edit`, `MultiEdit`, `NotebookEdit`)
+    #[must_use]
+    pub fn is_file_modification(&self) -> bool {
+        matches!(
+            self,
+            Self::Write(_) | Self::Edit(_) | Self::MultiEdit(_) | Self::NotebookEdit(_)
+        )
+    }
+}
+
+// ============================================================================
+// Tool Outputs
+// ============================================================================
+
+/// Result of the `Bash` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BashOutput {
+    /// Standard output
+    #[serde(default)]
+    pub stdout: String,
+    /// Standard error
+    #[serde(default)]
+    pub stderr: String,
+    /// Whether the command was interrupted
+    #[serde(default)]
+    pub interrupted: bool,
+    /// Whether stdout is image data
+    #[serde(default)]
+    pub is_image: bool,
+    /// ID of the background shell (when run in the background)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_task_id: Option<String>,
+}
+
+/// File read by the `Read` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadFile {
+    /// Path of the file
+    #[serde(default)]
+    pub file_path: String,
+    /// Content that was read
+    #[serde(default)]
+    pub content: String,
+    /// Number of lines returned
+    #[serde(default)]
+    pub num_lines: u64,
+    /// First line returned (1-based)
+    #[serde(default)]
+    pub start_line: u64,
+    /// Total lines in the file
+    #[serde(default)]
+    pub total_lines: u64,
+}
+
+/// Result of the `Read` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadOutput {
+    /// Kind of content read (`text`, `image`, `notebook`, `pdf`)
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Text file details (for `text` reads)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<ReadFile>,
+}
+
+/// Result of the `Write` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteOutput {
+    /// `create` for a new file, `update` for an overwrite
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Path of the file
+    pub file_path: String,
+    /// Content written
+    #[serde(default)]
+    pub content: String,
+    /// Diff hunks of the change
+    #[serde(default)]
+    pub structured_patch: Vec<serde_json::Value>,
+}
+
+/// Result of the `Edit` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditOutput {
+    /// Path of the file
+    pub file_path: String,
+    /// Text that was replaced
+    #[serde(default)]
+    pub old_string: String,
+    /// Replacement text
+    #[serde(default)]
+    pub new_string: String,
+    /// Whether every occurrence was replaced
+    #[serde(default)]
+    pub replace_all: bool,
+    /// Whether the user modified the proposed edit
+    #[serde(default)]
+    pub user_modified: bool,
+    /// Diff hunks of the change
+    #[serde(default)]
+    pub structured_patch: Vec<serde_json::Value>,
+}
+
+/// Result of the `MultiEdit` tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiEditOutput {
+    /// Path of the file
+    pub file_path: String,
+    /// Edits that were applied
+    #[serde(default)]
+    pub edits: Vec<EditOperation>,
+    /// Whether the user modified the proposed edits
+    #[serde(default)]
+    pub user_modified: bool,
+    /// Diff hunks of the change
+    #[serde(default)]
+    pub structured_patch: Vec<serde_json::Value>,
+}
+
+/// Result of the `Glob` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobOutput {
+    /// Matching paths
+    #[serde(default)]
+    pub filenames: Vec<String>,
+    /// Number of matches
+    #[serde(default)]
+    pub num_files: u64,
+    /// Whether the result was truncated
+    #[serde(default)]
+    pub truncated: bool,
+    /// Search duration in milliseconds
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+/// Result of the `Grep` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrepOutput {
+    /// Output mode that was used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<GrepOutputMode>,
+    /// Matching files
+    #[serde(default)]
+    pub filenames: Vec<String>,
+    /// Number of matching files
+    #[serde(default)]
+    pub num_files: u64,
+    /// Matching lines (`content` mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Number of lines in `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_lines: Option<u64>,
+}
+
+/// Result of the `WebFetch` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebFetchOutput {
+    /// URL that was fetched
+    #[serde(default)]
+    pub url: String,
+    /// HTTP status code
+    #[serde(default)]
+    pub code: u16,
+    /// HTTP status text
+    #[serde(default)]
+    pub code_text: String,
+    /// Response of the prompt run on the fetched content
+    #[serde(default)]
+    pub result: String,
+    /// Size of the fetched content in bytes
+    #[serde(default)]
+    pub bytes: u64,
+    /// Fetch duration in milliseconds
+    #[serde(default)]
+    pub duration_ms: u64,
+}
+
+/// Result of the `WebSearch` tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSearchOutput {
+    /// Query that was run
+    #[serde(default)]
+    pub query: String,
+    /// Search results (links and model commentary)
+    #[serde(default)]
+    pub results: Vec<serde_json::Value>,
+    /// Search duration in seconds
+    #[serde(default)]
+    pub duration_seconds: f64,
+}
+
+/// Result of the `Task` tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOutput {
+    /// Final response of the subagent (content blocks)
+    #[serde(default)]
+    pub content: Vec<serde_json::Value>,
+    /// Total duration in milliseconds
+    #[serde(default)]
+    pub total_duration_ms: u64,
+    /// Total tokens used by the subagent
+    #[serde(default)]
+    pub total_tokens: u64,
+    /// Number of tool calls made by the subagent
+    #[serde(default)]
+    pub total_tool_use_count: u64,
+}
+
+impl TaskOutput {
+    /// Concatenated text of the subagent's final response
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Result of the `TodoWrite` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoWriteOutput {
+    /// Todo list before the update
+    #[serde(default)]
+    pub old_todos: Vec<TodoItem>,
+    /// Todo list after the update
+    #[serde(default)]
+    pub new_todos: Vec<TodoItem>,
+}
+
+/// Result of the `NotebookEdit` tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotebookEditOutput {
+    /// Path of the notebook
+    #[serde(default)]
+    pub notebook_path: String,
+    /// ID of the edited cell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_id: Option<String>,
+    /// New cell source
+    #[serde(default)]
+    pub new_source: String,
+    /// Cell type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_type: Option<NotebookCellType>,
+    /// Edit mode that was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edit_mode: Option<NotebookEditMode>,
+    /// Error message, if the edit failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Typed result of a built-in tool call
+#[derive(Debug, Clone)]
+pub enum BuiltinToolOutput {
+    /// `Bash`
+    Bash(BashOutput),
+    /// `Read`
+    Read(ReadOutput),
+    /// `Write`
+    Write(WriteOutput),
+    /// `Edit`
+    Edit(EditOutput),
+    /// `MultiEdit`
+    MultiEdit(MultiEditOutput),
+    /// `Glob`
+    Glob(GlobOutput),
+    /// `Grep`
+    Grep(GrepOutput),
+    /// `WebFetch`
+    WebFetch(WebFetchOutput),
+    /// `WebSearch`
+    WebSearch(WebSearchOutput),
+    /// `Task`
+    Task(TaskOutput),
+    /// `TodoWrite`
+    TodoWrite(TodoWriteOutput),
+    /// `NotebookEdit`
+    NotebookEdit(NotebookEditOutput),
+    /// `AskUserQuestion`
+    AskUserQuestion(AskUserQuestionOutput),
+    /// Failed tool call; the CLI reports the error as a plain string
+    Error(String),
+    /// Any other tool (MCP tools, tools without a typed model)
+    Other {
+        /// Tool name
+        name: String,
+        /// Raw tool result
+        output: serde_json::Value,
+    },
+}
+
+impl BuiltinToolOutput {
+    /// Parse the structured result of a call to tool `name`
+    ///
+    /// `output` is the `tool_use_result` the CLI attaches to the user message
+    /// carrying the tool result. A string result of a built-in tool is an
+    /// error message and yields [`BuiltinToolOutput::Error`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClaudeError::MessageParse` if `name` is a built-in tool but
+    /// `output` does not match its result shape.
+    pub fn parse(name: &str, output: &serde_json::Value) -> Result<Self> {
+        if TYPED_TOOLS.contains(&name) {
+            if let Some(message) = output.as_str() {
+                return Ok(Self::Error(message.to_string()));
+            }
+        }
+
+        let parsed = match name {
+            "Bash" => Self::Bash(parse_payload(name, "result", output)?),
+            "Read" => Self::Read(parse_payload(name, "result", output)?),
+            "Write" => Self::Write(parse_payload(name, "result", output)?),
+            "Edit" => Self::Edit(parse_payload(name, "result", output)?),
+            "MultiEdit" => Self::MultiEdit(parse_payload(name, "result", output)?),
+            "Glob" => Self::Glob(parse_payload(name, "result", output)?),
+            "Grep" => Self::Grep(parse_payload(name, "result", output)?),
+            "WebFetch" => Self::WebFetch(parse_payload(name, "result", output)?),
+            "WebSearch" => Self::WebSearch(parse_payload(name, "result", output)?),
+            "Task" => Self::Task(parse_payload(name, "result", output)?),
+            "TodoWrite" => Self::TodoWrite(parse_payload(name, "result", output)?),
+            "NotebookEdit" => Self::NotebookEdit(parse_payload(name, "result", output)?),
+            "AskUserQuestion" => Self::AskUserQuestion(parse_payload(name, "result", output)?),
+            _ => Self::Other {
+                name: name.to_string(),
+                output: output.clone(),
+            },
+        };
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_builtin_inputs() {
+        let bash = BuiltinToolInput::parse(
+            "Bash",
+            &json!({"command": "ls -la", "description": "List files"}),
+        )
+        .unwrap();
+        match &bash {
+            BuiltinToolInput::Bash(input) => {
+                assert_eq!(input.command, "ls -la");
+                assert_eq!(input.timeout, None);
+            }
+            other => panic!("unexpected input {other:?}"),
+        }
+        assert_eq!(bash.name(), "Bash");
+        assert_eq!(bash.file_path(), None);
+
+        let edit = BuiltinToolInput::parse(
+            "Edit",
+            &json!({"file_path": "/src/lib.rs", "old_string": "a", "new_string": "b"}),
+        )
+        .unwrap();
+        assert_eq!(edit.file_path(), Some("/src/lib.rs"));
+        assert!(edit.is_file_modification());
+
+        let grep = BuiltinToolInput::parse(
+            "Grep",
+            &json!({"pattern": "fn main", "output_mode": "content", "-n": true, "type": "rust"}),
+        )
+        .unwrap();
+        match grep {
+            BuiltinToolInput::Grep(input) => {
+                assert_eq!(input.output_mode, Some(GrepOutputMode::Content));
+                assert_eq!(input.line_numbers, Some(true));
+                assert_eq!(input.file_type.as_deref(), Some("rust"));
+            }
+            other => panic!("unexpected input {other:?}"),
+        }
+
+        let todos = BuiltinToolInput::parse(
+            "TodoWrite",
+            &json!({"todos": [
+                {"content": "Run tests", "status": "in_progress", "activeForm": "Running tests"}
+            ]}),
+        )
+        .unwrap();
+        match todos {
+            BuiltinToolInput::TodoWrite(input) => {
+                assert_eq!(input.todos[0].status, TodoStatus::InProgress);
+            }
+            other => panic!("unexpected input {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_unknown_and_invalid_inputs() {
+        let mcp = BuiltinToolInput::parse("mcp__app__lookup", &json!({"id": 7})).unwrap();
+        match &mcp {
+            BuiltinToolInput::Other { name, input } => {
+                assert_eq!(name, "mcp__app__lookup");
+                assert_eq!(input["id"], 7);
+            }
+            other => panic!("unexpected input {other:?}"),
+        }
+        assert_eq!(mcp.name(), "mcp__app__lookup");
+
+        let err = BuiltinToolInput::parse("Read", &json!({"path": "/x"})).unwrap_err();
+        assert!(matches!(err, ClaudeError::MessageParse { .. }));
+        assert!(err.to_string().contains("Read"));
+    }
+
+    #[test]
+    fn test_parse_builtin_outputs() {
+        let bash = BuiltinToolOutput::parse(
+            "Bash",
+            &json!({"stdout": "ok\n", "stderr": "", "interrupted": false, "isImage": false}),
+        )
+        .unwrap();
+        match bash {
+            BuiltinToolOutput::Bash(output) => assert_eq!(output.stdout, "ok\n"),
+            other => panic!("unexpected output {other:?}"),
+        }
+
+        let read = BuiltinToolOutput::parse(
+            "Read",
+            &json!({"type": "text", "file": {
+                "filePath": "/a.rs", "content": "fn a() {}", "numLines": 1, "startLine": 1, "totalLines": 1
+            }}),
+        )
+        .unwrap();
+        match read {
+            BuiltinToolOutput::Read(output) => {
+                assert_eq!(output.file.unwrap().content, "fn a() {}");
+            }
+            other => panic!("unexpected output {other:?}"),
+        }
+
+        let glob = BuiltinToolOutput::parse(
+            "Glob",
+            &json!({"filenames": ["a.rs"], "numFiles": 1, "truncated": false, "durationMs": 3}),
+        )
+        .unwrap();
+        assert!(matches!(
+            glob,
+            BuiltinToolOutput::Glob(GlobOutput { num_files: 1, .. })
+        ));
+
+        let failed = BuiltinToolOutput::parse("Edit", &json!("Error: file not found")).unwrap();
+        assert!(matches!(failed, BuiltinToolOutput::Error(ref m) if m.contains("not found")));
+
+        let mcp = BuiltinToolOutput::parse("mcp__app__lookup", &json!("plain text")).unwrap();
+        assert!(matches!(mcp, BuiltinToolOutput::Other { .. }));
+    }
+
+    #[test]
+    fn test_tool_use_result_from_user_message() {
+        let message: crate::types::Message = serde_json::from_value(json!({
+            "type": "user",
+            "message": {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "a.rs"}
+            ]},
+            "tool_use_result": {"filenames": ["a.rs"], "numFiles": 1, "truncated": false, "durationMs": 2}
+        }))
+        .unwrap();
+
+        let result = message.tool_use_result().unwrap();
+        match BuiltinToolOutput::parse("Glob", result).unwrap() {
+            BuiltinToolOutput::Glob(output) => assert_eq!(output.filenames, vec!["a.rs"]),
+            other => panic!("unexpected output {other:?}"),
+        }
+    }
+}
@@ -1,6 +1,224 @@
-//! Usage data types for OAuth/Max Plan users
+//! Usage types: token usage reported by the CLI, and plan usage for OAuth/Max users
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::messages::Message;
+use super::stream::StreamEvent;
+
+// ============================================================================
+// Token Usage
+// ============================================================================
+
+/// Deserialize a token count, treating `null` as zero
+fn nullable_count<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Server-side tool usage counts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerToolUse {
+    /// Number of web search requests made
+    #[serde(default, deserialize_with = "nullable_count")]
+    pub web_search_requests: u64,
+    /// Number of web fetch requests made
+    #[serde(default, deserialize_with = "nullable_count")]
+    pub web_fetch_requests: u64,
+}
+
+/// Token usage of an API response or a whole turn
+///
+/// Reported on `Message::Result` (aggregate), on assistant messages, and in
+/// `message_start`/`message_delta` stream events. Use [`Message::usage`] to
+/// extract it from any of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Number of input tokens consumed
+    #[serde(default, deserialize_with = "nullable_count")]
+    pub input_tokens: u64,
+    /// Number of output tokens generated
+    #[serde(default, deserialize_with = "nullable_count")]
+    pub output_tokens: u64,
+    /// Tokens read from cache
+    #[serde(default, deserialize_with = "nullable_count")]
+    pub cache_read_input_tokens: u64,
+    /// Tokens used to create cache
+    #[serde(default, deserialize_with = "nullable_count")]
+    pub cache_creation_input_tokens: u64,
+    /// Server-side tool usage (web search, web fetch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_tool_use: Option<ServerToolUse>,
+    /// Service tier the request ran on (e.g. "standard")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
+}
+
+impl Usage {
+    /// Calculate total tokens (input + output)
+    #[must_use]
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens.saturating_add(self.output_tokens)
+    }
+
+    /// Calculate effective input tokens (including cache)
+    #[must_use]
+    pub fn effective_input_tokens(&self) -> u64 {
+        self.input_tokens
+            .saturating_add(self.cache_read_input_tokens)
+            .saturating_add(self.cache_creation_input_tokens)
+    }
+
+    /// Add another usage to this one
+    pub fn add(&mut self, other: &Self) {
+        self.input_tokens = self.input_tokens.saturating_add(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(other.output_tokens);
+        self.cache_read_input_tokens = self
+            .cache_read_input_tokens
+            .saturating_add(other.cache_read_input_tokens);
+        self.cache_creation_input_tokens = self
+            .cache_creation_input_tokens
+            .saturating_add(other.cache_creation_input_tokens);
+        if let Some(ref other_tools) = other.server_tool_use {
+            let tools = self.server_tool_use.get_or_insert_with(Default::default);
+            tools.web_search_requests = tools
+                .web_search_requests
+                .saturating_add(other_tools.web_search_requests);
+            tools.web_fetch_requests = tools
+                .web_fetch_requests
+                .saturating_add(other_tools.web_fetch_requests);
+        }
+        if other.service_tier.is_some() {
+            self.service_tier.clone_from(&other.service_tier);
+        }
+    }
+
+    /// Fold a later snapshot of the same API response into this one
+    ///
+    /// Counts in `message_delta` events and repeated assistant messages are
+    /// cumulative, so each count keeps its highest value.
+    pub fn update(&mut self, snapshot: &Self) {
+        self.input_tokens = self.input_tokens.max(snapshot.input_tokens);
+        self.output_tokens = self.output_tokens.max(snapshot.output_tokens);
+        self.cache_read_input_tokens = self
+            .cache_read_input_tokens
+            .max(snapshot.cache_read_input_tokens);
+        self.cache_creation_input_tokens = self
+            .cache_creation_input_tokens
+            .max(snapshot.cache_creation_input_tokens);
+        if let Some(ref other_tools) = snapshot.server_tool_use {
+            let tools = self.server_tool_use.get_or_insert_with(Default::default);
+            tools.web_search_requests = tools
+                .web_search_requests
+                .max(other_tools.web_search_requests);
+            tools.web_fetch_requests = tools.web_fetch_requests.max(other_tools.web_fetch_requests);
+        }
+        if snapshot.service_tier.is_some() {
+            self.service_tier.clone_from(&snapshot.service_tier);
+        }
+    }
+}
+
+/// Running token usage of the turn in flight
+///
+/// Feed every message of a turn to [`observe`](Self::observe). Usage from
+/// `message_start`/`message_delta` stream events and assistant messages is
+/// summed per API response, keyed by message id so the CLI's one assistant
+/// message per content block is not double counted. When the `Result` arrives,
+/// its aggregate usage replaces the estimate; the next message starts a new turn.
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    /// Usage per API response of this turn: (message id, usage so far)
+    responses: Vec<(Option<String>, Usage)>,
+    /// Aggregate usage from the `Result` that ended the turn
+    result: Option<Usage>,
+    /// Whether the turn has ended with a `Result`
+    turn_finished: bool,
+}
+
+impl UsageTracker {
+    /// Create an empty tracker
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Account for a message of the current turn
+    pub fn observe(&mut self, message: &Message) {
+        if self.turn_finished {
+            self.reset();
+        }
+
+        match message {
+            Message::Result { usage, .. } => {
+                self.result.clone_from(usage);
+                self.turn_finished = true;
+            }
+            Message::Assistant {
+                message: content, ..
+            } => {
+                if let Some(ref usage) = content.usage {
+                    self.record(content.id.as_deref(), usage, false);
+                }
+            }
+            Message::StreamEvent { .. } => match message.stream_event() {
+                Some(StreamEvent::MessageStart { message }) => {
+                    if let Some(ref usage) = message.usage {
+                        self.record(message.id.as_deref(), usage, true);
+                    }
+                }
+                Some(StreamEvent::MessageDelta {
+                    usage: Some(ref usage),
+                    ..
+                }) => self.record(None, usage, false),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Token usage so far (the `Result` aggregate once the turn has finished)
+    #[must_use]
+    pub fn total(&self) -> Usage {
+        if let Some(ref result) = self.result {
+            return result.clone();
+        }
+        let mut total = Usage::default();
+        for (_, usage) in &self.responses {
+            total.add(usage);
+        }
+        total
+    }
+
+    /// Whether the last observed message was the `Result` ending the turn
+    #[must_use]
+    pub fn is_turn_finished(&self) -> bool {
+        self.turn_finished
+    }
+
+    /// Forget all usage and start a new turn
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Record usage for the response with `id` (`None` means the latest one)
+    fn record(&mut self, id: Option<&str>, usage: &Usage, starts_response: bool) {
+        let existing = match id {
+            Some(id) => self
+                .responses
+                .iter_mut()
+                .find(|(response_id, _)| response_id.as_deref() == Some(id)),
+            None if starts_response => None,
+            None => self.responses.last_mut(),
+        };
+        match existing {
+            Some((_, current)) => current.update(usage),
+            None => self.responses.push((id.map(String::from), usage.clone())),
+        }
+    }
+}
+
+// ============================================================================
+// Plan Usage (OAuth/Max)
+// ============================================================================
 
 /// Usage limit information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,3 +268,123 @@ impl UsageData {
         .fold(0.0, f64::max)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn stream_event(event: serde_json::Value) -> Message {
+        serde_json::from_value(json!({
+            "type": "stream_event",
+            "uuid": "u1",
+            "session_id": "s1",
+            "event": event
+        }))
+        .unwrap()
+    }
+
+    fn assistant(id: &str, usage: serde_json::Value) -> Message {
+        serde_json::from_value(json!({
+            "type": "assistant",
+            "message": {
+                "id": id,
+                "model": "claude-sonnet-4-5",
+                "content": [{"type": "text", "text": "hi"}],
+                "usage": usage
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_usage_deserializes_api_shape() {
+        let usage: Usage = serde_json::from_value(json!({
+            "input_tokens": 12,
+            "output_tokens": 34,
+            "cache_read_input_tokens": 100,
+            "cache_creation_input_tokens": null,
+            "server_tool_use": {"web_search_requests": 2},
+            "service_tier": "standard",
+            "cache_creation": {"ephemeral_5m_input_tokens": 0}
+        }))
+        .unwrap();
+
+        assert_eq!(usage.input_tokens, 12);
+        assert_eq!(usage.output_tokens, 34);
+        assert_eq!(usage.cache_creation_input_tokens, 0);
+        assert_eq!(
+            usage.server_tool_use.as_ref().unwrap().web_search_requests,
+            2
+        );
+        assert_eq!(usage.service_tier.as_deref(), Some("standard"));
+        assert_eq!(usage.total_tokens(), 46);
+        assert_eq!(usage.effective_input_tokens(), 112);
+    }
+
+    #[test]
+    fn test_tracker_folds_stream_events_and_assistant_messages() {
+        let mut tracker = UsageTracker::new();
+
+        tracker.observe(&stream_event(json!({
+            "type": "message_start",
+            "message": {"id": "msg_1", "usage": {"input_tokens": 10, "output_tokens": 1}}
+        })));
+        tracker.observe(&stream_event(json!({
+            "type": "message_delta",
+            "delta": {"stop_reason": "tool_use"},
+            "usage": {"output_tokens": 20}
+        })));
+        assert_eq!(tracker.total().input_tokens, 10);
+        assert_eq!(tracker.total().output_tokens, 20);
+
+        // Assistant messages for the same response are not counted twice
+        tracker.observe(&assistant(
+            "msg_1",
+            json!({"input_tokens": 10, "output_tokens": 20}),
+        ));
+        tracker.observe(&assistant(
+            "msg_1",
+            json!({"input_tokens": 10, "output_tokens": 20}),
+        ));
+        assert_eq!(tracker.total().output_tokens, 20);
+
+        // A second API response in the same turn adds up
+        tracker.observe(&assistant(
+            "msg_2",
+            json!({"input_tokens": 30, "output_tokens": 5}),
+        ));
+        let total = tracker.total();
+        assert_eq!(total.input_tokens, 40);
+        assert_eq!(total.output_tokens, 25);
+        assert!(!tracker.is_turn_finished());
+    }
+
+    #[test]
+    fn test_tracker_uses_result_aggregate_and_resets() {
+        let mut tracker = UsageTracker::new();
+        tracker.observe(&assistant("msg_1", json!({"input_tokens": 10})));
+
+        let result: Message = serde_json::from_value(json!({
+            "type": "result",
+            "subtype": "success",
+            "duration_ms": 1,
+            "duration_api_ms": 1,
+            "is_error": false,
+            "num_turns": 1,
+            "session_id": "s1",
+            "usage": {"input_tokens": 11, "output_tokens": 7}
+        }))
+        .unwrap();
+        tracker.observe(&result);
+        assert!(tracker.is_turn_finished());
+        assert_eq!(tracker.total().input_tokens, 11);
+        assert_eq!(tracker.total().output_tokens, 7);
+
+        // The next message starts a new turn
+        tracker.observe(&assistant("msg_3", json!({"input_tokens": 4})));
+        assert!(!tracker.is_turn_finished());
+        assert_eq!(tracker.total().input_tokens, 4);
+        assert_eq!(tracker.total().output_tokens, 0);
+    }
+}
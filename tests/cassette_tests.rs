@@ -0,0 +1,200 @@
+//! Record/replay round-trip tests for cassette transports
+//!
+//! A session is recorded over `MockTransport` and then replayed through a
+//! fresh client, which must see the same messages and fire the same hooks.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anthropic_agent_sdk::testing::{self, MockControlReply, MockTransport};
+use anthropic_agent_sdk::transport::cassette::{CassetteDirection, CassetteEntry};
+use anthropic_agent_sdk::types::{ClaudeAgentOptions, HookEvent, HookOutput, Message};
+use anthropic_agent_sdk::{
+    ClaudeSDKClient, HookManager, HookMatcherBuilder, RecordingTransport, ReplaySpeed,
+    ReplayTransport, Transport,
+};
+use tokio::time::timeout;
+
+async fn collect_turn(client: &mut ClaudeSDKClient) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while let Ok(Some(msg)) = timeout(Duration::from_secs(5), client.next_message()).await {
+        let msg = msg.expect("unexpected error in message stream");
+        let is_result = matches!(msg, Message::Result { .. });
+        messages.push(msg);
+        if is_result {
+            break;
+        }
+    }
+    messages
+}
+
+fn counting_options(counter: Arc<AtomicUsize>) -> ClaudeAgentOptions {
+    let hook = HookManager::callback(move |_input, _tool_name, _ctx| {
+        let counter = counter.clone();
+        async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(HookOutput::default())
+        }
+    });
+    let mut hooks = HashMap::new();
+    hooks.insert(
+        HookEvent::PreToolUse,
+        vec![HookMatcherBuilder::new(Some("Bash")).add_hook(hook).build()],
+    );
+    ClaudeAgentOptions::builder().hooks(hooks).build()
+}
+
+#[tokio::test]
+async fn test_record_then_replay() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.jsonl");
+
+    // Record a scripted session, including a PreToolUse hook callback
+    let mock = MockTransport::new()
+        .wait_for_control_request("initialize")
+        .emit(testing::system_init("rec-1"))
+        .wait_for_user_message()
+        .emit(testing::tool_use(
+            "rec-1",
+            "tool_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+        ))
+        .emit(testing::hook_callback(
+            "cli_1",
+            "hook_0",
+            &serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "tool_name": "Bash",
+                "tool_input": {"command": "ls"}
+            }),
+        ))
+        .wait_for_control_response("cli_1")
+        .emit(testing::tool_result("rec-1", "tool_1", "file.txt", false))
+        .emit(testing::result("rec-1", "done"));
+    let recording_transport = RecordingTransport::new(mock, &path).unwrap();
+
+    let recorded_hooks = Arc::new(AtomicUsize::new(0));
+    let mut client = ClaudeSDKClient::with_transport(
+        recording_transport,
+        counting_options(recorded_hooks.clone()),
+    )
+    .await
+    .unwrap();
+    client.send_message("List files").await.unwrap();
+    let recorded = collect_turn(&mut client).await;
+    client.close().await.unwrap();
+
+    let entries = CassetteEntry::load(&path).unwrap();
+    assert!(
+        entries
+            .iter()
+            .any(|e| e.direction == CassetteDirection::Stdin && e.message_type() == Some("user"))
+    );
+    // init ack, system init, tool_use, hook_callback, tool_result, result
+    assert_eq!(
+        entries
+            .iter()
+            .filter(|e| e.direction == CassetteDirection::Stdout)
+            .count(),
+        6
+    );
+    assert!(entries.windows(2).all(|w| w[0].offset_ms <= w[1].offset_ms));
+
+    // Replay it through a fresh client
+    let replay = ReplayTransport::from_file(&path)
+        .unwrap()
+        .speed(ReplaySpeed::Instant);
+    let writes = replay.writes();
+    let replayed_hooks = Arc::new(AtomicUsize::new(0));
+    let mut client =
+        ClaudeSDKClient::with_transport(replay, counting_options(replayed_hooks.clone()))
+            .await
+            .unwrap();
+    client.send_message("List files").await.unwrap();
+    let replayed = collect_turn(&mut client).await;
+
+    assert_eq!(replayed.len(), recorded.len());
+    assert_eq!(client.get_session_id().unwrap().as_str(), "rec-1");
+    assert_eq!(recorded_hooks.load(Ordering::SeqCst), 1);
+    assert_eq!(replayed_hooks.load(Ordering::SeqCst), 1);
+    let written = writes.json();
+    let prompt = written.iter().find(|v| v["type"] == "user").unwrap();
+    assert_eq!(prompt["message"]["content"], "List files");
+    // The replayed hook_callback was answered by the new client
+    assert!(
+        written
+            .iter()
+            .any(|v| v["type"] == "control_response" && v["response"]["request_id"] == "cli_1")
+    );
+    client.close().await.unwrap();
+}
+
+/// Transport that returns from `write` only after the reply has had time to arrive
+struct SlowWrite<T>(T);
+
+#[async_trait::async_trait]
+impl<T: Transport> Transport for SlowWrite<T> {
+    async fn connect(&mut self) -> anthropic_agent_sdk::Result<()> {
+        self.0.connect().await
+    }
+
+    async fn write(&mut self, data: &str) -> anthropic_agent_sdk::Result<()> {
+        self.0.write(data).await?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok(())
+    }
+
+    async fn end_input(&mut self) -> anthropic_agent_sdk::Result<()> {
+        self.0.end_input().await
+    }
+
+    fn read_messages(
+        &mut self,
+    ) -> tokio::sync::mpsc::UnboundedReceiver<anthropic_agent_sdk::Result<serde_json::Value>> {
+        self.0.read_messages()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.0.is_ready()
+    }
+
+    async fn close(&mut self) -> anthropic_agent_sdk::Result<()> {
+        self.0.close().await
+    }
+}
+
+#[tokio::test]
+async fn test_requests_are_recorded_before_their_replies() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.jsonl");
+
+    // The mock answers control requests from inside `write`
+    let mock = MockTransport::new()
+        .emit(testing::system_init("rec-2"))
+        .control_reply("interrupt", MockControlReply::Success(None));
+    let recording_transport = RecordingTransport::new(SlowWrite(mock), &path).unwrap();
+    let mut client =
+        ClaudeSDKClient::with_transport(recording_transport, ClaudeAgentOptions::default())
+            .await
+            .unwrap();
+    client.interrupt().await.unwrap();
+    client.close().await.unwrap();
+
+    let entries = CassetteEntry::load(&path).unwrap();
+    let request = entries
+        .iter()
+        .position(|e| {
+            e.direction == CassetteDirection::Stdin && e.message_type() == Some("control_request")
+        })
+        .unwrap();
+    let reply = entries
+        .iter()
+        .position(|e| {
+            e.direction == CassetteDirection::Stdout && e.message_type() == Some("control_response")
+        })
+        .unwrap();
+    assert!(request < reply, "reply recorded before its request");
+}
@@ -3,7 +3,9 @@
 use anthropic_agent_sdk::control::{
     ControlMessage, ControlRequest, ControlResponse, InitResponse, ProtocolHandler,
 };
-use anthropic_agent_sdk::types::{HookEvent, PermissionRequest, RequestId, ToolName};
+use anthropic_agent_sdk::types::{
+    HookEvent, PermissionRequest, RequestId, ToolName, UserContentBlock, UserInput,
+};
 use tokio::sync::mpsc;
 
 #[tokio::test]
@@ -79,10 +81,17 @@ async fn test_send_message_request() {
     match request {
         ControlRequest::SendMessage { id, content } => {
             assert!(id.as_str().starts_with("req-"));
-            assert_eq!(content, "Hello!");
+            assert_eq!(content, UserInput::Text("Hello!".to_string()));
         }
         _ => panic!("Expected SendMessage request"),
     }
+
+    // Content blocks are serialized as an array
+    let request =
+        handler.create_send_message_request(vec![UserContentBlock::text("Describe this")]);
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["params"]["content"][0]["type"], "text");
+    assert_eq!(json["params"]["content"][0]["text"], "Describe this");
 }
 
 #[tokio::test]
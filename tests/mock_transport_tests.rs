@@ -0,0 +1,1212 @@
+//! End-to-end tests for `ClaudeSDKClient` using the scripted `MockTransport`
+//!
+//! These tests run the full client (reader/writer tasks, hooks, session
+//! tracking) against an in-memory transport, so they do not need the CLI.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anthropic_agent_sdk::command_hook::hooks_from_settings;
+use anthropic_agent_sdk::mcp::SdkMcpServer;
+use anthropic_agent_sdk::session::{FileSessionStore, SessionQuery, SessionRecord, SessionStore};
+use anthropic_agent_sdk::testing::{self, MockControlReply, MockTransport};
+use anthropic_agent_sdk::types::{
+    ClaudeAgentOptions, HookDecision, HookEvent, HookMatcher, HookOutput, Message, PermissionMode,
+    PermissionUpdate, PreToolUseHookInput, PreToolUseHookOutput,
+};
+use anthropic_agent_sdk::{
+    ClaudeError, ClaudeSDKClient, HookManager, HookMatcherBuilder, PermissionManager,
+    PermissionResult, PermissionResultAllow, PermissionResultDeny, SessionId,
+};
+use futures::StreamExt;
+use tokio::time::timeout;
+
+/// Read messages until a Result arrives, returning everything received
+async fn collect_turn(client: &mut ClaudeSDKClient) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while let Ok(Some(msg)) = timeout(Duration::from_secs(5), client.next_message()).await {
+        let msg = msg.expect("unexpected error in message stream");
+        let is_result = matches!(msg, Message::Result { .. });
+        messages.push(msg);
+        if is_result {
+            break;
+        }
+    }
+    messages
+}
+
+#[tokio::test]
+async fn test_basic_turn_with_mock() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(testing::assistant_text("mock-session", "4"))
+        .emit(testing::result("mock-session", "4"));
+    let handle = mock.handle();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    assert!(client.is_connected().await);
+
+    client.send_message("What is 2+2?").await.unwrap();
+    let messages = collect_turn(&mut client).await;
+
+    assert_eq!(messages.len(), 3);
+    assert!(matches!(messages[1], Message::Assistant { .. }));
+
+    // Session tracking and introspection come from the scripted init/result
+    assert_eq!(client.get_session_id().unwrap().as_str(), "mock-session");
+    assert_eq!(client.bound_session().unwrap().as_str(), "mock-session");
+    assert_eq!(client.current_model().as_deref(), Some("claude-sonnet-4-5"));
+
+    // The SDK wrote exactly one user message with our prompt
+    let user_messages = handle.user_messages();
+    assert_eq!(user_messages.len(), 1);
+    assert_eq!(user_messages[0]["message"]["content"], "What is 2+2?");
+
+    client.close().await.unwrap();
+    assert!(!client.is_connected().await);
+}
+
+/// PreToolUse/PostToolUse hooks that count their invocations
+fn counting_tool_hooks() -> (
+    HashMap<HookEvent, Vec<HookMatcher>>,
+    Arc<AtomicUsize>,
+    Arc<AtomicUsize>,
+) {
+    let pre_calls = Arc::new(AtomicUsize::new(0));
+    let post_calls = Arc::new(AtomicUsize::new(0));
+
+    let pre = pre_calls.clone();
+    let pre_hook = HookManager::callback(move |input, tool_name, _ctx| {
+        let pre = pre.clone();
+        async move {
+            assert_eq!(tool_name.as_deref(), Some("Bash"));
+            assert_eq!(input["tool_input"]["command"], "ls");
+            pre.fetch_add(1, Ordering::SeqCst);
+            Ok(HookOutput::default())
+        }
+    });
+    let post = post_calls.clone();
+    let post_hook = HookManager::callback(move |input, _tool_name, _ctx| {
+        let post = post.clone();
+        async move {
+            assert_eq!(input["tool_use_id"], "tool_1");
+            post.fetch_add(1, Ordering::SeqCst);
+            Ok(HookOutput::default())
+        }
+    });
+
+    let mut hooks = HashMap::new();
+    hooks.insert(
+        HookEvent::PreToolUse,
+        vec![
+            HookMatcherBuilder::new(Some("Bash"))
+                .add_hook(pre_hook)
+                .build(),
+        ],
+    );
+    hooks.insert(
+        HookEvent::PostToolUse,
+        vec![
+            HookMatcherBuilder::new(Some("*"))
+                .add_hook(post_hook)
+                .build(),
+        ],
+    );
+    (hooks, pre_calls, post_calls)
+}
+
+#[tokio::test]
+async fn test_hooks_fall_back_to_messages_without_initialize() {
+    let (hooks, pre_calls, post_calls) = counting_tool_hooks();
+    let options = ClaudeAgentOptions::builder().hooks(hooks).build();
+
+    // A CLI that rejects the initialize handshake: hooks are derived from messages
+    let mock = MockTransport::new()
+        .control_reply("initialize", MockControlReply::Error("unsupported".into()))
+        .emit(testing::system_init("s1"))
+        .wait_for_user_message()
+        .emit(testing::tool_use(
+            "s1",
+            "tool_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+        ))
+        .emit(testing::tool_result("s1", "tool_1", "file.txt", false))
+        .emit(testing::result("s1", "done"));
+
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    client.send_message("List files").await.unwrap();
+    collect_turn(&mut client).await;
+
+    assert_eq!(pre_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(post_calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_unanswered_initialize_does_not_block_construction() {
+    let (hooks, pre_calls, _post_calls) = counting_tool_hooks();
+    let options = ClaudeAgentOptions::builder()
+        .hooks(hooks)
+        .control_request_timeout_secs(60)
+        .build();
+
+    // A transport that never answers initialize
+    let mock = MockTransport::new()
+        .wait_timeout(Duration::from_secs(120))
+        .control_reply("initialize", MockControlReply::NoReply)
+        .emit(testing::system_init("s1"))
+        .wait_for_user_message()
+        .emit(testing::tool_use(
+            "s1",
+            "tool_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+        ))
+        .emit(testing::result("s1", "done"));
+
+    let started = tokio::time::Instant::now();
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(60));
+
+    client.send_message("List files").await.unwrap();
+    collect_turn(&mut client).await;
+    assert_eq!(pre_calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_hooks_run_through_cli_hook_callbacks() {
+    let (hooks, pre_calls, post_calls) = counting_tool_hooks();
+    let options = ClaudeAgentOptions::builder().hooks(hooks).build();
+
+    let pre_input = serde_json::json!({
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "ls"},
+        "tool_use_id": "tool_1"
+    });
+    let post_input = serde_json::json!({
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "ls"},
+        "tool_response": "file.txt",
+        "tool_use_id": "tool_1"
+    });
+
+    // PreToolUse registers first, so its callback is hook_0
+    let mock = MockTransport::new()
+        .wait_for_control_request("initialize")
+        .emit(testing::system_init("s1"))
+        .wait_for_user_message()
+        .emit(testing::tool_use(
+            "s1",
+            "tool_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+        ))
+        .emit(testing::hook_callback("cli_1", "hook_0", &pre_input))
+        .wait_for_control_response("cli_1")
+        .emit(testing::tool_result("s1", "tool_1", "file.txt", false))
+        .emit(testing::hook_callback("cli_2", "hook_1", &post_input))
+        .wait_for_control_response("cli_2")
+        .emit(testing::result("s1", "done"));
+    let handle = mock.handle();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    client.send_message("List files").await.unwrap();
+    collect_turn(&mut client).await;
+
+    // Each hook ran exactly once: via the CLI callback, not again from messages
+    assert_eq!(pre_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(post_calls.load(Ordering::SeqCst), 1);
+
+    let init = handle.assert_control_request("initialize");
+    assert_eq!(
+        init["hooks"]["PreToolUse"][0]["hookCallbackIds"],
+        serde_json::json!(["hook_0"])
+    );
+    assert_eq!(init["hooks"]["PreToolUse"][0]["matcher"], "Bash");
+    assert_eq!(
+        init["hooks"]["PostToolUse"][0]["hookCallbackIds"],
+        serde_json::json!(["hook_1"])
+    );
+
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 2);
+    assert!(
+        responses
+            .iter()
+            .all(|r| r["response"]["subtype"] == "success")
+    );
+}
+
+#[tokio::test]
+async fn test_pre_tool_use_block_is_sent_to_cli() {
+    let block = HookManager::callback(|_input, _tool_name, _ctx| async move {
+        Ok(HookOutput {
+            decision: Some(HookDecision::Block),
+            system_message: Some("rm is not allowed".to_string()),
+            hook_specific_output: Some(serde_json::json!({
+                "hookEventName": "PreToolUse",
+                "permissionDecision": "deny"
+            })),
+            ..Default::default()
+        })
+    });
+    let mut hooks = HashMap::new();
+    hooks.insert(
+        HookEvent::PreToolUse,
+        vec![
+            HookMatcherBuilder::new(Some("Bash"))
+                .add_hook(block)
+                .build(),
+        ],
+    );
+    let options = ClaudeAgentOptions::builder().hooks(hooks).build();
+
+    let mock = MockTransport::new()
+        .wait_for_control_request("initialize")
+        .emit(testing::hook_callback(
+            "cli_1",
+            "hook_0",
+            &serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "tool_name": "Bash",
+                "tool_input": {"command": "rm -rf /"}
+            }),
+        ))
+        .wait_for_control_response("cli_1")
+        .emit(testing::hook_callback(
+            "cli_2",
+            "hook_missing",
+            &serde_json::json!({}),
+        ))
+        .wait_for_control_response("cli_2");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().len() < 2 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 2);
+
+    let blocked = &responses[0]["response"];
+    assert_eq!(blocked["request_id"], "cli_1");
+    assert_eq!(blocked["response"]["decision"], "block");
+    assert_eq!(blocked["response"]["systemMessage"], "rm is not allowed");
+    assert_eq!(
+        blocked["response"]["hookSpecificOutput"]["permissionDecision"],
+        "deny"
+    );
+
+    // Unknown callback IDs are answered with an error instead of hanging the CLI
+    assert_eq!(responses[1]["response"]["subtype"], "error");
+}
+
+#[tokio::test]
+async fn test_typed_pre_tool_use_hook_answers_cli() {
+    let mut hooks = HashMap::new();
+    hooks.insert(
+        HookEvent::PreToolUse,
+        vec![
+            HookMatcherBuilder::new(Some("Write"))
+                .add_typed_hook(|input: PreToolUseHookInput, _ctx| async move {
+                    let mut tool_input = input.tool_input;
+                    tool_input["file_path"] = serde_json::json!("/sandbox/out.txt");
+                    Ok(PreToolUseHookOutput::allow().with_updated_input(tool_input))
+                })
+                .build(),
+        ],
+    );
+    let options = ClaudeAgentOptions::builder().hooks(hooks).build();
+
+    let mock = MockTransport::new()
+        .wait_for_control_request("initialize")
+        .emit(testing::hook_callback(
+            "cli_1",
+            "hook_0",
+            &serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s1",
+                "transcript_path": "/tmp/s1.jsonl",
+                "cwd": "/work",
+                "tool_name": "Write",
+                "tool_input": {"file_path": "/etc/passwd", "content": "x"}
+            }),
+        ))
+        .wait_for_control_response("cli_1");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().is_empty() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    let output = &responses[0]["response"]["response"];
+    assert!(output.get("decision").is_none());
+    assert_eq!(
+        output["hookSpecificOutput"],
+        serde_json::json!({
+            "hookEventName": "PreToolUse",
+            "permissionDecision": "allow",
+            "updatedInput": {"file_path": "/sandbox/out.txt", "content": "x"}
+        })
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_settings_command_hook_answers_cli() {
+    let project = tempfile::tempdir().unwrap();
+    let settings = serde_json::json!({
+        "hooks": {
+            "PreToolUse": [{
+                "matcher": "Bash",
+                "hooks": [{"type": "command", "command": "echo 'No shell today' >&2; exit 2"}]
+            }]
+        }
+    });
+    let hooks = hooks_from_settings(&settings, Some(project.path())).unwrap();
+    let options = ClaudeAgentOptions::builder().hooks(hooks).build();
+
+    let mock = MockTransport::new()
+        .wait_for_control_request("initialize")
+        .emit(testing::hook_callback(
+            "cli_1",
+            "hook_0",
+            &serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s1",
+                "transcript_path": "/tmp/s1.jsonl",
+                "cwd": "/work",
+                "tool_name": "Bash",
+                "tool_input": {"command": "ls"}
+            }),
+        ))
+        .wait_for_control_response("cli_1");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while handle.control_responses().is_empty() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 1);
+
+    let hook = &responses[0]["response"]["response"];
+    assert_eq!(hook["decision"], "block");
+    assert_eq!(hook["hookSpecificOutput"]["permissionDecision"], "deny");
+    assert_eq!(
+        hook["hookSpecificOutput"]["permissionDecisionReason"],
+        "No shell today"
+    );
+}
+
+#[tokio::test]
+async fn test_interrupt_writes_control_request() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("s1"))
+        .expect_control_request("interrupt");
+    let handle = mock.handle();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    client.interrupt().await.unwrap();
+
+    let request = handle
+        .wait_for_control_request("interrupt", Duration::from_secs(2))
+        .await;
+    assert!(request.is_some());
+    handle.verify();
+}
+
+#[tokio::test]
+async fn test_runtime_setters_await_ack() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("s1"))
+        .control_reply(
+            "set_permission_mode",
+            MockControlReply::Success(Some(serde_json::json!({"mode": "acceptEdits"}))),
+        );
+    let handle = mock.handle();
+
+    let client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    client
+        .set_permission_mode(PermissionMode::AcceptEdits)
+        .await
+        .unwrap();
+    client.set_model("haiku").await.unwrap();
+    client.set_max_thinking_tokens(2048).await.unwrap();
+
+    assert_eq!(
+        client.get_runtime_permission_mode(),
+        Some(PermissionMode::AcceptEdits)
+    );
+    assert_eq!(client.get_runtime_model().as_deref(), Some("haiku"));
+    assert_eq!(client.get_runtime_max_thinking_tokens(), Some(2048));
+
+    let request = handle.assert_control_request("set_permission_mode");
+    assert_eq!(request["mode"], "acceptEdits");
+    assert_eq!(handle.assert_control_request("set_model")["model"], "haiku");
+}
+
+#[tokio::test]
+async fn test_rejected_control_request_is_an_error() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("s1"))
+        .control_reply("set_model", MockControlReply::Error("unknown model".into()));
+
+    let client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    match client.set_model("not-a-model").await {
+        Err(ClaudeError::ControlRequestFailed {
+            request_type,
+            message,
+        }) => {
+            assert_eq!(request_type, "set_model");
+            assert_eq!(message, "unknown model");
+        }
+        other => panic!("expected ControlRequestFailed, got {other:?}"),
+    }
+    // A rejected request does not update the local override
+    assert!(client.get_runtime_model().is_none());
+}
+
+#[tokio::test]
+async fn test_unacknowledged_control_request_times_out() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("s1"))
+        .control_reply("interrupt", MockControlReply::NoReply);
+    let options = ClaudeAgentOptions::builder()
+        .control_request_timeout_secs(1)
+        .build();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    match client.interrupt().await {
+        Err(ClaudeError::ControlTimeout {
+            timeout_secs,
+            request_type,
+        }) => {
+            assert_eq!(timeout_secs, 1);
+            assert_eq!(request_type, "interrupt");
+        }
+        other => panic!("expected ControlTimeout, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_process_failure_surfaces_as_error() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("s1"))
+        .fail(1, "boom");
+
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        client.next_message().await,
+        Some(Ok(Message::System { .. }))
+    ));
+    match client.next_message().await {
+        Some(Err(ClaudeError::Process {
+            exit_code, stderr, ..
+        })) => {
+            assert_eq!(exit_code, 1);
+            assert_eq!(stderr.as_deref(), Some("boom"));
+        }
+        other => panic!("expected process error, got {other:?}"),
+    }
+    assert!(client.next_message().await.is_none());
+}
+
+#[tokio::test]
+async fn test_connect_failure() {
+    let mock = MockTransport::new().fail_on_connect("no route");
+    let result = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default()).await;
+    assert!(matches!(result, Err(ClaudeError::Connection(_))));
+}
+
+#[tokio::test]
+async fn test_query_with_transport() {
+    let mock = MockTransport::new()
+        .wait_for_user_message()
+        .emit(testing::system_init("q1"))
+        .emit(testing::assistant_text("q1", "Paris"))
+        .emit(testing::result("q1", "Paris"));
+    let handle = mock.handle();
+
+    let stream = anthropic_agent_sdk::query_with_transport("Capital of France?", mock, None)
+        .await
+        .unwrap();
+    let messages: Vec<_> = Box::pin(stream).collect().await;
+
+    assert_eq!(messages.len(), 3);
+    assert!(messages.iter().all(Result::is_ok));
+    assert!(handle.input_ended());
+    assert_eq!(
+        handle.user_messages()[0]["message"]["content"],
+        "Capital of France?"
+    );
+}
+
+#[tokio::test]
+async fn test_query_with_transport_answers_permission_requests() {
+    let callback = PermissionManager::callback(|_tool_name, _input, _ctx| async move {
+        Ok(PermissionResult::Allow(PermissionResultAllow {
+            updated_input: None,
+            updated_permissions: None,
+        }))
+    });
+    let options = ClaudeAgentOptions::builder().can_use_tool(callback).build();
+
+    let mock = MockTransport::new()
+        .wait_for_user_message()
+        .emit(testing::system_init("q1"))
+        .emit(testing::can_use_tool(
+            "cli_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+            &serde_json::json!([]),
+        ))
+        .wait_for_control_response("cli_1")
+        .emit(testing::result("q1", "done"));
+    let handle = mock.handle();
+
+    let stream = anthropic_agent_sdk::query_with_transport("List files", mock, Some(options))
+        .await
+        .unwrap();
+    let messages: Vec<_> = Box::pin(stream).collect().await;
+
+    assert!(messages.iter().all(Result::is_ok));
+    assert!(matches!(messages.last(), Some(Ok(Message::Result { .. }))));
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["response"]["response"]["behavior"], "allow");
+    assert!(handle.input_ended());
+}
+
+#[tokio::test]
+async fn test_concurrent_permission_requests_run_in_parallel() {
+    // Each callback waits for the other: serialized callbacks would deadlock
+    let barrier = Arc::new(tokio::sync::Barrier::new(2));
+    let callback = PermissionManager::callback(move |_tool_name, _input, _ctx| {
+        let barrier = barrier.clone();
+        async move {
+            barrier.wait().await;
+            Ok(PermissionResult::Allow(PermissionResultAllow {
+                updated_input: None,
+                updated_permissions: None,
+            }))
+        }
+    });
+    let options = ClaudeAgentOptions::builder().can_use_tool(callback).build();
+
+    let mock = MockTransport::new()
+        .emit(testing::can_use_tool(
+            "cli_1",
+            "Read",
+            &serde_json::json!({"file_path": "a.txt"}),
+            &serde_json::json!([]),
+        ))
+        .emit(testing::can_use_tool(
+            "cli_2",
+            "Read",
+            &serde_json::json!({"file_path": "b.txt"}),
+            &serde_json::json!([]),
+        ))
+        .wait_for_control_response("cli_1")
+        .wait_for_control_response("cli_2");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().len() < 2 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 2);
+    assert!(
+        responses
+            .iter()
+            .all(|r| r["response"]["response"]["behavior"] == "allow")
+    );
+}
+
+#[tokio::test]
+async fn test_can_use_tool_requests_are_answered() {
+    let seen_suggestions = Arc::new(AtomicUsize::new(0));
+    let seen = seen_suggestions.clone();
+    let callback = PermissionManager::callback(move |tool_name, input, ctx| {
+        let seen = seen.clone();
+        async move {
+            seen.fetch_add(ctx.suggestions.len(), Ordering::SeqCst);
+            if tool_name != "Bash" {
+                return Ok(PermissionResult::Allow(PermissionResultAllow {
+                    updated_input: None,
+                    updated_permissions: None,
+                }));
+            }
+            if input["command"] == "rm -rf /" {
+                return Ok(PermissionResult::Deny(PermissionResultDeny {
+                    message: "rm is not allowed".to_string(),
+                    interrupt: true,
+                }));
+            }
+            // Approve with a safer command and accept the CLI's suggestion
+            let accepted: Vec<PermissionUpdate> = ctx
+                .suggestions
+                .into_iter()
+                .filter(|s| matches!(s, PermissionUpdate::AddRules { .. }))
+                .collect();
+            Ok(PermissionResult::Allow(PermissionResultAllow {
+                updated_input: Some(serde_json::json!({"command": "ls -la"})),
+                updated_permissions: Some(accepted),
+            }))
+        }
+    });
+    let options = ClaudeAgentOptions::builder()
+        .can_use_tool(callback)
+        .allowed_tools(vec!["Read".into()])
+        .build();
+
+    let suggestions = serde_json::json!([
+        {
+            "type": "addRules",
+            "rules": [{"toolName": "Bash", "ruleContent": "ls"}],
+            "behavior": "allow",
+            "destination": "localSettings"
+        },
+        {"type": "setMode", "mode": "acceptEdits", "destination": "session"}
+    ]);
+    let mock = MockTransport::new()
+        .emit(testing::can_use_tool(
+            "cli_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+            &suggestions,
+        ))
+        .wait_for_control_response("cli_1")
+        .emit(testing::can_use_tool(
+            "cli_2",
+            "Bash",
+            &serde_json::json!({"command": "rm -rf /"}),
+            &serde_json::json!([]),
+        ))
+        .wait_for_control_response("cli_2")
+        // Not in allowed_tools, which must not turn into a deny list
+        .emit(testing::can_use_tool(
+            "cli_3",
+            "Write",
+            &serde_json::json!({"file_path": "a.txt"}),
+            &serde_json::json!([]),
+        ))
+        .wait_for_control_response("cli_3");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().len() < 3 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(seen_suggestions.load(Ordering::SeqCst), 2);
+
+    let allowed = &responses[0]["response"];
+    assert_eq!(allowed["request_id"], "cli_1");
+    assert_eq!(allowed["subtype"], "success");
+    assert_eq!(allowed["response"]["behavior"], "allow");
+    assert_eq!(allowed["response"]["updatedInput"]["command"], "ls -la");
+    let updates = &allowed["response"]["updatedPermissions"];
+    assert_eq!(updates.as_array().unwrap().len(), 1);
+    assert_eq!(updates[0]["type"], "addRules");
+    assert_eq!(updates[0]["behavior"], "allow");
+    assert_eq!(updates[0]["rules"][0]["toolName"], "Bash");
+
+    let denied = &responses[1]["response"];
+    assert_eq!(denied["request_id"], "cli_2");
+    assert_eq!(denied["response"]["behavior"], "deny");
+    assert_eq!(denied["response"]["message"], "rm is not allowed");
+    assert_eq!(denied["response"]["interrupt"], true);
+
+    // Unmodified input is echoed back as updatedInput
+    let passthrough = &responses[2]["response"];
+    assert_eq!(passthrough["request_id"], "cli_3");
+    assert_eq!(passthrough["response"]["behavior"], "allow");
+    assert_eq!(
+        passthrough["response"]["updatedInput"]["file_path"],
+        "a.txt"
+    );
+}
+
+#[tokio::test]
+async fn test_can_use_tool_without_callback_is_an_error_response() {
+    let mock = MockTransport::new()
+        .emit(testing::can_use_tool(
+            "cli_1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+            &serde_json::json!([]),
+        ))
+        .wait_for_control_response("cli_1");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().is_empty() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["response"]["subtype"], "error");
+    assert_eq!(responses[0]["response"]["request_id"], "cli_1");
+}
+
+/// Minimal in-process MCP server exposing a single `add` tool
+struct AddServer {
+    notifications: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl SdkMcpServer for AddServer {
+    async fn handle_message(
+        &self,
+        message: serde_json::Value,
+    ) -> anthropic_agent_sdk::Result<Option<serde_json::Value>> {
+        let Some(id) = message.get("id").cloned() else {
+            self.notifications.fetch_add(1, Ordering::SeqCst);
+            return Ok(None);
+        };
+        match message["method"].as_str() {
+            Some("tools/call") => {
+                let args = &message["params"]["arguments"];
+                let sum = args["a"].as_i64().unwrap_or(0) + args["b"].as_i64().unwrap_or(0);
+                Ok(Some(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": {"content": [{"type": "text", "text": sum.to_string()}]}
+                })))
+            }
+            _ => Err(ClaudeError::mcp("unsupported method")),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_mcp_messages_are_routed_to_sdk_servers() {
+    let server = Arc::new(AddServer {
+        notifications: AtomicUsize::new(0),
+    });
+    let mut servers: HashMap<String, Arc<dyn SdkMcpServer>> = HashMap::new();
+    servers.insert("calc".to_string(), server.clone());
+    let options = ClaudeAgentOptions::builder()
+        .sdk_mcp_servers(servers)
+        .build();
+
+    let mock = MockTransport::new()
+        .emit(testing::mcp_message(
+            "cli_1",
+            "calc",
+            &serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        ))
+        .wait_for_control_response("cli_1")
+        .emit(testing::mcp_message(
+            "cli_2",
+            "calc",
+            &serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": "add", "arguments": {"a": 2, "b": 40}}
+            }),
+        ))
+        .wait_for_control_response("cli_2")
+        .emit(testing::mcp_message(
+            "cli_3",
+            "calc",
+            &serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}),
+        ))
+        .wait_for_control_response("cli_3")
+        .emit(testing::mcp_message(
+            "cli_4",
+            "missing",
+            &serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "tools/list"}),
+        ))
+        .wait_for_control_response("cli_4");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().len() < 4 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 4);
+    assert_eq!(server.notifications.load(Ordering::SeqCst), 1);
+
+    // Notification: empty success
+    assert_eq!(responses[0]["response"]["subtype"], "success");
+    assert!(
+        responses[0]["response"]["response"]
+            .get("mcp_response")
+            .is_none()
+    );
+
+    let call = &responses[1]["response"]["response"]["mcp_response"];
+    assert_eq!(call["id"], 2);
+    assert_eq!(call["result"]["content"][0]["text"], "42");
+
+    // Server errors and unknown servers become JSON-RPC errors
+    let failed = &responses[2]["response"]["response"]["mcp_response"];
+    assert_eq!(failed["id"], 3);
+    assert_eq!(failed["error"]["code"], -32603);
+    let missing = &responses[3]["response"]["response"]["mcp_response"];
+    assert_eq!(missing["id"], 4);
+    assert_eq!(missing["error"]["code"], -32601);
+}
+
+#[tokio::test]
+async fn test_current_usage_tracks_turn_in_flight() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("usage-session"))
+        .wait_for_user_message()
+        .emit(testing::stream_event(
+            "usage-session",
+            &serde_json::json!({
+                "type": "message_start",
+                "message": {"id": "msg_1", "usage": {"input_tokens": 100, "output_tokens": 1}}
+            }),
+        ))
+        .emit(testing::stream_event(
+            "usage-session",
+            &serde_json::json!({
+                "type": "message_delta",
+                "delta": {"stop_reason": "end_turn"},
+                "usage": {"output_tokens": 42}
+            }),
+        ))
+        .emit(serde_json::json!({
+            "type": "result",
+            "subtype": "success",
+            "duration_ms": 100,
+            "duration_api_ms": 80,
+            "is_error": false,
+            "num_turns": 1,
+            "session_id": "usage-session",
+            "usage": {
+                "input_tokens": 100,
+                "output_tokens": 42,
+                "cache_read_input_tokens": 500,
+                "service_tier": "standard"
+            }
+        }));
+
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    client.send_message("Count tokens").await.unwrap();
+
+    let mut seen = Vec::new();
+    while let Ok(Some(msg)) = timeout(Duration::from_secs(5), client.next_message()).await {
+        let msg = msg.unwrap();
+        seen.push(client.current_usage());
+        if let Message::Result { usage, .. } = msg {
+            let usage = usage.expect("typed usage on result");
+            assert_eq!(usage.cache_read_input_tokens, 500);
+            assert_eq!(usage.service_tier.as_deref(), Some("standard"));
+            break;
+        }
+    }
+
+    // init, message_start, message_delta, result
+    assert_eq!(seen.len(), 4);
+    assert_eq!(seen[0].total_tokens(), 0);
+    assert_eq!((seen[1].input_tokens, seen[1].output_tokens), (100, 1));
+    assert_eq!((seen[2].input_tokens, seen[2].output_tokens), (100, 42));
+    assert_eq!(seen[3].effective_input_tokens(), 600);
+}
+
+#[tokio::test]
+async fn test_send_content_writes_blocks_after_validation() {
+    use anthropic_agent_sdk::types::UserContentBlock;
+
+    let mock = MockTransport::new()
+        .emit(testing::system_init("content-session"))
+        .wait_for_user_message()
+        .emit(testing::assistant_text("content-session", "A login form"))
+        .emit(testing::result("content-session", "A login form"));
+    let handle = mock.handle();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+
+    // Invalid blocks are rejected before anything reaches stdin
+    let err = client
+        .send_content(vec![UserContentBlock::image_base64(
+            "image/png",
+            "aGVsbG8=",
+        )])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClaudeError::InvalidContent(_)));
+    assert!(handle.user_messages().is_empty());
+
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    client
+        .send_content(vec![
+            UserContentBlock::text("What is on this screen?"),
+            UserContentBlock::image_bytes(png).unwrap(),
+            UserContentBlock::pdf_bytes(b"%PDF-1.7\n").unwrap(),
+        ])
+        .await
+        .unwrap();
+    let messages = collect_turn(&mut client).await;
+    assert!(matches!(messages.last(), Some(Message::Result { .. })));
+
+    let user_messages = handle.user_messages();
+    assert_eq!(user_messages.len(), 1);
+    let content = &user_messages[0]["message"]["content"];
+    assert_eq!(content[0]["text"], "What is on this screen?");
+    assert_eq!(content[1]["type"], "image");
+    assert_eq!(content[1]["source"]["media_type"], "image/png");
+    assert_eq!(content[2]["type"], "document");
+    assert_eq!(content[2]["source"]["media_type"], "application/pdf");
+}
+
+#[tokio::test]
+async fn test_unknown_message_types_do_not_break_the_stream() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(serde_json::json!({"type": "brand_new_message", "session_id": "mock-session"}))
+        .emit(testing::assistant_text("mock-session", "still here"))
+        .emit(testing::result("mock-session", "still here"));
+
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    client.send_message("Hello").await.unwrap();
+    let messages = collect_turn(&mut client).await;
+
+    assert_eq!(messages.len(), 4);
+    match &messages[1] {
+        Message::Unknown { raw } => assert_eq!(raw["type"], "brand_new_message"),
+        other => panic!("unexpected message {other:?}"),
+    }
+    assert!(matches!(messages[2], Message::Assistant { .. }));
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_session_store_records_the_conversation() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(FileSessionStore::new(dir.path()));
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(testing::tool_use(
+            "mock-session",
+            "tool-1",
+            "Bash",
+            &serde_json::json!({"command": "ls"}),
+        ))
+        .emit(testing::tool_result(
+            "mock-session",
+            "tool-1",
+            "a.txt",
+            false,
+        ))
+        .emit(testing::assistant_text("mock-session", "One file"))
+        .emit(testing::result("mock-session", "One file"));
+
+    let options = ClaudeAgentOptions::builder()
+        .user("tenant-1")
+        .session_store(store.clone())
+        .build();
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    client.send_message("List files").await.unwrap();
+    collect_turn(&mut client).await;
+    client.close().await.unwrap();
+
+    let session_id = SessionId::new("mock-session");
+    let record = store.get_session(&session_id).await.unwrap().unwrap();
+    assert_eq!(record.user.as_deref(), Some("tenant-1"));
+    assert_eq!(record.model.as_deref(), Some("claude-sonnet-4-5"));
+    assert_eq!(record.message_count, 5);
+    assert_eq!(record.turns, 1);
+    assert_eq!(record.tool_use_count("Bash"), 1);
+    assert_eq!(store.messages(&session_id).await.unwrap().len(), 5);
+
+    let found = store
+        .find_sessions(&SessionQuery {
+            user: Some("tenant-1".to_string()),
+            tool: Some("Bash".to_string()),
+            ..SessionQuery::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+}
+
+/// Session store whose writes wait for a gate and that counts record saves
+struct GatedStore {
+    inner: FileSessionStore,
+    gate: Arc<tokio::sync::Semaphore>,
+    saves: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl SessionStore for GatedStore {
+    async fn save_session(&self, record: &SessionRecord) -> anthropic_agent_sdk::Result<()> {
+        self.saves.fetch_add(1, Ordering::SeqCst);
+        self.inner.save_session(record).await
+    }
+
+    async fn append_message(
+        &self,
+        session_id: &SessionId,
+        message: &Message,
+    ) -> anthropic_agent_sdk::Result<()> {
+        let _permit = self.gate.acquire().await.unwrap();
+        self.inner.append_message(session_id, message).await
+    }
+
+    async fn get_session(
+        &self,
+        session_id: &SessionId,
+    ) -> anthropic_agent_sdk::Result<Option<SessionRecord>> {
+        self.inner.get_session(session_id).await
+    }
+
+    async fn messages(&self, session_id: &SessionId) -> anthropic_agent_sdk::Result<Vec<Message>> {
+        self.inner.messages(session_id).await
+    }
+
+    async fn find_sessions(
+        &self,
+        query: &SessionQuery,
+    ) -> anthropic_agent_sdk::Result<Vec<SessionRecord>> {
+        self.inner.find_sessions(query).await
+    }
+
+    async fn delete_session(&self, session_id: &SessionId) -> anthropic_agent_sdk::Result<bool> {
+        self.inner.delete_session(session_id).await
+    }
+}
+
+#[tokio::test]
+async fn test_slow_session_store_does_not_stall_messages() {
+    let dir = tempfile::tempdir().unwrap();
+    let gate = Arc::new(tokio::sync::Semaphore::new(0));
+    let store = Arc::new(GatedStore {
+        inner: FileSessionStore::new(dir.path()),
+        gate: gate.clone(),
+        saves: AtomicUsize::new(0),
+    });
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(testing::assistant_text("mock-session", "Hi"))
+        .emit(testing::result("mock-session", "Hi"));
+
+    let options = ClaudeAgentOptions::builder()
+        .session_store(store.clone())
+        .build();
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    client.send_message("Hello").await.unwrap();
+
+    // The store is blocked, yet the whole turn is delivered
+    let messages = timeout(Duration::from_secs(2), collect_turn(&mut client))
+        .await
+        .unwrap();
+    assert!(matches!(messages.last(), Some(Message::Result { .. })));
+    assert_eq!(store.saves.load(Ordering::SeqCst), 0);
+
+    gate.add_permits(100);
+    client.close().await.unwrap();
+
+    // Saved once, at the result
+    assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+    let record = store
+        .get_session(&SessionId::new("mock-session"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.message_count, 3);
+}
+
+#[tokio::test]
+async fn test_close_does_not_wait_forever_for_a_blocked_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(GatedStore {
+        inner: FileSessionStore::new(dir.path()),
+        gate: Arc::new(tokio::sync::Semaphore::new(0)),
+        saves: AtomicUsize::new(0),
+    });
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .emit(testing::result("mock-session", "Hi"));
+
+    let options = ClaudeAgentOptions::builder()
+        .session_store(store.clone())
+        .build();
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+    collect_turn(&mut client).await;
+
+    // The gate never opens, so the recording cannot finish
+    timeout(Duration::from_secs(10), client.close())
+        .await
+        .expect("close() waited for the blocked store")
+        .unwrap();
+    assert_eq!(store.saves.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,80 @@
+//! End-to-end tests for `PolicyEngine` hooks and permissions using the
+//! scripted `MockTransport`
+
+use std::time::Duration;
+
+use anthropic_agent_sdk::ClaudeSDKClient;
+use anthropic_agent_sdk::policy::{Policy, PolicyEngine};
+use anthropic_agent_sdk::testing::{self, MockTransport};
+use anthropic_agent_sdk::types::ClaudeAgentOptions;
+
+#[tokio::test]
+async fn test_policy_engine_answers_hooks_and_permissions() {
+    let policy = Policy::from_toml_str(
+        r#"
+        [[rules]]
+        tool = "Bash"
+        when = [{ pointer = "/tool_input/command", matches = "rm\\s+-rf" }]
+        action = "block"
+        message = "Recursive deletes are not allowed"
+
+        [[permissions]]
+        tool = "Write"
+        decision = "deny"
+        message = "Read-only session"
+        "#,
+    )
+    .unwrap();
+    let engine = PolicyEngine::new(&policy).unwrap();
+    let options = ClaudeAgentOptions::builder()
+        .hooks(engine.hooks())
+        .can_use_tool(engine.can_use_tool())
+        .build();
+
+    let mock = MockTransport::new()
+        .wait_for_control_request("initialize")
+        .emit(testing::hook_callback(
+            "cli_1",
+            "hook_0",
+            &serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s1",
+                "transcript_path": "/tmp/s1.jsonl",
+                "cwd": "/work",
+                "tool_name": "Bash",
+                "tool_input": {"command": "rm -rf build"}
+            }),
+        ))
+        .wait_for_control_response("cli_1")
+        .emit(testing::can_use_tool(
+            "cli_2",
+            "Write",
+            &serde_json::json!({"file_path": "a.txt"}),
+            &serde_json::json!([]),
+        ))
+        .wait_for_control_response("cli_2");
+    let handle = mock.handle();
+
+    let _client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while handle.control_responses().len() < 2 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let responses = handle.control_responses();
+    assert_eq!(responses.len(), 2);
+
+    let hook = &responses[0]["response"]["response"];
+    assert_eq!(hook["decision"], "block");
+    assert_eq!(hook["hookSpecificOutput"]["permissionDecision"], "deny");
+    assert_eq!(
+        hook["hookSpecificOutput"]["permissionDecisionReason"],
+        "Recursive deletes are not allowed"
+    );
+
+    let permission = &responses[1]["response"]["response"];
+    assert_eq!(permission["behavior"], "deny");
+    assert_eq!(permission["message"], "Read-only session");
+}
@@ -0,0 +1,213 @@
+//! End-to-end tests for `ask_typed` and `ask_structured` using the scripted
+//! `MockTransport`
+
+use anthropic_agent_sdk::structured_output::StructuredOutputPolicy;
+use anthropic_agent_sdk::testing::{self, MockTransport};
+use anthropic_agent_sdk::types::{ClaudeAgentOptions, OutputFormat};
+use anthropic_agent_sdk::{ClaudeError, ClaudeSDKClient};
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct Verdict {
+    approved: bool,
+    reasons: Vec<String>,
+}
+
+fn structured_result(session_id: &str, output: serde_json::Value) -> serde_json::Value {
+    let mut result = testing::result(session_id, "");
+    result["structured_output"] = output;
+    result
+}
+
+#[tokio::test]
+async fn test_ask_typed_deserializes_structured_output() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"approved": true, "reasons": ["tests pass"]}),
+        ))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"approved": "yes"}),
+        ));
+
+    let options = ClaudeAgentOptions::builder()
+        .output_format(OutputFormat::for_type::<Verdict>())
+        .build();
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    let verdict: Verdict = client.ask_typed("Review the change").await.unwrap();
+    assert!(verdict.approved);
+    assert_eq!(verdict.reasons, ["tests pass"]);
+
+    match client.ask_typed::<Verdict>("And this one?").await {
+        Err(ClaudeError::StructuredOutput { raw, .. }) => {
+            assert_eq!(raw, serde_json::json!({"approved": "yes"}));
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ask_typed_requires_output_format() {
+    let mock = MockTransport::new();
+    let mut client = ClaudeSDKClient::with_transport(mock, ClaudeAgentOptions::default())
+        .await
+        .unwrap();
+    assert!(matches!(
+        client.ask_typed::<Verdict>("Review").await,
+        Err(ClaudeError::InvalidConfig(_))
+    ));
+}
+
+fn score_options(max_attempts: u32) -> ClaudeAgentOptions {
+    ClaudeAgentOptions::builder()
+        .output_format(OutputFormat::json_schema(serde_json::json!({
+            "type": "object",
+            "properties": {"score": {"type": "integer", "minimum": 0, "maximum": 10}},
+            "required": ["score"]
+        })))
+        .structured_output_policy(StructuredOutputPolicy::new(max_attempts))
+        .build()
+}
+
+#[tokio::test]
+async fn test_ask_structured_repairs_invalid_output() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"score": 12}),
+        ))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"score": 8}),
+        ));
+    let handle = mock.handle();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, score_options(3))
+        .await
+        .unwrap();
+    let output = client.ask_structured("Rate the README").await.unwrap();
+    assert_eq!(output, serde_json::json!({"score": 8}));
+
+    let prompts = handle.user_messages();
+    assert_eq!(prompts.len(), 2);
+    let repair = prompts[1]["message"]["content"].as_str().unwrap();
+    assert!(repair.contains("$.score: 12 is greater than the maximum 10"));
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ask_structured_reports_every_attempt() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(testing::result("mock-session", "I cannot rate it"))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"score": "eight"}),
+        ));
+
+    let mut client = ClaudeSDKClient::with_transport(mock, score_options(2))
+        .await
+        .unwrap();
+    match client.ask_structured("Rate the README").await {
+        Err(ClaudeError::StructuredOutputInvalid { attempts }) => {
+            assert_eq!(attempts.len(), 2);
+            assert_eq!(attempts[0].raw, serde_json::json!("I cannot rate it"));
+            assert_eq!(attempts[1].raw, serde_json::json!({"score": "eight"}));
+            assert_eq!(attempts[1].violations[0].path, "$.score");
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+
+    client.close().await.unwrap();
+}
+
+/// Assistant message with a `tool_use` block missing its `input`
+fn malformed_assistant(session_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "assistant",
+        "session_id": session_id,
+        "message": {
+            "model": "claude-sonnet-4-5",
+            "content": [{"type": "tool_use", "id": "t1", "name": "Bash"}]
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_ask_typed_reads_the_whole_turn_after_a_bad_message() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(malformed_assistant("mock-session"))
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"approved": false, "reasons": ["first"]}),
+        ))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"approved": true, "reasons": ["second"]}),
+        ));
+
+    let options = ClaudeAgentOptions::builder()
+        .output_format(OutputFormat::for_type::<Verdict>())
+        .build();
+    let mut client = ClaudeSDKClient::with_transport(mock, options)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        client.ask_typed::<Verdict>("Review the change").await,
+        Err(ClaudeError::MessageParse { .. })
+    ));
+    // The first turn's result is not taken as the second turn's
+    let verdict: Verdict = client.ask_typed("And this one?").await.unwrap();
+    assert_eq!(verdict.reasons, ["second"]);
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ask_structured_treats_a_bad_message_as_a_violation() {
+    let mock = MockTransport::new()
+        .emit(testing::system_init("mock-session"))
+        .wait_for_user_message()
+        .emit(malformed_assistant("mock-session"))
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"score": 8}),
+        ))
+        .wait_for_user_message()
+        .emit(structured_result(
+            "mock-session",
+            serde_json::json!({"score": 9}),
+        ));
+    let handle = mock.handle();
+
+    let mut client = ClaudeSDKClient::with_transport(mock, score_options(2))
+        .await
+        .unwrap();
+    let output = client.ask_structured("Rate the README").await.unwrap();
+    assert_eq!(output, serde_json::json!({"score": 9}));
+
+    let prompts = handle.user_messages();
+    assert_eq!(prompts.len(), 2);
+    let repair = prompts[1]["message"]["content"].as_str().unwrap();
+    assert!(repair.contains("unexpected payload for type 'assistant'"));
+
+    client.close().await.unwrap();
+}